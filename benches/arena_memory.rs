@@ -0,0 +1,174 @@
+//! Heap usage of a normalization run over a large generated profile.
+//!
+//! A counting global allocator records the peak number of live heap bytes while
+//! `normalize_bookmarks` runs, excluding the input document built beforehand, then
+//! the peak for a whole run from a file: read into memory versus streamed.
+//! Run with `cargo bench --bench arena_memory`.
+
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::read_options::ReadOptions;
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::scc_kosaraju::KosarajuSccDetector;
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::serde_json_adapter::{
+    read_bookmarks_file, read_bookmarks_streaming, BookmarkNodeDto, BookmarksFileDto,
+};
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
+use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::{
+    normalize_bookmarks, normalize_bookmarks_with_report, normalize_loaded_with_report,
+    NormalizeOptions,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Folders per level and URLs per folder; three folder levels give ~56k nodes per root.
+const FANOUT: usize = 20;
+const URLS_PER_FOLDER: usize = 6;
+
+fn url(i: usize) -> BookmarkNodeDto {
+    BookmarkNodeDto {
+        node_type: "url".to_string(),
+        name: Some(format!("Page {i}")),
+        url: Some(format!("https://example.com/{i}")),
+        id: Some(i.to_string()),
+        ..BookmarkNodeDto::default()
+    }
+}
+
+fn folder(name: String, children: Vec<BookmarkNodeDto>) -> BookmarkNodeDto {
+    BookmarkNodeDto {
+        node_type: "folder".to_string(),
+        name: Some(name),
+        children,
+        ..BookmarkNodeDto::default()
+    }
+}
+
+/// Every folder name is unique, so the run measures one build, dedup and rebuild pass.
+fn profile() -> (BookmarksFileDto, usize) {
+    let mut next = 0usize;
+    let mut tree = |prefix: &str| -> Vec<BookmarkNodeDto> {
+        let mut out = Vec::new();
+        for a in 0..FANOUT {
+            let mut level2 = Vec::new();
+            for b in 0..FANOUT {
+                let mut level3 = Vec::new();
+                for c in 0..FANOUT {
+                    let urls = (0..URLS_PER_FOLDER)
+                        .map(|_| {
+                            next += 1;
+                            url(next)
+                        })
+                        .collect();
+                    level3.push(folder(format!("{prefix} {a}.{b}.{c}"), urls));
+                }
+                level2.push(folder(format!("{prefix} {a}.{b}"), level3));
+            }
+            out.push(folder(format!("{prefix} {a}"), level2));
+        }
+        out
+    };
+
+    let mut roots = BTreeMap::new();
+    for key in ["bookmark_bar", "other", "synced"] {
+        roots.insert(key.to_string(), folder(key.to_string(), tree(key)));
+    }
+    let folders = 3 * (FANOUT + FANOUT * FANOUT + FANOUT * FANOUT * FANOUT);
+    let dto = BookmarksFileDto {
+        checksum: None,
+        roots,
+        version: Some(1),
+        extra: BTreeMap::new(),
+    };
+    (dto, folders + next + 3)
+}
+
+fn mb(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Peak live heap while `run` executes, above what was live when it started.
+fn measure(run: impl FnOnce()) -> usize {
+    let baseline = LIVE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    run();
+    PEAK.load(Ordering::Relaxed) - baseline
+}
+
+fn main() {
+    let rt = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let (input, nodes) = profile();
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("Bookmarks");
+    std::fs::write(&path, serde_json::to_vec(&input).expect("serialize")).expect("write");
+    let path = path.to_str().expect("utf-8 path").to_string();
+
+    let baseline = LIVE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let (_out, _stats) = rt
+        .block_on(normalize_bookmarks(
+            input,
+            &DefaultUrlCanonicalizer,
+            &KosarajuSccDetector,
+            None,
+        ))
+        .expect("normalize");
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+
+    println!("nodes:          {nodes}");
+    println!("peak heap (MB): {:.1}", mb(peak));
+    println!("bytes per node: {}", peak / nodes);
+
+    let options = NormalizeOptions::default();
+    let in_memory = measure(|| {
+        rt.block_on(async {
+            let dto = read_bookmarks_file(&path).await.expect("read");
+            normalize_bookmarks_with_report(
+                dto,
+                &DefaultUrlCanonicalizer,
+                &KosarajuSccDetector,
+                &options,
+                None,
+            )
+            .await
+            .expect("normalize");
+        })
+    });
+    let streamed = measure(|| {
+        let loaded = read_bookmarks_streaming(&path, &ReadOptions::default()).expect("stream");
+        rt.block_on(normalize_loaded_with_report(
+            loaded,
+            &DefaultUrlCanonicalizer,
+            &KosarajuSccDetector,
+            &options,
+            None,
+        ))
+        .expect("normalize");
+    });
+
+    println!("from file, read whole (MB): {:.1}", mb(in_memory));
+    println!("from file, streamed (MB):   {:.1}", mb(streamed));
+}
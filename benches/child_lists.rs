@@ -0,0 +1,96 @@
+//! Synthetic benchmark for wide folders: `n` same-named folders holding duplicate URLs plus
+//! `n` empty unnamed siblings, all under one root. Folder merge, URL dedup and prune each
+//! remove thousands of entries from a single child list.
+//!
+//! Run with `cargo bench --bench child_lists`. Time should roughly double with `n`; a
+//! quadratic child-list update shows up as a ratio near 4.
+
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::scc_kosaraju::KosarajuSccDetector;
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::serde_json_adapter::{
+    BookmarkNodeDto, BookmarksFileDto,
+};
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
+use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::normalize_bookmarks;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+const SIZES: [usize; 4] = [2_000, 4_000, 8_000, 16_000];
+const RUNS: usize = 3;
+
+fn url(i: usize) -> BookmarkNodeDto {
+    BookmarkNodeDto {
+        node_type: "url".to_string(),
+        name: Some(format!("Page {i}")),
+        url: Some(format!("https://example.com/{i}")),
+        id: Some(i.to_string()),
+        ..BookmarkNodeDto::default()
+    }
+}
+
+fn folder(name: Option<&str>, children: Vec<BookmarkNodeDto>) -> BookmarkNodeDto {
+    BookmarkNodeDto {
+        node_type: "folder".to_string(),
+        name: name.map(str::to_string),
+        children,
+        ..BookmarkNodeDto::default()
+    }
+}
+
+/// After the merge the surviving `Dup` folder holds `2n` URLs with `n` distinct keys.
+fn synthetic(n: usize) -> BookmarksFileDto {
+    let mut children = Vec::with_capacity(2 * n);
+    for i in 0..n {
+        children.push(folder(Some("Dup"), vec![url(i), url(i)]));
+    }
+    for _ in 0..n {
+        children.push(folder(None, vec![]));
+    }
+
+    let mut roots = BTreeMap::new();
+    roots.insert(
+        "bookmark_bar".to_string(),
+        folder(Some("Bookmarks bar"), children),
+    );
+    BookmarksFileDto {
+        checksum: None,
+        roots,
+        version: Some(1),
+        extra: BTreeMap::new(),
+    }
+}
+
+fn run_once(rt: &tokio::runtime::Runtime, n: usize) -> Duration {
+    let input = synthetic(n);
+    let start = Instant::now();
+    let (_out, stats) = rt
+        .block_on(normalize_bookmarks(
+            input,
+            &DefaultUrlCanonicalizer,
+            &KosarajuSccDetector,
+            None,
+        ))
+        .expect("normalize");
+    let elapsed = start.elapsed();
+    assert_eq!(stats.folders_merged, n - 1);
+    assert_eq!(stats.urls_deduped, n);
+    assert_eq!(stats.folders_pruned, n);
+    elapsed
+}
+
+fn main() {
+    let rt = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let mut previous: Option<Duration> = None;
+
+    println!("{:>8} {:>12} {:>8}", "n", "best (ms)", "ratio");
+    for n in SIZES {
+        let best = (0..RUNS)
+            .map(|_| run_once(&rt, n))
+            .min()
+            .expect("at least one run");
+        let ratio = previous
+            .map(|p| format!("{:.2}", best.as_secs_f64() / p.as_secs_f64()))
+            .unwrap_or_else(|| "-".to_string());
+        println!("{n:>8} {:>12.1} {ratio:>8}", best.as_secs_f64() * 1e3);
+        previous = Some(best);
+    }
+}
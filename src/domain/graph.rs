@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::fmt;
+
 #[derive(Debug, Clone, Default)]
 pub struct Graph {
     pub edges: Vec<Vec<usize>>,
@@ -25,3 +28,82 @@ pub struct SccResult {
     pub components: Vec<Vec<usize>>,
     pub cyclic_component: Vec<bool>,
 }
+
+impl SccResult {
+    /// Builds a result from per-node component labels (any numbering).
+    ///
+    /// Components are renumbered by their smallest member and members are sorted,
+    /// so detectors that label differently still produce identical results.
+    pub fn from_labels(graph: &Graph, labels: &[usize]) -> Self {
+        let mut renumber: HashMap<usize, usize> = HashMap::new();
+        let mut component_of = vec![0; labels.len()];
+        let mut components: Vec<Vec<usize>> = Vec::new();
+
+        for (v, label) in labels.iter().enumerate() {
+            let cid = *renumber.entry(*label).or_insert_with(|| {
+                components.push(Vec::new());
+                components.len() - 1
+            });
+            component_of[v] = cid;
+            components[cid].push(v);
+        }
+
+        let cyclic_component = components
+            .iter()
+            .map(|comp| comp.len() > 1 || graph.edges[comp[0]].contains(&comp[0]))
+            .collect();
+
+        Self {
+            component_of,
+            components,
+            cyclic_component,
+        }
+    }
+
+    /// First node whose component differs between `self` and `other`, or `None` when both
+    /// describe the same partition (component numbering is ignored).
+    pub fn first_partition_difference(&self, other: &SccResult) -> Option<usize> {
+        let n = self.component_of.len();
+        if n != other.component_of.len() {
+            return Some(n.min(other.component_of.len()));
+        }
+        // Each component is named by its smallest member, computed once per component.
+        let smallest = |r: &SccResult| -> Vec<Option<usize>> {
+            r.components
+                .iter()
+                .map(|c| c.iter().min().copied())
+                .collect()
+        };
+        let (ours, theirs) = (smallest(self), smallest(other));
+        (0..n).find(|&v| ours[self.component_of[v]] != theirs[other.component_of[v]])
+    }
+}
+
+/// Failure of a fallible SCC computation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SccError {
+    /// Two detectors disagreed on the component partition.
+    PartitionMismatch {
+        primary: String,
+        secondary: String,
+        /// First graph node whose component differs.
+        node: usize,
+    },
+}
+
+impl fmt::Display for SccError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PartitionMismatch {
+                primary,
+                secondary,
+                node,
+            } => write!(
+                f,
+                "SCC cross-check failed: {primary} and {secondary} disagree on the component of node {node}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SccError {}
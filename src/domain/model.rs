@@ -1,12 +1,41 @@
 use std::cmp::Ordering;
+use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum NodeKind {
+    #[default]
     Folder,
     Url,
+    /// Any other `type` value, kept verbatim so it round-trips.
     Other(String),
 }
 
+impl NodeKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Folder => "folder",
+            Self::Url => "url",
+            Self::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for NodeKind {
+    fn from(s: &str) -> Self {
+        match s {
+            "folder" => Self::Folder,
+            "url" => Self::Url,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct NodeId(pub String);
 
@@ -50,6 +79,17 @@ impl BookmarkNode {
 mod tests {
     use super::*;
 
+    #[test]
+    fn node_kind_round_trips_type_strings() {
+        for s in ["folder", "url", "separator"] {
+            assert_eq!(NodeKind::from(s).as_str(), s);
+        }
+        assert_eq!(
+            NodeKind::from("separator"),
+            NodeKind::Other("separator".to_string())
+        );
+    }
+
     #[test]
     fn normalized_folder_name_trims_and_lowercases() {
         let node = BookmarkNode {
@@ -1,9 +1,16 @@
-use crate::domain::graph::{Graph, SccResult};
+use crate::domain::graph::{Graph, SccError, SccResult};
 
-pub trait UrlCanonicalizer {
+/// Shared across threads when canonical keys are computed in parallel.
+pub trait UrlCanonicalizer: Send + Sync {
     fn canonicalize(&self, url: &str) -> String;
 }
 
 pub trait SccDetector {
     fn compute_scc(&self, graph: &Graph) -> SccResult;
+
+    /// Fallible variant used by the pipeline; detectors that verify their result
+    /// (e.g. cross-checking) override it. Defaults to `compute_scc`.
+    fn try_compute_scc(&self, graph: &Graph) -> Result<SccResult, SccError> {
+        Ok(self.compute_scc(graph))
+    }
 }
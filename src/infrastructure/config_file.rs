@@ -0,0 +1,479 @@
+use crate::infrastructure::read_options::ReadOptions;
+use crate::infrastructure::scc_selection::SccSelection;
+use crate::infrastructure::schema_catalog::Schemas;
+use crate::usecase::lint::LintOptions;
+use crate::usecase::normalize::folder_attrs::FolderMergePolicy;
+use crate::usecase::normalize::folder_winner::FolderWinnerPolicy;
+use crate::usecase::normalize::url_merge::UrlMergePolicy;
+use crate::usecase::normalize::url_winner::UrlWinnerPolicy;
+use crate::usecase::normalize::NormalizeOptions;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs;
+
+/// On-disk JSON configuration for `bookmarks normalize` (passed with `--config`).
+///
+/// Every field is optional; anything omitted keeps the built-in default.
+/// Command-line flags take precedence over values loaded from this file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NormalizeConfigDto {
+    /// Folder winner rules in priority order, e.g. `["prefer-root:bookmark_bar", "outermost"]`.
+    pub folder_winner: Option<Vec<String>>,
+    /// Display-name rule for merged folders: `winner`, `most-frequent`, `most-descendants`
+    /// or `title-case`.
+    pub folder_name: Option<String>,
+    /// Explicit spellings keyed by folder name, e.g. `{"work": "Work"}`; these override
+    /// `folder_name` for their group.
+    pub folder_aliases: Option<BTreeMap<String, String>>,
+    /// Attribute merge of loser folders into the winner.
+    pub folder_merge: Option<FolderMergeConfigDto>,
+    /// URL dedup winner rules in priority order, e.g. `["prefer-https", "most-visited"]`.
+    pub url_winner: Option<Vec<String>>,
+    /// Field-level merge of duplicate URLs into the winner.
+    pub url_merge: Option<UrlMergeConfigDto>,
+    /// SCC detector: `kosaraju`, `tarjan` or `path-based`.
+    pub scc: Option<String>,
+    /// Second detector whose component partition must match `scc`'s.
+    pub scc_cross_check: Option<String>,
+    /// Handling of a `guid`/`id` shared by several nodes: `report`, `merge` or `separate`.
+    pub duplicate_identities: Option<String>,
+    /// Upper bound on merge iterations; must be at least 1.
+    pub max_merge_iterations: Option<usize>,
+    /// Most URLs the run may remove: a count, a percentage or both, e.g. `"5%,250"`.
+    pub max_removed_urls: Option<String>,
+    /// Most folders the run may remove, in the same form as `max_removed_urls`.
+    pub max_removed_folders: Option<String>,
+    /// Input limits checked while the Bookmarks file is read.
+    pub limits: Option<LimitsConfigDto>,
+    /// Schema mode for reading the input: `strict`, `lenient` or `off`.
+    pub schema: Option<String>,
+    /// Directory of schema files used in place of the embedded ones.
+    pub schema_dir: Option<String>,
+    /// Rule switches and thresholds for `bookmarks lint`.
+    pub lint: Option<LintConfigDto>,
+}
+
+/// `lint` section: rules keyed by id, `false` to disable one, plus thresholds.
+///
+/// Example: `{"rules": {"non-http-url": false}, "huge_folder_children": 500}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfigDto {
+    pub rules: Option<BTreeMap<String, bool>>,
+    pub huge_folder_children: Option<usize>,
+}
+
+/// `limits` section; omitted fields keep the defaults of [`ReadOptions`].
+///
+/// Example: `{"max_depth": 32, "max_file_bytes": 104857600}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfigDto {
+    pub max_file_bytes: Option<u64>,
+    pub max_nodes: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_name_bytes: Option<usize>,
+    pub max_url_bytes: Option<usize>,
+}
+
+/// `url_merge` section: an optional preset (`winner` or `fold`) refined per field.
+///
+/// Example: `{"preset": "fold", "visit_count": "max", "title": "winner"}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrlMergeConfigDto {
+    pub preset: Option<String>,
+    /// `winner` or `earliest`.
+    pub date_added: Option<String>,
+    /// `winner` or `latest`.
+    pub date_last_used: Option<String>,
+    /// `winner`, `sum` or `max`.
+    pub visit_count: Option<String>,
+    /// `winner` or `union`.
+    pub meta_info: Option<String>,
+    /// `winner` or `longest`.
+    pub title: Option<String>,
+}
+
+/// `folder_merge` section: an optional preset (`winner`, `accumulate` or `fold`) refined per field.
+///
+/// Example: `{"preset": "fold", "extra": "winner"}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FolderMergeConfigDto {
+    pub preset: Option<String>,
+    /// `winner` or `earliest`.
+    pub date_added: Option<String>,
+    /// `winner` or `latest`.
+    pub date_modified: Option<String>,
+    /// `winner`, `sum` or `max`.
+    pub visit_count: Option<String>,
+    /// `winner` or `union` (covers `meta_info` and other unknown fields).
+    pub extra: Option<String>,
+}
+
+impl FolderMergeConfigDto {
+    fn to_policy(&self) -> Result<FolderMergePolicy> {
+        let mut policy = match &self.preset {
+            Some(p) => parse_field(p, "folder_merge.preset")?,
+            None => FolderMergePolicy::default(),
+        };
+        if let Some(v) = &self.date_added {
+            policy.date_added = parse_field(v, "folder_merge.date_added")?;
+        }
+        if let Some(v) = &self.date_modified {
+            policy.date_modified = parse_field(v, "folder_merge.date_modified")?;
+        }
+        if let Some(v) = &self.visit_count {
+            policy.visit_count = parse_field(v, "folder_merge.visit_count")?;
+        }
+        if let Some(v) = &self.extra {
+            policy.extra = parse_field(v, "folder_merge.extra")?;
+        }
+        Ok(policy)
+    }
+}
+
+impl UrlMergeConfigDto {
+    fn to_policy(&self) -> Result<UrlMergePolicy> {
+        let mut policy = match &self.preset {
+            Some(p) => parse_field(p, "url_merge.preset")?,
+            None => UrlMergePolicy::default(),
+        };
+        if let Some(v) = &self.date_added {
+            policy.date_added = parse_field(v, "url_merge.date_added")?;
+        }
+        if let Some(v) = &self.date_last_used {
+            policy.date_last_used = parse_field(v, "url_merge.date_last_used")?;
+        }
+        if let Some(v) = &self.visit_count {
+            policy.visit_count = parse_field(v, "url_merge.visit_count")?;
+        }
+        if let Some(v) = &self.meta_info {
+            policy.meta_info = parse_field(v, "url_merge.meta_info")?;
+        }
+        if let Some(v) = &self.title {
+            policy.title = parse_field(v, "url_merge.title")?;
+        }
+        Ok(policy)
+    }
+}
+
+impl NormalizeConfigDto {
+    pub fn apply_to(&self, options: &mut NormalizeOptions) -> Result<()> {
+        if let Some(rules) = &self.folder_winner {
+            options.folder_winner = parse_list::<FolderWinnerPolicy>(rules, "folder_winner")?;
+        }
+        if let Some(rule) = &self.folder_name {
+            options.folder_name.rule = parse_field(rule, "folder_name")?;
+        }
+        if let Some(aliases) = &self.folder_aliases {
+            options.folder_name = options.folder_name.clone().with_aliases(aliases);
+        }
+        if let Some(merge) = &self.folder_merge {
+            options.folder_merge = merge.to_policy()?;
+        }
+        if let Some(rules) = &self.url_winner {
+            options.url_winner = parse_list::<UrlWinnerPolicy>(rules, "url_winner")?;
+        }
+        if let Some(merge) = &self.url_merge {
+            options.url_merge = merge.to_policy()?;
+        }
+        if let Some(mode) = &self.duplicate_identities {
+            options.duplicate_identities = parse_field(mode, "duplicate_identities")?;
+        }
+        if let Some(limit) = self.max_merge_iterations {
+            if limit == 0 {
+                return Err(anyhow!(
+                    "config field max_merge_iterations: must be at least 1"
+                ));
+            }
+            options.max_merge_iterations = limit;
+        }
+        if let Some(limit) = &self.max_removed_urls {
+            options.removal_limits.urls = Some(parse_field(limit, "max_removed_urls")?);
+        }
+        if let Some(limit) = &self.max_removed_folders {
+            options.removal_limits.folders = Some(parse_field(limit, "max_removed_folders")?);
+        }
+        Ok(())
+    }
+
+    pub fn apply_limits(&self, read: &mut ReadOptions) {
+        let Some(limits) = &self.limits else {
+            return;
+        };
+        if let Some(v) = limits.max_file_bytes {
+            read.max_file_bytes = v;
+        }
+        if let Some(v) = limits.max_nodes {
+            read.max_nodes = v;
+        }
+        if let Some(v) = limits.max_depth {
+            read.max_depth = v;
+        }
+        if let Some(v) = limits.max_name_bytes {
+            read.max_name_bytes = v;
+        }
+        if let Some(v) = limits.max_url_bytes {
+            read.max_url_bytes = v;
+        }
+    }
+
+    pub fn apply_lint(&self, options: &mut LintOptions) -> Result<()> {
+        let Some(lint) = &self.lint else {
+            return Ok(());
+        };
+        for (rule, enabled) in lint.rules.iter().flatten() {
+            options.set_enabled(parse_field(rule, "lint.rules")?, *enabled);
+        }
+        if let Some(limit) = lint.huge_folder_children {
+            options.huge_folder_children = limit;
+        }
+        Ok(())
+    }
+
+    pub fn apply_schema(&self, read: &mut ReadOptions) -> Result<()> {
+        if let Some(mode) = &self.schema {
+            read.schema = parse_field(mode, "schema")?;
+        }
+        if let Some(dir) = &self.schema_dir {
+            let schemas = Schemas::with_overrides(Path::new(dir))
+                .map_err(|e| anyhow!("config field schema_dir: {e:#}"))?;
+            read.schemas = Arc::new(schemas);
+        }
+        Ok(())
+    }
+
+    pub fn apply_scc(&self, selection: &mut SccSelection) -> Result<()> {
+        if let Some(algo) = &self.scc {
+            selection.algorithm = parse_field(algo, "scc")?;
+        }
+        if let Some(algo) = &self.scc_cross_check {
+            selection.cross_check = Some(parse_field(algo, "scc_cross_check")?);
+        }
+        Ok(())
+    }
+}
+
+pub async fn read_normalize_config(path: &str) -> Result<NormalizeConfigDto> {
+    let raw = fs::read_to_string(path).await?;
+    let cfg: NormalizeConfigDto =
+        serde_json::from_str(&raw).with_context(|| format!("parsing config JSON: {path}"))?;
+    Ok(cfg)
+}
+
+fn parse_field<T>(value: &str, field: &str) -> Result<T>
+where
+    T: std::str::FromStr<Err = anyhow::Error>,
+{
+    value
+        .parse::<T>()
+        .map_err(|e| anyhow!("config field {field}: {e}"))
+}
+
+/// Policies parse from a comma-separated list; config files spell them as JSON arrays.
+fn parse_list<T>(items: &[String], field: &str) -> Result<T>
+where
+    T: std::str::FromStr<Err = anyhow::Error>,
+{
+    items
+        .join(",")
+        .parse::<T>()
+        .map_err(|e| anyhow!("config field {field}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::scc_selection::SccAlgorithm;
+    use crate::usecase::normalize::folder_name::{FolderNamePolicy, FolderNameRule};
+    use crate::usecase::normalize::folder_winner::FolderWinnerRule;
+    use crate::usecase::normalize::url_merge::{TitleMerge, VisitCountMerge};
+    use crate::usecase::normalize::url_winner::UrlWinnerRule;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn read_and_apply_policies() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"folder_winner": ["prefer-root:other", "most-children"], "url_winner": ["has-meta-info", "earliest-added"]}"#,
+        )
+        .expect("write config");
+
+        let cfg = read_normalize_config(path.to_str().unwrap())
+            .await
+            .expect("read config");
+        let mut options = NormalizeOptions::default();
+        cfg.apply_to(&mut options).expect("apply");
+
+        assert_eq!(
+            options.folder_winner.rules,
+            vec![
+                FolderWinnerRule::PreferRoot("other".to_string()),
+                FolderWinnerRule::MostChildren
+            ]
+        );
+        assert_eq!(
+            options.url_winner.rules,
+            vec![UrlWinnerRule::HasMetaInfo, UrlWinnerRule::EarliestAdded]
+        );
+    }
+
+    #[test]
+    fn apply_reports_field_name_on_bad_rule() {
+        let cfg = NormalizeConfigDto {
+            folder_winner: Some(vec!["nope".to_string()]),
+            ..NormalizeConfigDto::default()
+        };
+        let err = cfg
+            .apply_to(&mut NormalizeOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("folder_winner"));
+    }
+
+    #[test]
+    fn url_merge_preset_is_refined_per_field() {
+        let cfg: NormalizeConfigDto = serde_json::from_str(
+            r#"{"url_merge": {"preset": "fold", "visit_count": "max", "title": "winner"}}"#,
+        )
+        .expect("parse config");
+        let mut options = NormalizeOptions::default();
+        cfg.apply_to(&mut options).expect("apply");
+
+        assert_eq!(
+            options.url_merge,
+            UrlMergePolicy {
+                visit_count: VisitCountMerge::Max,
+                title: TitleMerge::Winner,
+                ..UrlMergePolicy::fold()
+            }
+        );
+
+        let cfg: NormalizeConfigDto =
+            serde_json::from_str(r#"{"url_merge": {"meta_info": "all"}}"#).expect("parse config");
+        let err = cfg
+            .apply_to(&mut NormalizeOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("url_merge.meta_info"));
+    }
+
+    #[test]
+    fn folder_name_rule_and_aliases() {
+        let cfg: NormalizeConfigDto = serde_json::from_str(
+            r#"{"folder_name": "title-case", "folder_aliases": {"Work ": "WORK"}}"#,
+        )
+        .expect("parse config");
+        let mut options = NormalizeOptions::default();
+        cfg.apply_to(&mut options).expect("apply");
+
+        assert_eq!(
+            options.folder_name,
+            FolderNamePolicy::new(FolderNameRule::TitleCase).with_aliases([("work", "WORK")])
+        );
+    }
+
+    #[test]
+    fn folder_merge_preset_is_refined_per_field() {
+        let cfg: NormalizeConfigDto =
+            serde_json::from_str(r#"{"folder_merge": {"preset": "fold", "visit_count": "max"}}"#)
+                .expect("parse config");
+        let mut options = NormalizeOptions::default();
+        cfg.apply_to(&mut options).expect("apply");
+
+        assert_eq!(
+            options.folder_merge,
+            FolderMergePolicy {
+                visit_count: VisitCountMerge::Max,
+                ..FolderMergePolicy::fold()
+            }
+        );
+    }
+
+    #[test]
+    fn scc_selection_from_config() {
+        let cfg: NormalizeConfigDto =
+            serde_json::from_str(r#"{"scc": "tarjan", "scc_cross_check": "path-based"}"#)
+                .expect("parse config");
+        let mut selection = SccSelection::default();
+        cfg.apply_scc(&mut selection).expect("apply");
+        assert_eq!(
+            selection,
+            SccSelection {
+                algorithm: SccAlgorithm::Tarjan,
+                cross_check: Some(SccAlgorithm::PathBased),
+            }
+        );
+
+        let cfg: NormalizeConfigDto = serde_json::from_str(r#"{"scc": "dfs"}"#).expect("parse");
+        let err = cfg
+            .apply_scc(&mut SccSelection::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("config field scc"));
+    }
+
+    #[test]
+    fn schema_mode_from_config() {
+        use crate::infrastructure::read_options::SchemaMode;
+
+        let cfg: NormalizeConfigDto =
+            serde_json::from_str(r#"{"schema": "lenient"}"#).expect("parse config");
+        let mut read = ReadOptions::default();
+        cfg.apply_schema(&mut read).expect("apply");
+        assert_eq!(read.schema, SchemaMode::Lenient);
+
+        let cfg: NormalizeConfigDto =
+            serde_json::from_str(r#"{"schema": "loose"}"#).expect("parse config");
+        let err = cfg.apply_schema(&mut read).unwrap_err().to_string();
+        assert!(err.contains("config field schema"), "{err}");
+
+        let cfg: NormalizeConfigDto =
+            serde_json::from_str(r#"{"schema_dir": "/nonexistent/schemas"}"#)
+                .expect("parse config");
+        let err = cfg.apply_schema(&mut read).unwrap_err().to_string();
+        assert!(err.contains("config field schema_dir"), "{err}");
+    }
+
+    #[test]
+    fn lint_rules_switch_by_id() {
+        let cfg: NormalizeConfigDto = serde_json::from_str(
+            r#"{"lint": {"rules": {"non-http-url": false, "empty-name": true}, "huge_folder_children": 50}}"#,
+        )
+        .expect("parse config");
+        let mut options = LintOptions::default();
+        cfg.apply_lint(&mut options).expect("apply");
+        assert_eq!(
+            options
+                .disabled
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["non-http-url"]
+        );
+        assert_eq!(options.huge_folder_children, 50);
+
+        let cfg: NormalizeConfigDto =
+            serde_json::from_str(r#"{"lint": {"rules": {"no-such-rule": false}}}"#)
+                .expect("parse config");
+        let err = cfg
+            .apply_lint(&mut LintOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("config field lint.rules: unknown lint rule: no-such-rule"));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let res = serde_json::from_str::<NormalizeConfigDto>(r#"{"folder_winer": []}"#);
+        assert!(res.is_err());
+    }
+}
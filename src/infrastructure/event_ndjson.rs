@@ -15,6 +15,29 @@ fn app_event_to_json(ev: &AppEvent) -> serde_json::Value {
         } => {
             json!({"type":"scc_computed","nodes":nodes,"edges":edges,"components":components,"cyclic_components":cyclic_components})
         }
+        AppEvent::DuplicateIdentity {
+            field,
+            value,
+            identical,
+            instances,
+        } => {
+            json!({"type":"duplicate_identity","field":field,"value":value,"identical":identical,"instances":instances})
+        }
+        AppEvent::CycleDetected {
+            members,
+            witness,
+            witness_edges,
+        } => {
+            json!({"type":"cycle_detected","members":members,"witness":witness,"witness_edges":witness_edges})
+        }
+        AppEvent::IdentityEdgeCut {
+            identity,
+            parent_path,
+            child_path,
+            moved_to,
+        } => {
+            json!({"type":"identity_edge_cut","identity":identity,"parent_path":parent_path,"child_path":child_path,"moved_to":moved_to})
+        }
         AppEvent::FolderMergePlanned {
             normalized_name,
             group_size,
@@ -25,8 +48,21 @@ fn app_event_to_json(ev: &AppEvent) -> serde_json::Value {
             normalized_name,
             winner_path,
             losers,
+            policy,
+            rationale,
+            name,
+            name_rule,
         } => {
-            json!({"type":"folder_merged","normalized_name":normalized_name,"winner_path":winner_path,"losers":losers})
+            json!({"type":"folder_merged","normalized_name":normalized_name,"winner_path":winner_path,"losers":losers,"policy":policy,"rationale":rationale,"name":name,"name_rule":name_rule})
+        }
+        AppEvent::FolderAttributeConflict {
+            folder_path,
+            loser_path,
+            field,
+            kept,
+            dropped,
+        } => {
+            json!({"type":"folder_attribute_conflict","folder_path":folder_path,"loser_path":loser_path,"field":field,"kept":kept,"dropped":dropped})
         }
         AppEvent::UrlDeduped {
             folder_path,
@@ -38,6 +74,15 @@ fn app_event_to_json(ev: &AppEvent) -> serde_json::Value {
         AppEvent::FolderPruned { folder_path } => {
             json!({"type":"folder_pruned","folder_path":folder_path})
         }
+        AppEvent::RemovalLimitExceeded {
+            kind,
+            removed,
+            total,
+            limit,
+            forced,
+        } => {
+            json!({"type":"removal_limit_exceeded","kind":kind,"removed":removed,"total":total,"limit":limit,"forced":forced})
+        }
         AppEvent::Finished { stats } => json!({"type":"finished","stats":stats}),
         AppEvent::FolderCounts { counts } => json!({"type":"folder_counts","counts":counts}),
     }
@@ -80,6 +125,32 @@ mod tests {
         assert_eq!(v["type"], "scc_computed");
         assert_eq!(v["nodes"], 1);
 
+        let v = app_event_to_json(&AppEvent::DuplicateIdentity {
+            field: "guid".to_string(),
+            value: "g1".to_string(),
+            identical: true,
+            instances: vec![],
+        });
+        assert_eq!(v["type"], "duplicate_identity");
+        assert_eq!(v["identical"], true);
+
+        let v = app_event_to_json(&AppEvent::CycleDetected {
+            members: vec![],
+            witness: "A → B → A".to_string(),
+            witness_edges: vec![],
+        });
+        assert_eq!(v["type"], "cycle_detected");
+        assert_eq!(v["witness"], "A → B → A");
+
+        let v = app_event_to_json(&AppEvent::IdentityEdgeCut {
+            identity: "g1".to_string(),
+            parent_path: "/A/B".to_string(),
+            child_path: "/A/B/A".to_string(),
+            moved_to: "/A".to_string(),
+        });
+        assert_eq!(v["type"], "identity_edge_cut");
+        assert_eq!(v["moved_to"], "/A");
+
         let v = app_event_to_json(&AppEvent::FolderMergePlanned {
             normalized_name: "a".to_string(),
             group_size: 2,
@@ -90,8 +161,23 @@ mod tests {
             normalized_name: "a".to_string(),
             winner_path: "/root/a".to_string(),
             losers: vec!["/root/b".to_string()],
+            policy: "outermost".to_string(),
+            rationale: vec!["outermost: depth 1 < 2".to_string()],
+            name: "A".to_string(),
+            name_rule: "winner".to_string(),
         });
         assert_eq!(v["type"], "folder_merged");
+        assert_eq!(v["rationale"][0], "outermost: depth 1 < 2");
+
+        let v = app_event_to_json(&AppEvent::FolderAttributeConflict {
+            folder_path: "/root/a".to_string(),
+            loser_path: "/root/b".to_string(),
+            field: "meta_info.k".to_string(),
+            kept: json!("1"),
+            dropped: json!("2"),
+        });
+        assert_eq!(v["type"], "folder_attribute_conflict");
+        assert_eq!(v["dropped"], "2");
 
         let v = app_event_to_json(&AppEvent::UrlDeduped {
             folder_path: "/root".to_string(),
@@ -105,6 +191,16 @@ mod tests {
         });
         assert_eq!(v["type"], "folder_pruned");
 
+        let v = app_event_to_json(&AppEvent::RemovalLimitExceeded {
+            kind: "URLs".to_string(),
+            removed: 40,
+            total: 100,
+            limit: "10%".to_string(),
+            forced: false,
+        });
+        assert_eq!(v["type"], "removal_limit_exceeded");
+        assert_eq!(v["limit"], "10%");
+
         let v = app_event_to_json(&AppEvent::Finished {
             stats: NormalizeStats::default(),
         });
@@ -0,0 +1,282 @@
+//! Maps JSON pointers back to where their values start in the source text, so errors found
+//! on a parsed document can name a line and column.
+
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::{self, BufRead, Bytes};
+
+/// Start of a value in the source text: 1-based line, and 1-based byte column within it
+/// (the convention serde_json uses in its parse errors).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TextPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for TextPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Scans `reader` once, without building the document, and returns where the value at
+/// each of `pointers` starts. Pointers that do not resolve are left out; so is everything
+/// after the first syntax error, since the text is expected to have parsed already.
+pub fn locate<R: BufRead>(
+    reader: R,
+    pointers: &BTreeSet<String>,
+) -> io::Result<HashMap<String, TextPosition>> {
+    let mut found: HashMap<String, TextPosition> = HashMap::new();
+    if pointers.is_empty() {
+        return Ok(found);
+    }
+    let mut text = Cursor::new(reader);
+    let mut pointer = String::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut state = State::Value;
+
+    loop {
+        text.skip_whitespace()?;
+        match state {
+            State::Value => {
+                if pointers.contains(pointer.as_str()) {
+                    found.insert(pointer.clone(), text.position());
+                    if found.len() == pointers.len() {
+                        break;
+                    }
+                }
+                match text.peek()? {
+                    Some(b'{') => {
+                        text.next()?;
+                        text.skip_whitespace()?;
+                        if text.peek()? == Some(b'}') {
+                            text.next()?;
+                            state = State::AfterValue;
+                        } else {
+                            stack.push(Frame::Object {
+                                base: pointer.len(),
+                            });
+                            state = State::Key;
+                        }
+                    }
+                    Some(b'[') => {
+                        text.next()?;
+                        text.skip_whitespace()?;
+                        if text.peek()? == Some(b']') {
+                            text.next()?;
+                            state = State::AfterValue;
+                        } else {
+                            stack.push(Frame::Array {
+                                base: pointer.len(),
+                                index: 0,
+                            });
+                            pointer.push_str("/0");
+                        }
+                    }
+                    Some(b'"') => {
+                        text.next()?;
+                        text.string()?;
+                        state = State::AfterValue;
+                    }
+                    Some(_) => {
+                        text.scalar()?;
+                        state = State::AfterValue;
+                    }
+                    None => break,
+                }
+            }
+            State::Key => {
+                if text.next()? != Some(b'"') {
+                    break;
+                }
+                let key = text.string()?;
+                text.skip_whitespace()?;
+                if text.next()? != Some(b':') {
+                    break;
+                }
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                state = State::Value;
+            }
+            State::AfterValue => {
+                let Some(frame) = stack.last_mut() else {
+                    break;
+                };
+                match (frame, text.next()?) {
+                    (Frame::Object { base }, Some(b',')) => {
+                        pointer.truncate(*base);
+                        state = State::Key;
+                    }
+                    (Frame::Array { base, index }, Some(b',')) => {
+                        pointer.truncate(*base);
+                        *index += 1;
+                        pointer.push('/');
+                        pointer.push_str(&index.to_string());
+                        state = State::Value;
+                    }
+                    (Frame::Object { base }, Some(b'}'))
+                    | (Frame::Array { base, .. }, Some(b']')) => {
+                        pointer.truncate(*base);
+                        stack.pop();
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+    Ok(found)
+}
+
+enum State {
+    Value,
+    Key,
+    AfterValue,
+}
+
+/// An open container; `base` is the length of its own pointer.
+enum Frame {
+    Object { base: usize },
+    Array { base: usize, index: usize },
+}
+
+/// Byte reader that knows the position of the next byte.
+struct Cursor<R> {
+    bytes: Bytes<R>,
+    peeked: Option<u8>,
+    line: usize,
+    column: usize,
+}
+
+impl<R: BufRead> Cursor<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            bytes: reader.bytes(),
+            peeked: None,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn position(&self) -> TextPosition {
+        TextPosition {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        if self.peeked.is_none() {
+            self.peeked = self.bytes.next().transpose()?;
+        }
+        Ok(self.peeked)
+    }
+
+    fn next(&mut self) -> io::Result<Option<u8>> {
+        let b = match self.peeked.take() {
+            Some(b) => Some(b),
+            None => self.bytes.next().transpose()?,
+        };
+        if b == Some(b'\n') {
+            self.line += 1;
+            self.column = 1;
+        } else if b.is_some() {
+            self.column += 1;
+        }
+        Ok(b)
+    }
+
+    fn skip_whitespace(&mut self) -> io::Result<()> {
+        while matches!(self.peek()?, Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    /// Consumes a number, `true`, `false` or `null`.
+    fn scalar(&mut self) -> io::Result<()> {
+        while let Some(b) = self.peek()? {
+            if matches!(b, b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r') {
+                break;
+            }
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    /// Consumes the rest of a string whose opening quote was read and returns it decoded.
+    fn string(&mut self) -> io::Result<String> {
+        let mut out: Vec<u8> = Vec::new();
+        while let Some(b) = self.next()? {
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = match self.next()? {
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let high = self.hex4()?;
+                            if (0xD800..0xDC00).contains(&high) {
+                                // A surrogate pair spells one character in two escapes.
+                                self.next()?;
+                                self.next()?;
+                                let low = self.hex4()?;
+                                char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                                    .unwrap_or(char::REPLACEMENT_CHARACTER)
+                            } else {
+                                char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER)
+                            }
+                        }
+                        Some(other) => other as char,
+                        None => break,
+                    };
+                    let mut buf = [0u8; 4];
+                    out.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                other => out.push(other),
+            }
+        }
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    fn hex4(&mut self) -> io::Result<u32> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next()?
+                .and_then(|b| (b as char).to_digit(16))
+                .unwrap_or(0);
+            value = value * 16 + digit;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_values_by_pointer_with_line_and_column() {
+        let text = "{\n  \"roots\": {\"a/b\": {\"children\": [\n    {\"x\": 1},\n    {\"na\\u00efve~\": [true, \"s\"]}\n  ]}}\n}";
+        let pointers: BTreeSet<String> = [
+            "",
+            "/roots/a~1b/children/0",
+            "/roots/a~1b/children/1/na\u{ef}ve~0/1",
+            "/roots/missing",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        let found = locate(text.as_bytes(), &pointers).expect("scan");
+        let at = |p: &str| found.get(p).map(|pos| (pos.line, pos.column));
+        assert_eq!(at(""), Some((1, 1)));
+        assert_eq!(at("/roots/a~1b/children/0"), Some((3, 5)));
+        assert_eq!(at("/roots/a~1b/children/1/na\u{ef}ve~0/1"), Some((4, 28)));
+        assert_eq!(at("/roots/missing"), None);
+    }
+}
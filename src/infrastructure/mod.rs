@@ -1,6 +1,14 @@
 // Infrastructure layer: adapters, file I/O, serde, eventing
+pub mod config_file;
 pub mod event_ndjson;
+pub mod json_locator;
+pub mod process_memory;
+pub mod read_options;
 pub mod scc_kosaraju;
+pub mod scc_path_based;
+pub mod scc_selection;
+pub mod scc_tarjan;
+pub mod schema_catalog;
 pub mod schema_validator;
 pub mod serde_json_adapter;
 pub mod url_canonicalizer;
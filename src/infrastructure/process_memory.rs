@@ -0,0 +1,32 @@
+/// Peak resident set size of this process (`VmHWM`), on Linux.
+///
+/// Other platforms, and kernels without `/proc`, report `None`.
+pub fn peak_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_hwm(&status)
+}
+
+fn parse_vm_hwm(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kib: u64 = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_vm_hwm_in_kib() {
+        let status =
+            "Name:\tbookmarks\nVmPeak:\t  20000 kB\nVmHWM:\t    1536 kB\nVmRSS:\t1024 kB\n";
+        assert_eq!(parse_vm_hwm(status), Some(1536 * 1024));
+        assert_eq!(parse_vm_hwm("Name:\tbookmarks\n"), None);
+    }
+}
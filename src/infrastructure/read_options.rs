@@ -0,0 +1,216 @@
+use crate::infrastructure::schema_catalog::Schemas;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Bounds on what the Bookmarks readers accept, so a corrupted or hostile file fails with
+/// a clear message instead of exhausting memory or stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOptions {
+    pub max_file_bytes: u64,
+    /// Every node counts, root containers included.
+    pub max_nodes: usize,
+    /// Folder nesting below a root container (its direct children are at depth 1).
+    /// The JSON parser independently stops at 128 nested values, about 62 folder levels.
+    pub max_depth: usize,
+    pub max_name_bytes: usize,
+    pub max_url_bytes: usize,
+    /// What a schema violation does to the read.
+    pub schema: SchemaMode,
+    /// The schema sets to check against; the file's `version` picks one.
+    pub schemas: Arc<Schemas>,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 512 * 1024 * 1024,
+            max_nodes: 2_000_000,
+            max_depth: 48,
+            max_name_bytes: 8 * 1024,
+            max_url_bytes: 64 * 1024,
+            schema: SchemaMode::default(),
+            schemas: Schemas::embedded(),
+        }
+    }
+}
+
+/// How the Bookmarks readers treat nodes that fail their JSON schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaMode {
+    /// Any violation fails the read.
+    #[default]
+    Strict,
+    /// Violations are returned as warnings; nodes that fail their schema are read anyway
+    /// and normalization leaves them as they are.
+    Lenient,
+    /// No schema checks; the file only has to fit the bookmarks model.
+    Off,
+}
+
+impl fmt::Display for SchemaMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Strict => "strict",
+            Self::Lenient => "lenient",
+            Self::Off => "off",
+        })
+    }
+}
+
+impl FromStr for SchemaMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "strict" => Ok(Self::Strict),
+            "lenient" => Ok(Self::Lenient),
+            "off" => Ok(Self::Off),
+            other => Err(anyhow!(
+                "unknown schema mode: {other} (expected strict, lenient, off)"
+            )),
+        }
+    }
+}
+
+/// A [`ReadOptions`] bound the input went over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub limit: &'static str,
+    pub max: u64,
+    pub found: u64,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "input exceeds {} ({} > {})",
+            self.limit, self.found, self.max
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl ReadOptions {
+    pub fn check_file_size(&self, bytes: u64) -> Result<(), LimitExceeded> {
+        exceeds("max_file_bytes", self.max_file_bytes, bytes)
+    }
+
+    pub fn check_depth(&self, depth: usize) -> Result<(), LimitExceeded> {
+        exceeds("max_depth", self.max_depth as u64, depth as u64)
+    }
+
+    pub fn check_node_count(&self, nodes: usize) -> Result<(), LimitExceeded> {
+        exceeds("max_nodes", self.max_nodes as u64, nodes as u64)
+    }
+
+    /// Checks the `name` and `url` strings of one node; other value types are left to the
+    /// schema.
+    pub fn check_strings(
+        &self,
+        name: Option<&str>,
+        url: Option<&str>,
+    ) -> Result<(), LimitExceeded> {
+        if let Some(name) = name {
+            exceeds(
+                "max_name_bytes",
+                self.max_name_bytes as u64,
+                name.len() as u64,
+            )?;
+        }
+        if let Some(url) = url {
+            exceeds("max_url_bytes", self.max_url_bytes as u64, url.len() as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Walks a parsed Bookmarks document (iteratively) and fails on the first node over a
+    /// limit, naming its JSON pointer.
+    pub fn check_document(&self, doc: &Value) -> Result<()> {
+        let Some(roots) = doc.get("roots").and_then(Value::as_object) else {
+            return Ok(());
+        };
+
+        let mut nodes = 0usize;
+        let mut stack: Vec<(String, &Value, usize)> = roots
+            .iter()
+            .rev()
+            .map(|(key, root)| (format!("/roots/{}", escape(key)), root, 0))
+            .collect();
+        while let Some((pointer, node, depth)) = stack.pop() {
+            nodes += 1;
+            self.check_node_count(nodes)
+                .and_then(|()| self.check_depth(depth))
+                .and_then(|()| {
+                    self.check_strings(
+                        node.get("name").and_then(Value::as_str),
+                        node.get("url").and_then(Value::as_str),
+                    )
+                })
+                .map_err(|e| anyhow!("{e} at {pointer}"))?;
+
+            if let Some(children) = node.get("children").and_then(Value::as_array) {
+                for (i, child) in children.iter().enumerate().rev() {
+                    stack.push((format!("{pointer}/children/{i}"), child, depth + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn exceeds(limit: &'static str, max: u64, found: u64) -> Result<(), LimitExceeded> {
+    if found > max {
+        return Err(LimitExceeded { limit, max, found });
+    }
+    Ok(())
+}
+
+fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_the_first_node_over_a_limit_by_pointer() {
+        let doc = json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+            {"type": "folder", "name": "A", "children": [
+                {"type": "url", "name": "x", "url": "https://example.com/a-long-path"}
+            ]}
+        ]}}});
+
+        let limits = ReadOptions {
+            max_url_bytes: 10,
+            ..ReadOptions::default()
+        };
+        let err = limits.check_document(&doc).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input exceeds max_url_bytes (31 > 10) at /roots/bookmark_bar/children/0/children/0"
+        );
+
+        let limits = ReadOptions {
+            max_depth: 1,
+            ..ReadOptions::default()
+        };
+        let err = limits.check_document(&doc).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("input exceeds max_depth (2 > 1)"));
+
+        let limits = ReadOptions {
+            max_nodes: 2,
+            ..ReadOptions::default()
+        };
+        assert!(limits.check_document(&doc).is_err());
+        assert!(ReadOptions::default().check_document(&doc).is_ok());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::domain::graph::{Graph, SccResult};
+use crate::domain::traits::SccDetector;
+
+/// Path-based (Gabow) algorithm with an explicit call stack.
+///
+/// Keeps a stack of tentative component roots instead of low-links; like Tarjan it needs
+/// a single pass and no reverse graph.
+pub struct PathBasedSccDetector;
+
+impl SccDetector for PathBasedSccDetector {
+    fn compute_scc(&self, graph: &Graph) -> SccResult {
+        path_based_scc(graph)
+    }
+}
+
+const UNVISITED: usize = usize::MAX;
+
+fn path_based_scc(graph: &Graph) -> SccResult {
+    let n = graph.node_count();
+    let mut preorder = vec![UNVISITED; n];
+    let mut labels = vec![UNVISITED; n];
+    // Nodes not yet assigned to a component, in preorder.
+    let mut pending: Vec<usize> = Vec::new();
+    // Tentative roots along the current path.
+    let mut roots: Vec<usize> = Vec::new();
+    let mut next_preorder = 0;
+    let mut next_label = 0;
+
+    let mut call: Vec<(usize, usize)> = Vec::new();
+
+    for start in 0..n {
+        if preorder[start] != UNVISITED {
+            continue;
+        }
+        preorder[start] = next_preorder;
+        next_preorder += 1;
+        pending.push(start);
+        roots.push(start);
+        call.push((start, 0));
+
+        while let Some(frame) = call.last_mut() {
+            let v = frame.0;
+            if let Some(&w) = graph.edges[v].get(frame.1) {
+                frame.1 += 1;
+                if preorder[w] == UNVISITED {
+                    preorder[w] = next_preorder;
+                    next_preorder += 1;
+                    pending.push(w);
+                    roots.push(w);
+                    call.push((w, 0));
+                } else if labels[w] == UNVISITED {
+                    // `w` is on the current path: everything after it joins its component.
+                    while roots.last().is_some_and(|&r| preorder[r] > preorder[w]) {
+                        roots.pop();
+                    }
+                }
+                continue;
+            }
+
+            call.pop();
+            if roots.last() == Some(&v) {
+                roots.pop();
+                while let Some(w) = pending.pop() {
+                    labels[w] = next_label;
+                    if w == v {
+                        break;
+                    }
+                }
+                next_label += 1;
+            }
+        }
+    }
+
+    SccResult::from_labels(graph, &labels)
+}
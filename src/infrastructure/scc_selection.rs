@@ -0,0 +1,144 @@
+use crate::domain::graph::{Graph, SccError, SccResult};
+use crate::domain::traits::SccDetector;
+use crate::infrastructure::scc_kosaraju::KosarajuSccDetector;
+use crate::infrastructure::scc_path_based::PathBasedSccDetector;
+use crate::infrastructure::scc_tarjan::TarjanSccDetector;
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Built-in SCC detectors, selectable with `--scc` or the `scc` config field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SccAlgorithm {
+    #[default]
+    Kosaraju,
+    Tarjan,
+    PathBased,
+}
+
+impl SccDetector for SccAlgorithm {
+    fn compute_scc(&self, graph: &Graph) -> SccResult {
+        match self {
+            Self::Kosaraju => KosarajuSccDetector.compute_scc(graph),
+            Self::Tarjan => TarjanSccDetector.compute_scc(graph),
+            Self::PathBased => PathBasedSccDetector.compute_scc(graph),
+        }
+    }
+}
+
+impl fmt::Display for SccAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Kosaraju => "kosaraju",
+            Self::Tarjan => "tarjan",
+            Self::PathBased => "path-based",
+        })
+    }
+}
+
+impl FromStr for SccAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "kosaraju" => Ok(Self::Kosaraju),
+            "tarjan" => Ok(Self::Tarjan),
+            "path-based" => Ok(Self::PathBased),
+            other => Err(anyhow!(
+                "unknown scc algorithm: {other} (expected kosaraju, tarjan, path-based)"
+            )),
+        }
+    }
+}
+
+/// The detector used by `normalize`, optionally verified by a second one.
+///
+/// With `cross_check` set, `try_compute_scc` runs both detectors and fails with
+/// [`SccError::PartitionMismatch`] when their component partitions differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SccSelection {
+    pub algorithm: SccAlgorithm,
+    pub cross_check: Option<SccAlgorithm>,
+}
+
+impl SccDetector for SccSelection {
+    fn compute_scc(&self, graph: &Graph) -> SccResult {
+        self.algorithm.compute_scc(graph)
+    }
+
+    fn try_compute_scc(&self, graph: &Graph) -> Result<SccResult, SccError> {
+        let primary = self.algorithm.compute_scc(graph);
+        if let Some(other) = self.cross_check {
+            let secondary = other.compute_scc(graph);
+            if let Some(node) = primary.first_partition_difference(&secondary) {
+                return Err(SccError::PartitionMismatch {
+                    primary: self.algorithm.to_string(),
+                    secondary: other.to_string(),
+                    node,
+                });
+            }
+        }
+        Ok(primary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always puts every node in its own component.
+    struct Singletons;
+
+    impl SccDetector for Singletons {
+        fn compute_scc(&self, graph: &Graph) -> SccResult {
+            let labels: Vec<usize> = (0..graph.node_count()).collect();
+            SccResult::from_labels(graph, &labels)
+        }
+    }
+
+    fn two_cycle() -> Graph {
+        let mut g = Graph::new(3);
+        g.edges[0].push(1);
+        g.edges[1].push(0);
+        g.edges[1].push(2);
+        g
+    }
+
+    #[test]
+    fn cross_check_passes_when_partitions_agree() {
+        let sel = SccSelection {
+            algorithm: SccAlgorithm::Tarjan,
+            cross_check: Some(SccAlgorithm::Kosaraju),
+        };
+        let res = sel.try_compute_scc(&two_cycle()).expect("detectors agree");
+        assert_eq!(res.components, vec![vec![0, 1], vec![2]]);
+    }
+
+    #[test]
+    fn partition_difference_names_first_disagreeing_node() {
+        let g = two_cycle();
+        let ok = SccAlgorithm::PathBased.compute_scc(&g);
+        let wrong = Singletons.compute_scc(&g);
+        assert_eq!(ok.first_partition_difference(&wrong), Some(1));
+        assert_eq!(ok.first_partition_difference(&ok.clone()), None);
+
+        let err = SccError::PartitionMismatch {
+            primary: "tarjan".to_string(),
+            secondary: "kosaraju".to_string(),
+            node: 1,
+        };
+        assert!(err.to_string().contains("node 1"));
+    }
+
+    #[test]
+    fn parse_algorithm_names() {
+        for algo in [
+            SccAlgorithm::Kosaraju,
+            SccAlgorithm::Tarjan,
+            SccAlgorithm::PathBased,
+        ] {
+            assert_eq!(algo.to_string().parse::<SccAlgorithm>().unwrap(), algo);
+        }
+        assert!("dfs".parse::<SccAlgorithm>().is_err());
+    }
+}
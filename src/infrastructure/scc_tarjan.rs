@@ -0,0 +1,74 @@
+use crate::domain::graph::{Graph, SccResult};
+use crate::domain::traits::SccDetector;
+
+/// Tarjan's algorithm with an explicit call stack: one pass, no reverse graph.
+pub struct TarjanSccDetector;
+
+impl SccDetector for TarjanSccDetector {
+    fn compute_scc(&self, graph: &Graph) -> SccResult {
+        tarjan_scc(graph)
+    }
+}
+
+const UNVISITED: usize = usize::MAX;
+
+fn tarjan_scc(graph: &Graph) -> SccResult {
+    let n = graph.node_count();
+    let mut index = vec![UNVISITED; n];
+    let mut low = vec![0usize; n];
+    let mut on_stack = vec![false; n];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut labels = vec![UNVISITED; n];
+    let mut next_index = 0;
+    let mut next_label = 0;
+
+    // (node, position of the next outgoing edge to explore)
+    let mut call: Vec<(usize, usize)> = Vec::new();
+
+    for start in 0..n {
+        if index[start] != UNVISITED {
+            continue;
+        }
+        index[start] = next_index;
+        low[start] = next_index;
+        next_index += 1;
+        stack.push(start);
+        on_stack[start] = true;
+        call.push((start, 0));
+
+        while let Some(frame) = call.last_mut() {
+            let v = frame.0;
+            if let Some(&w) = graph.edges[v].get(frame.1) {
+                frame.1 += 1;
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    low[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    call.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+
+            call.pop();
+            if low[v] == index[v] {
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    labels[w] = next_label;
+                    if w == v {
+                        break;
+                    }
+                }
+                next_label += 1;
+            }
+            if let Some(&(parent, _)) = call.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+        }
+    }
+
+    SccResult::from_labels(graph, &labels)
+}
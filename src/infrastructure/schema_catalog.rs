@@ -0,0 +1,284 @@
+//! The JSON schemas the readers check Bookmarks files against: one embedded set per file
+//! format `version`, optionally overridden file by file from a directory.
+
+use anyhow::{anyhow, Context, Result};
+use jsonschema::{Draft, JSONSchema};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Schema sources built into the binary, by the `version` they describe, oldest first.
+const EMBEDDED: &[(i64, [&str; 3])] = &[(
+    1,
+    [
+        include_str!("../schemas/v1/bookmarks_schema.json"),
+        include_str!("../schemas/v1/folder_schema.json"),
+        include_str!("../schemas/v1/url_schema.json"),
+    ],
+)];
+
+static EMBEDDED_SCHEMAS: Lazy<Arc<Schemas>> = Lazy::new(|| {
+    let sets = EMBEDDED
+        .iter()
+        .map(|(version, sources)| {
+            let sources =
+                sources.map(|s| serde_json::from_str(s).expect("Invalid embedded schema"));
+            let origins =
+                SchemaKind::ALL.map(|kind| format!("embedded v{version} {}", kind.file_name()));
+            SchemaSet::compile(*version, sources, origins)
+                .expect("Failed to compile embedded schema")
+        })
+        .collect();
+    Arc::new(Schemas { sets })
+});
+
+/// The three schemas of a set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// The whole file: `checksum`, `roots`, `version`, ...
+    Bookmarks,
+    Folder,
+    Url,
+}
+
+impl SchemaKind {
+    pub const ALL: [SchemaKind; 3] = [SchemaKind::Bookmarks, SchemaKind::Folder, SchemaKind::Url];
+
+    /// The file an override directory holds this schema in.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Bookmarks => "bookmarks_schema.json",
+            Self::Folder => "folder_schema.json",
+            Self::Url => "url_schema.json",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for SchemaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bookmarks => "bookmarks",
+            Self::Folder => "folder",
+            Self::Url => "url",
+        })
+    }
+}
+
+impl FromStr for SchemaKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "bookmarks" => Ok(Self::Bookmarks),
+            "folder" => Ok(Self::Folder),
+            "url" => Ok(Self::Url),
+            other => Err(anyhow!(
+                "unknown schema: {other} (expected bookmarks, folder, url)"
+            )),
+        }
+    }
+}
+
+/// The compiled bookmarks, folder and URL schemas for one file format version.
+pub struct SchemaSet {
+    version: i64,
+    sources: [Value; 3],
+    /// Where each schema came from: `embedded v1 url_schema.json` or an override's path.
+    origins: [String; 3],
+    compiled: [JSONSchema; 3],
+}
+
+impl SchemaSet {
+    fn compile(version: i64, sources: [Value; 3], origins: [String; 3]) -> Result<Self> {
+        let mut compiled = Vec::with_capacity(3);
+        for (source, origin) in sources.iter().zip(&origins) {
+            let schema = JSONSchema::options()
+                .with_draft(Draft::Draft7)
+                .compile(source)
+                .map_err(|e| anyhow!("compiling schema {origin}: {e}"))?;
+            compiled.push(schema);
+        }
+        let compiled: [JSONSchema; 3] = compiled
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("three schemas"));
+        Ok(Self {
+            version,
+            sources,
+            origins,
+            compiled,
+        })
+    }
+
+    /// The file format version this set was selected for.
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    pub fn source(&self, kind: SchemaKind) -> &Value {
+        &self.sources[kind.index()]
+    }
+
+    pub fn origin(&self, kind: SchemaKind) -> &str {
+        &self.origins[kind.index()]
+    }
+
+    pub(crate) fn schema(&self, kind: SchemaKind) -> &JSONSchema {
+        &self.compiled[kind.index()]
+    }
+}
+
+impl fmt::Debug for SchemaSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchemaSet")
+            .field("version", &self.version)
+            .field("origins", &self.origins)
+            .finish()
+    }
+}
+
+impl PartialEq for SchemaSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version && self.sources == other.sources
+    }
+}
+
+impl Eq for SchemaSet {}
+
+/// Every schema set a reader may pick from, by the file's `version`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Schemas {
+    /// Oldest version first.
+    sets: Vec<SchemaSet>,
+}
+
+impl Schemas {
+    /// The sets built into the binary.
+    pub fn embedded() -> Arc<Schemas> {
+        EMBEDDED_SCHEMAS.clone()
+    }
+
+    pub(crate) fn embedded_ref() -> &'static Schemas {
+        &EMBEDDED_SCHEMAS
+    }
+
+    /// The embedded sets with every schema file found in `dir` (`bookmarks_schema.json`,
+    /// `folder_schema.json`, `url_schema.json`) used in place of the built-in one, for
+    /// every version. Each file must be a self-contained draft 7 schema.
+    pub fn with_overrides(dir: &Path) -> Result<Schemas> {
+        if !dir.is_dir() {
+            return Err(anyhow!("schema directory not found: {}", dir.display()));
+        }
+        let mut overrides: [Option<(Value, PathBuf)>; 3] = Default::default();
+        for kind in SchemaKind::ALL {
+            let path = dir.join(kind.file_name());
+            if !path.exists() {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading schema {}", path.display()))?;
+            let source: Value = serde_json::from_str(&text)
+                .with_context(|| format!("parsing schema {}", path.display()))?;
+            overrides[kind.index()] = Some((source, path));
+        }
+        if overrides.iter().all(Option::is_none) {
+            return Err(anyhow!(
+                "schema directory {} holds none of bookmarks_schema.json, folder_schema.json, url_schema.json",
+                dir.display()
+            ));
+        }
+
+        let sets = Self::embedded()
+            .sets
+            .iter()
+            .map(|set| {
+                let mut sources = set.sources.clone();
+                let mut origins = set.origins.clone();
+                for kind in SchemaKind::ALL {
+                    if let Some((source, path)) = &overrides[kind.index()] {
+                        sources[kind.index()] = source.clone();
+                        origins[kind.index()] = path.display().to_string();
+                    }
+                }
+                SchemaSet::compile(set.version, sources, origins)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Schemas { sets })
+    }
+
+    /// The set for `version`; a missing or unknown version gets the newest set, whose
+    /// bookmarks schema then decides whether the version is acceptable.
+    pub fn for_version(&self, version: Option<i64>) -> &SchemaSet {
+        version
+            .and_then(|v| self.sets.iter().find(|set| set.version == v))
+            .or_else(|| self.sets.last())
+            .expect("at least one schema set")
+    }
+
+    /// The set for the document's top-level `version` field.
+    pub fn for_document(&self, document: &Value) -> &SchemaSet {
+        self.for_version(document.get("version").and_then(Value::as_i64))
+    }
+
+    /// Every set, oldest version first.
+    pub fn sets(&self) -> &[SchemaSet] {
+        &self.sets
+    }
+
+    /// Index into [`Schemas::sets`] of [`Schemas::for_version`]'s choice.
+    pub(crate) fn index_for_version(&self, version: Option<i64>) -> usize {
+        let chosen = self.for_version(version);
+        self.sets
+            .iter()
+            .position(|set| std::ptr::eq(set, chosen))
+            .expect("chosen from the sets")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn sets_are_picked_by_version_and_overridden_file_by_file() {
+        let embedded = Schemas::embedded();
+        assert_eq!(embedded.for_version(Some(1)).version(), 1);
+        // Unknown versions fall back to the newest set.
+        let newest = embedded.sets().last().expect("a set").version();
+        assert_eq!(embedded.for_version(Some(99)).version(), newest);
+        assert_eq!(
+            embedded.for_document(&serde_json::json!({})).version(),
+            newest
+        );
+
+        let dir = tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join("url_schema.json"),
+            r#"{"type": "object", "required": ["url", "x_vivaldi"]}"#,
+        )
+        .expect("write override");
+        let custom = Schemas::with_overrides(dir.path()).expect("load overrides");
+        let set = custom.for_version(Some(1));
+        assert_eq!(set.source(SchemaKind::Url)["required"][1], "x_vivaldi");
+        assert!(set.origin(SchemaKind::Url).ends_with("url_schema.json"));
+        assert_eq!(
+            set.source(SchemaKind::Folder),
+            embedded.for_version(Some(1)).source(SchemaKind::Folder)
+        );
+
+        std::fs::write(dir.path().join("folder_schema.json"), r#"{"type": 5}"#)
+            .expect("write override");
+        let err = Schemas::with_overrides(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("compiling schema"), "{err}");
+
+        let empty = tempdir().expect("tempdir");
+        assert!(Schemas::with_overrides(empty.path()).is_err());
+    }
+}
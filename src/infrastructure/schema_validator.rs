@@ -1,41 +1,32 @@
+use crate::infrastructure::json_locator::TextPosition;
+use crate::infrastructure::schema_catalog::{SchemaKind, SchemaSet, Schemas};
 use anyhow::{anyhow, Result};
-use jsonschema::{Draft, JSONSchema};
-use once_cell::sync::Lazy;
 use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 
-static BOOKMARKS_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
-    let schema_content = include_str!("../schemas/bookmarks_schema.json");
-    let schema: Value = serde_json::from_str(schema_content).expect("Invalid bookmarks schema");
-    JSONSchema::options()
-        .with_draft(Draft::Draft7)
-        .compile(&schema)
-        .expect("Failed to compile bookmarks schema")
-});
-
-static FOLDER_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
-    let schema_content = include_str!("../schemas/folder_schema.json");
-    let schema: Value = serde_json::from_str(schema_content).expect("Invalid folder schema");
-    JSONSchema::options()
-        .with_draft(Draft::Draft7)
-        .compile(&schema)
-        .expect("Failed to compile folder schema")
-});
-
-static URL_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
-    let schema_content = include_str!("../schemas/url_schema.json");
-    let schema: Value = serde_json::from_str(schema_content).expect("Invalid URL schema");
-    JSONSchema::options()
-        .with_draft(Draft::Draft7)
-        .compile(&schema)
-        .expect("Failed to compile URL schema")
-});
-
-/// Validate the entire bookmarks file against the bookmarks schema
+/// A jsonschema error prefixed with the JSON pointer of the value it is about.
+fn located_message(e: jsonschema::ValidationError) -> String {
+    let pointer = e.instance_path.to_string();
+    if pointer.is_empty() {
+        e.to_string()
+    } else {
+        format!("{pointer}: {e}")
+    }
+}
+
+/// The embedded set for items, whose version is not known on their own: the newest.
+fn newest_embedded() -> &'static SchemaSet {
+    Schemas::embedded_ref().for_version(None)
+}
+
+/// Validate the entire bookmarks file against the embedded bookmarks schema for its version
 pub fn validate_bookmarks_file(bookmarks: &Value) -> Result<()> {
-    match BOOKMARKS_SCHEMA.validate(bookmarks) {
+    let set = Schemas::embedded_ref().for_document(bookmarks);
+    match set.schema(SchemaKind::Bookmarks).validate(bookmarks) {
         Ok(()) => Ok(()),
         Err(errors) => {
-            let error_list: Vec<String> = errors.map(|e| e.to_string()).collect();
+            let error_list: Vec<String> = errors.map(located_message).collect();
             Err(anyhow!(
                 "Bookmarks file validation failed:\n{}",
                 error_list.join("\n")
@@ -44,12 +35,15 @@ pub fn validate_bookmarks_file(bookmarks: &Value) -> Result<()> {
     }
 }
 
-/// Validate a folder item against the folder schema
+/// Validate a folder item against the newest embedded folder schema
 pub fn validate_folder_item(folder: &Value) -> Result<()> {
-    match FOLDER_SCHEMA.validate(folder) {
+    match newest_embedded()
+        .schema(SchemaKind::Folder)
+        .validate(folder)
+    {
         Ok(()) => Ok(()),
         Err(errors) => {
-            let error_list: Vec<String> = errors.map(|e| e.to_string()).collect();
+            let error_list: Vec<String> = errors.map(located_message).collect();
             Err(anyhow!(
                 "Folder item validation failed:\n{}",
                 error_list.join("\n")
@@ -58,12 +52,12 @@ pub fn validate_folder_item(folder: &Value) -> Result<()> {
     }
 }
 
-/// Validate a URL item against the URL schema
+/// Validate a URL item against the newest embedded URL schema
 pub fn validate_url_item(url_item: &Value) -> Result<()> {
-    match URL_SCHEMA.validate(url_item) {
+    match newest_embedded().schema(SchemaKind::Url).validate(url_item) {
         Ok(()) => Ok(()),
         Err(errors) => {
-            let error_list: Vec<String> = errors.map(|e| e.to_string()).collect();
+            let error_list: Vec<String> = errors.map(located_message).collect();
             Err(anyhow!(
                 "URL item validation failed:\n{}",
                 error_list.join("\n")
@@ -72,46 +66,197 @@ pub fn validate_url_item(url_item: &Value) -> Result<()> {
     }
 }
 
-/// Recursively validate all items in the bookmarks structure
-pub fn validate_all_bookmark_items(bookmarks: &Value) -> Result<()> {
-    if let Some(roots) = bookmarks.get("roots") {
-        if let Some(roots_obj) = roots.as_object() {
-            for (_root_name, root_folder) in roots_obj {
-                validate_bookmark_tree(root_folder)?;
-            }
+/// One schema error, located by JSON pointer into the validated document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    pub pointer: String,
+    /// The folder or URL item whose schema failed; `None` for the file schema.
+    pub node: Option<String>,
+    /// Root key and names down to the failing item, e.g. `/bookmark_bar/Dev/Rust`.
+    pub name_path: Option<String>,
+    /// Where the value at `pointer` starts in the source text, once located.
+    pub position: Option<TextPosition>,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut details: Vec<String> = self.name_path.iter().cloned().collect();
+        details.extend(self.position.map(|p| p.to_string()));
+        match (self.pointer.is_empty(), details.is_empty()) {
+            (true, true) => f.write_str(&self.message),
+            (true, false) => write!(f, "{}: {}", details.join(", "), self.message),
+            (false, true) => write!(f, "{}: {}", self.pointer, self.message),
+            (false, false) => write!(
+                f,
+                "{} ({}): {}",
+                self.pointer,
+                details.join(", "),
+                self.message
+            ),
         }
     }
+}
 
-    Ok(())
+/// Strict reading found schema violations; all of them are listed, not just the first.
+#[derive(Debug)]
+pub struct SchemaValidationFailed {
+    pub violations: Vec<SchemaViolation>,
 }
 
-/// Recursively validate a bookmark tree (folder with children)
-fn validate_bookmark_tree(node: &Value) -> Result<()> {
-    // Validate the current node
-    if let Some(node_type) = node.get("type") {
-        if let Some(type_str) = node_type.as_str() {
-            match type_str {
-                "folder" => {
-                    validate_folder_item(node)?;
-                    // Recursively validate children
-                    if let Some(children) = node.get("children") {
-                        if let Some(children_array) = children.as_array() {
-                            for child in children_array {
-                                validate_bookmark_tree(child)?;
-                            }
-                        }
-                    }
-                }
-                "url" => {
-                    validate_url_item(node)?;
-                }
-                _ => {
-                    // Unknown node types are permitted by higher-level business
-                    // validation; schema-validator should not reject them here.
-                    // Skip schema validation for unknown node types.
-                    return Ok(());
+impl SchemaValidationFailed {
+    /// Violations spelled out in the message; the rest are counted.
+    const SHOWN: usize = 20;
+}
+
+impl fmt::Display for SchemaValidationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "input failed schema validation ({} violation(s)):",
+            self.violations.len()
+        )?;
+        for v in self.violations.iter().take(Self::SHOWN) {
+            write!(f, "\n  {v}")?;
+        }
+        if self.violations.len() > Self::SHOWN {
+            write!(
+                f,
+                "\n  ... and {} more",
+                self.violations.len() - Self::SHOWN
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SchemaValidationFailed {}
+
+/// Fills in each violation's [`SchemaViolation::position`] from `found` (see
+/// [`locate`](crate::infrastructure::json_locator::locate)); a pointer that was not found,
+/// such as a missing property's, falls back to its item's position.
+pub fn set_positions(violations: &mut [SchemaViolation], found: &HashMap<String, TextPosition>) {
+    for v in violations {
+        v.position = found
+            .get(&v.pointer)
+            .or_else(|| v.node.as_ref().and_then(|n| found.get(n)))
+            .copied();
+    }
+}
+
+/// Pointers [`set_positions`] may look up for `violations`.
+pub fn violation_pointers(violations: &[SchemaViolation]) -> BTreeSet<String> {
+    violations
+        .iter()
+        .flat_map(|v| std::iter::once(v.pointer.clone()).chain(v.node.clone()))
+        .collect()
+}
+
+/// JSON pointers of the items with at least one violation; file-level violations name none.
+pub fn failing_nodes(violations: &[SchemaViolation]) -> BTreeSet<String> {
+    violations.iter().filter_map(|v| v.node.clone()).collect()
+}
+
+/// Schema errors of one folder or URL item at `pointer` (named `name_path`) under `set`;
+/// other node types have no schema.
+pub fn item_violations(
+    set: &SchemaSet,
+    node: &Value,
+    pointer: &str,
+    name_path: &str,
+) -> Vec<SchemaViolation> {
+    let schema = match node.get("type").and_then(Value::as_str) {
+        Some("folder") => set.schema(SchemaKind::Folder),
+        Some("url") => set.schema(SchemaKind::Url),
+        _ => return Vec::new(),
+    };
+    match schema.validate(node) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|e| SchemaViolation {
+                pointer: format!("{pointer}{}", e.instance_path),
+                node: Some(pointer.to_string()),
+                name_path: Some(name_path.to_string()),
+                position: None,
+                message: e.to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Every schema error in the document under the embedded set for its version: the file
+/// schema first, then each item in document order. Unlike [`validate_bookmarks_file`] and
+/// [`validate_all_bookmark_items`], it does not stop at the first failure.
+pub fn schema_violations(bookmarks: &Value) -> Vec<SchemaViolation> {
+    schema_violations_with(Schemas::embedded_ref().for_document(bookmarks), bookmarks)
+}
+
+/// [`schema_violations`] under `set`.
+pub fn schema_violations_with(set: &SchemaSet, bookmarks: &Value) -> Vec<SchemaViolation> {
+    let mut out: Vec<SchemaViolation> = match set.schema(SchemaKind::Bookmarks).validate(bookmarks)
+    {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|e| SchemaViolation {
+                pointer: e.instance_path.to_string(),
+                node: None,
+                name_path: None,
+                position: None,
+                message: e.to_string(),
+            })
+            .collect(),
+    };
+    let Some(roots_obj) = bookmarks.get("roots").and_then(Value::as_object) else {
+        return out;
+    };
+    let mut stack: Vec<(String, String, &Value)> = roots_obj
+        .iter()
+        .rev()
+        .map(|(key, root)| {
+            let escaped = key.replace('~', "~0").replace('/', "~1");
+            (format!("/roots/{escaped}"), format!("/{key}"), root)
+        })
+        .collect();
+    while let Some((pointer, name_path, node)) = stack.pop() {
+        out.extend(item_violations(set, node, &pointer, &name_path));
+        if node.get("type").and_then(Value::as_str) != Some("folder") {
+            continue;
+        }
+        if let Some(children) = node.get("children").and_then(Value::as_array) {
+            for (i, child) in children.iter().enumerate().rev() {
+                let name = child.get("name").and_then(Value::as_str).unwrap_or("");
+                stack.push((
+                    format!("{pointer}/children/{i}"),
+                    format!("{name_path}/{name}"),
+                    child,
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// Validate every item in the bookmarks structure, walking it with an explicit stack so
+/// deeply nested input cannot overflow the call stack.
+pub fn validate_all_bookmark_items(bookmarks: &Value) -> Result<()> {
+    let Some(roots_obj) = bookmarks.get("roots").and_then(Value::as_object) else {
+        return Ok(());
+    };
+
+    // Pre-order, so the first failure reported is the first one in the file.
+    let mut stack: Vec<&Value> = roots_obj.values().rev().collect();
+    while let Some(node) = stack.pop() {
+        match node.get("type").and_then(Value::as_str) {
+            Some("folder") => {
+                validate_folder_item(node)?;
+                if let Some(children) = node.get("children").and_then(Value::as_array) {
+                    stack.extend(children.iter().rev());
                 }
             }
+            Some("url") => validate_url_item(node)?,
+            // Unknown node types are permitted by higher-level business validation;
+            // neither they nor their children are schema items.
+            _ => {}
         }
     }
 
@@ -1,10 +1,16 @@
+use crate::infrastructure::json_locator::locate;
+use crate::infrastructure::read_options::{ReadOptions, SchemaMode};
 use crate::infrastructure::schema_validator::{
-    validate_all_bookmark_items, validate_bookmarks_file,
+    schema_violations_with, set_positions, violation_pointers, SchemaValidationFailed,
+    SchemaViolation,
 };
+use crate::usecase::normalize::stream::{load_bookmarks, LoadedBookmarks};
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
 use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
 use tokio::{fs, process, time};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -22,7 +28,7 @@ pub struct BookmarksFileDto {
     pub extra: BTreeMap<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct BookmarkNodeDto {
     #[serde(rename = "type")]
     pub node_type: String,
@@ -56,18 +62,295 @@ pub struct BookmarkNodeDto {
 
     #[serde(flatten)]
     pub extra: BTreeMap<String, Value>,
+
+    /// Set on a node kept as read whose `type` did not fit: the value read, or `None` when
+    /// it had none. Written in place of `node_type`.
+    #[serde(skip)]
+    pub raw_type: Option<Option<Value>>,
+}
+
+/// Field for field what `#[derive(Serialize)]` would write, except that `raw_type`, when
+/// set, decides the `type` entry.
+impl Serialize for BookmarkNodeDto {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        fn entry<M: SerializeMap, T: Serialize>(
+            map: &mut M,
+            key: &str,
+            value: &Option<T>,
+        ) -> Result<(), M::Error> {
+            match value {
+                Some(value) => map.serialize_entry(key, value),
+                None => Ok(()),
+            }
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        match &self.raw_type {
+            None => map.serialize_entry("type", &self.node_type)?,
+            Some(raw) => entry(&mut map, "type", raw)?,
+        }
+        entry(&mut map, "name", &self.name)?;
+        entry(&mut map, "url", &self.url)?;
+        if !self.children.is_empty() {
+            map.serialize_entry("children", &self.children)?;
+        }
+        entry(&mut map, "date_added", &self.date_added)?;
+        entry(&mut map, "date_modified", &self.date_modified)?;
+        entry(&mut map, "date_last_used", &self.date_last_used)?;
+        entry(&mut map, "visit_count", &self.visit_count)?;
+        entry(&mut map, "guid", &self.guid)?;
+        entry(&mut map, "id", &self.id)?;
+        entry(&mut map, "source", &self.source)?;
+        entry(&mut map, "show_icon", &self.show_icon)?;
+        for (key, value) in &self.extra {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Fields of a node, `children` aside, whose values the DTO cannot hold: `type` when it is
+/// missing or not a string, any other typed field of the wrong JSON type.
+pub fn misfit_fields(node: &Map<String, Value>) -> Vec<&'static str> {
+    const TEXT: [&str; 8] = [
+        "name",
+        "url",
+        "date_added",
+        "date_modified",
+        "date_last_used",
+        "guid",
+        "id",
+        "source",
+    ];
+    let mut out = Vec::new();
+    if !node.get("type").is_some_and(Value::is_string) {
+        out.push("type");
+    }
+    let fits =
+        |key: &str, ok: fn(&Value) -> bool| node.get(key).is_none_or(|v| v.is_null() || ok(v));
+    out.extend(TEXT.into_iter().filter(|key| !fits(key, Value::is_string)));
+    if !fits("visit_count", Value::is_i64) {
+        out.push("visit_count");
+    }
+    if !fits("show_icon", Value::is_boolean) {
+        out.push("show_icon");
+    }
+    out
+}
+
+/// Converts a node whose `misfits` (see [`misfit_fields`]) do not fit the DTO, keeping
+/// their raw values in `raw_type` and `extra` so the node is written back as read.
+pub fn node_as_read(
+    mut node: Map<String, Value>,
+    misfits: &[&str],
+) -> Result<BookmarkNodeDto, serde_json::Error> {
+    let raw_type = misfits.contains(&"type").then(|| node.remove("type"));
+    let kept: Vec<(String, Value)> = misfits
+        .iter()
+        .filter(|key| **key != "type")
+        .filter_map(|key| node.remove_entry(*key))
+        .collect();
+    if raw_type.is_some() {
+        // Only `raw_type` is written back, but the DTO still needs a `type` to deserialize.
+        node.insert("type".to_string(), Value::String(String::new()));
+    }
+    let mut dto: BookmarkNodeDto = serde_json::from_value(Value::Object(node))?;
+    dto.raw_type = raw_type;
+    dto.extra.extend(kept);
+    Ok(dto)
+}
+
+/// The violation reporting a node at `pointer` kept as read because of its `misfits`.
+pub fn misfit_violation(pointer: String, name_path: String, misfits: &[&str]) -> SchemaViolation {
+    SchemaViolation {
+        message: format!(
+            "does not fit the bookmarks model ({}); kept as read",
+            misfits.join(", ")
+        ),
+        node: Some(pointer.clone()),
+        pointer,
+        name_path: Some(name_path),
+        position: None,
+    }
+}
+
+/// Every node of `bookmarks` with [`misfit_fields`], in document order, as violations.
+pub fn model_misfits(bookmarks: &Value) -> Vec<SchemaViolation> {
+    let mut out = Vec::new();
+    let Some(roots) = bookmarks.get("roots").and_then(Value::as_object) else {
+        return out;
+    };
+    let mut stack: Vec<(String, String, &Value)> = roots
+        .iter()
+        .rev()
+        .map(|(key, root)| {
+            let escaped = key.replace('~', "~0").replace('/', "~1");
+            (format!("/roots/{escaped}"), format!("/{key}"), root)
+        })
+        .collect();
+    while let Some((pointer, name_path, node)) = stack.pop() {
+        let Some(fields) = node.as_object() else {
+            continue;
+        };
+        if let Some(children) = fields.get("children").and_then(Value::as_array) {
+            for (i, child) in children.iter().enumerate().rev() {
+                let name = child.get("name").and_then(Value::as_str).unwrap_or("");
+                stack.push((
+                    format!("{pointer}/children/{i}"),
+                    format!("{name_path}/{name}"),
+                    child,
+                ));
+            }
+        }
+        let misfits = misfit_fields(fields);
+        if !misfits.is_empty() {
+            out.push(misfit_violation(pointer, name_path, &misfits));
+        }
+    }
+    out
+}
+
+/// Converts `bookmarks`, keeping every node with [`misfit_fields`] as read.
+fn file_as_read(mut bookmarks: Value) -> Result<BookmarksFileDto> {
+    let roots = bookmarks
+        .as_object_mut()
+        .and_then(|file| file.remove("roots"));
+    let mut dto: BookmarksFileDto = serde_json::from_value(bookmarks)?;
+    match roots {
+        Some(Value::Object(roots)) => {
+            for (key, root) in roots {
+                dto.roots.insert(key, node_as_read_deep(root)?);
+            }
+        }
+        Some(other) => dto.roots = serde_json::from_value(other)?,
+        None => {}
+    }
+    Ok(dto)
+}
+
+/// [`node_as_read`] for `node` and its subtree; nesting is bounded by the parser.
+fn node_as_read_deep(node: Value) -> Result<BookmarkNodeDto> {
+    let Value::Object(mut fields) = node else {
+        return Ok(serde_json::from_value(node)?);
+    };
+    let children = match fields.remove("children") {
+        Some(Value::Array(children)) => children,
+        Some(other) => {
+            fields.insert("children".to_string(), other);
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    let misfits = misfit_fields(&fields);
+    let mut dto = node_as_read(fields, &misfits)?;
+    dto.children = children
+        .into_iter()
+        .map(node_as_read_deep)
+        .collect::<Result<_>>()?;
+    Ok(dto)
 }
 
 pub async fn read_bookmarks_file(path: &str) -> Result<BookmarksFileDto> {
+    read_bookmarks_file_with(path, &ReadOptions::default()).await
+}
+
+/// Reads and validates a Bookmarks file, rejecting input over any of `options`' limits
+/// before it is schema-checked or converted. Violations tolerated by
+/// [`SchemaMode::Lenient`] are dropped; use [`read_bookmarks_checked`] to see them.
+pub async fn read_bookmarks_file_with(
+    path: &str,
+    options: &ReadOptions,
+) -> Result<BookmarksFileDto> {
+    let (dto, _) = read_bookmarks_checked(path, options).await?;
+    Ok(dto)
+}
+
+/// Reads a Bookmarks file under `options.schema`: strict fails with
+/// [`SchemaValidationFailed`] listing every violation, lenient returns them next to the
+/// file, off checks nothing. The file's `version` picks the set in `options.schemas`.
+/// Violations carry their line and column in `path`. Lenient reads also keep nodes that do
+/// not fit the bookmarks model as read and report them; the other modes fail on them.
+pub async fn read_bookmarks_checked(
+    path: &str,
+    options: &ReadOptions,
+) -> Result<(BookmarksFileDto, Vec<SchemaViolation>)> {
+    let raw_value = read_bookmarks_value(path, options).await?;
+
+    let mut violations = match options.schema {
+        SchemaMode::Strict | SchemaMode::Lenient => {
+            schema_violations_with(options.schemas.for_document(&raw_value), &raw_value)
+        }
+        SchemaMode::Off => Vec::new(),
+    };
+    if options.schema == SchemaMode::Lenient {
+        violations.extend(model_misfits(&raw_value));
+    }
+    if !violations.is_empty() {
+        // Rare enough that reading the file a second time beats keeping the text around.
+        let raw = fs::read(path).await?;
+        locate_violations(raw.as_slice(), &mut violations)?;
+    }
+    if options.schema == SchemaMode::Strict && !violations.is_empty() {
+        return Err(SchemaValidationFailed { violations }.into());
+    }
+
+    let dto: BookmarksFileDto = if options.schema == SchemaMode::Lenient {
+        file_as_read(raw_value)?
+    } else {
+        serde_json::from_value(raw_value)?
+    };
+    Ok((dto, violations))
+}
+
+/// Sets each violation's line and column by scanning the source text in `reader`.
+pub fn locate_violations<R: BufRead>(reader: R, violations: &mut [SchemaViolation]) -> Result<()> {
+    let found = locate(reader, &violation_pointers(violations))?;
+    set_positions(violations, &found);
+    Ok(())
+}
+
+/// Reads and parses `path` within `options`, without any schema validation.
+pub async fn read_bookmarks_value(path: &str, options: &ReadOptions) -> Result<Value> {
+    options.check_file_size(fs::metadata(path).await?.len())?;
     let raw = fs::read_to_string(path).await?;
-    let raw_value: Value = serde_json::from_str(&raw)?;
+    let raw_value: Value = serde_json::from_str(&raw).map_err(explain_parse_error)?;
+    drop(raw);
+    options.check_document(&raw_value)?;
+    Ok(raw_value)
+}
 
-    // Run schema validation for top-level and all bookmark items.
-    validate_bookmarks_file(&raw_value)?;
-    validate_all_bookmark_items(&raw_value)?;
+/// Streams `path` into a node arena without holding the whole document in memory.
+/// Schema violations, raised or tolerated, carry their line and column in `path`.
+/// Blocking; call it from `spawn_blocking`.
+pub fn read_bookmarks_streaming(path: &str, options: &ReadOptions) -> Result<LoadedBookmarks> {
+    let file = std::fs::File::open(path)?;
+    options.check_file_size(file.metadata()?.len())?;
+    let relocate = || std::fs::File::open(path).map(BufReader::new);
+    match load_bookmarks(BufReader::new(file), options) {
+        Ok(mut loaded) => {
+            if !loaded.schema_violations.is_empty() {
+                locate_violations(relocate()?, &mut loaded.schema_violations)?;
+            }
+            Ok(loaded)
+        }
+        Err(mut err) => {
+            if let Some(failed) = err.downcast_mut::<SchemaValidationFailed>() {
+                locate_violations(relocate()?, &mut failed.violations)?;
+            }
+            Err(err)
+        }
+    }
+}
 
-    let dto: BookmarksFileDto = serde_json::from_value(raw_value)?;
-    Ok(dto)
+/// serde_json's nesting guard fires before any limit can be checked; say what it means.
+pub(crate) fn explain_parse_error(e: serde_json::Error) -> anyhow::Error {
+    let explained = e.to_string().starts_with("recursion limit exceeded");
+    let err = anyhow::Error::new(e);
+    if explained {
+        err.context("input nests deeper than the JSON parser allows (128 levels, about 62 folders)")
+    } else {
+        err
+    }
 }
 
 pub async fn write_bookmarks_file(path: &str, dto: &BookmarksFileDto) -> Result<()> {
@@ -1,13 +1,31 @@
+use crate::infrastructure::config_file::read_normalize_config;
 use crate::infrastructure::event_ndjson::spawn_ndjson_printer;
-use crate::infrastructure::scc_kosaraju::KosarajuSccDetector;
-use crate::infrastructure::serde_json_adapter::{read_bookmarks_file, write_bookmarks_file};
+use crate::infrastructure::read_options::ReadOptions;
+use crate::infrastructure::scc_selection::SccSelection;
+use crate::infrastructure::schema_catalog::{SchemaKind, Schemas};
+use crate::infrastructure::schema_validator::{failing_nodes, SchemaValidationFailed};
+use crate::infrastructure::serde_json_adapter::{
+    read_bookmarks_checked, read_bookmarks_file_with, read_bookmarks_streaming,
+    read_bookmarks_value, write_bookmarks_file,
+};
 use crate::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
+use crate::usecase::conservation::verify_conservation;
 use crate::usecase::event::AppEvent;
-use crate::usecase::normalize::normalize_bookmarks;
-use crate::usecase::validate::validate_bookmarks;
+use crate::usecase::lint::{lint_document, LintOptions};
+use crate::usecase::normalize::idempotence;
+use crate::usecase::normalize::provenance::Provenance;
+use crate::usecase::normalize::removals::RemovalLimitError;
+use crate::usecase::normalize::{
+    normalize_bookmarks_with_report, normalize_loaded_with_report, NormalizeOptions,
+};
+use crate::usecase::validate::{validate_value_with, ValidationFailed};
 use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
 use std::env;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc;
 
@@ -26,7 +44,15 @@ pub async fn run_with_args(args: &[String]) -> Result<()> {
             emit_events,
             backup,
             dry_run,
+            report,
+            provenance,
+            streaming,
+            check_idempotent,
+            policy,
         } => {
+            let (mut options, scc, read) = load_normalize_options(&policy).await?;
+            options.record_provenance = provenance.is_some();
+
             if !dry_run && is_same_file(&input, &output) {
                 if !backup {
                     return Err(anyhow!(
@@ -45,14 +71,101 @@ pub async fn run_with_args(args: &[String]) -> Result<()> {
                 None
             };
 
-            let dto = read_bookmarks_file(&input)
+            let mut recheck =
+                check_idempotent.then(|| (options.clone(), scc, read.schemas.clone()));
+
+            // The pipeline is CPU-bound; run it off the async workers so the event printer
+            // keeps draining the channel.
+            let runtime = tokio::runtime::Handle::current();
+            // Lenient reading returns the schema violations it tolerated; the nodes they
+            // name are left as read.
+            let (violations, result) = if streaming {
+                let source = input.clone();
+                tokio::task::spawn_blocking(move || {
+                    let loaded = read_bookmarks_streaming(&source, &read)
+                        .with_context(|| format!("reading input bookmarks JSON: {source}"))?;
+                    let violations = loaded.schema_violations().to_vec();
+                    options.preserve = failing_nodes(&violations);
+                    let canonicalizer = DefaultUrlCanonicalizer;
+                    let result = runtime.block_on(normalize_loaded_with_report(
+                        loaded,
+                        &canonicalizer,
+                        &scc,
+                        &options,
+                        Some(tx),
+                    ));
+                    anyhow::Ok((violations, result))
+                })
                 .await
-                .with_context(|| format!("reading input bookmarks JSON: {input}"))?;
-
-            let canonicalizer = DefaultUrlCanonicalizer;
-            let scc = KosarajuSccDetector;
+                .context("normalize task failed")??
+            } else {
+                let (dto, violations) = read_bookmarks_checked(&input, &read)
+                    .await
+                    .with_context(|| format!("reading input bookmarks JSON: {input}"))?;
+                options.preserve = failing_nodes(&violations);
+                let result = tokio::task::spawn_blocking(move || {
+                    let canonicalizer = DefaultUrlCanonicalizer;
+                    runtime.block_on(normalize_bookmarks_with_report(
+                        dto,
+                        &canonicalizer,
+                        &scc,
+                        &options,
+                        Some(tx),
+                    ))
+                })
+                .await
+                .context("normalize task failed")?;
+                (violations, result)
+            };
+            for violation in violations.iter() {
+                eprintln!("warning: schema: {violation}");
+            }
+            // Let the printer flush every event, including the one explaining a failure.
+            if let Some(handle) = printer {
+                handle.await.ok();
+            }
+            let (out, outcome) = result.map_err(|e| {
+                if e.downcast_ref::<RemovalLimitError>().is_some() {
+                    e.context("removal guardrail tripped; nothing was written (rerun with --force to apply)")
+                } else {
+                    e
+                }
+            })?;
+            let stats = &outcome.stats;
+            if !violations.is_empty() {
+                eprintln!(
+                    "schema: {} violation(s) tolerated; {} node(s) left as read",
+                    violations.len(),
+                    stats.nodes_preserved
+                );
+            }
+            if let Some((options, _, _)) = recheck.as_mut() {
+                options.preserve = failing_nodes(&violations);
+            }
 
-            let (out, stats) = normalize_bookmarks(dto, &canonicalizer, &scc, Some(tx)).await?;
+            // Checked before anything is written, so an oscillating run leaves no output.
+            let out = match recheck {
+                Some((options, scc, schemas)) => {
+                    let runtime = tokio::runtime::Handle::current();
+                    let out = tokio::task::spawn_blocking(move || {
+                        let canonicalizer = DefaultUrlCanonicalizer;
+                        runtime
+                            .block_on(idempotence::check_idempotent_with(
+                                &out,
+                                &canonicalizer,
+                                &scc,
+                                &options,
+                                &schemas,
+                            ))
+                            .map(|()| out)
+                    })
+                    .await
+                    .context("idempotence task failed")??;
+                    eprintln!("ok: normalizing the output again changes nothing");
+                    out
+                }
+                None => out,
+            };
 
             if !dry_run {
                 write_bookmarks_file(&output, &out)
@@ -60,40 +173,267 @@ pub async fn run_with_args(args: &[String]) -> Result<()> {
                     .with_context(|| format!("writing output bookmarks JSON: {output}"))?;
             }
 
-            if let Some(handle) = printer {
-                handle.await.ok();
+            if let Some(path) = report.as_deref() {
+                let json = serde_json::to_string_pretty(&outcome)?;
+                tokio::fs::write(path, json)
+                    .await
+                    .with_context(|| format!("writing report: {path}"))?;
+            }
+
+            if let (Some(path), Some(records)) =
+                (provenance.as_deref(), outcome.provenance.as_ref())
+            {
+                let json = serde_json::to_string(records)?;
+                tokio::fs::write(path, json)
+                    .await
+                    .with_context(|| format!("writing provenance: {path}"))?;
             }
 
             eprintln!(
-                "summary: folders_seen={} folders_merged={} urls_seen={} urls_deduped={} folders_pruned={}",
+                "summary: folders_seen={} identity_edges_cut={} folders_merged={} urls_seen={} urls_deduped={} folders_pruned={}",
                 stats.folders_seen,
+                stats.identity_edges_cut,
                 stats.folders_merged,
                 stats.urls_seen,
                 stats.urls_deduped,
                 stats.folders_pruned
             );
+            if let Some(bytes) = stats.peak_memory_bytes {
+                eprintln!("peak memory: {:.1} MiB", bytes as f64 / (1024.0 * 1024.0));
+            }
 
             Ok(())
         }
 
-        Cli::BookmarksValidate { input } => {
-            let dto = read_bookmarks_file(&input)
+        Cli::BookmarksValidate {
+            input,
+            limits,
+            format,
+            schema_dir,
+        } => {
+            let mut read = limits.apply_to(ReadOptions::default())?;
+            if let Some(dir) = schema_dir.as_deref() {
+                read.schemas = schema_overrides(dir)?;
+            }
+            let format: ReportFormat = match format.as_deref() {
+                Some(v) => v.parse().context("parsing --format")?,
+                None => ReportFormat::default(),
+            };
+            let document = read_bookmarks_value(&input, &read)
                 .await
                 .with_context(|| format!("reading input bookmarks JSON: {input}"))?;
 
-            let canonicalizer = DefaultUrlCanonicalizer;
-            validate_bookmarks(&dto, &canonicalizer)
-                .with_context(|| format!("validating bookmarks: {input}"))?;
+            let schemas = read.schemas.clone();
+            let mut report = tokio::task::spawn_blocking(move || {
+                let canonicalizer = DefaultUrlCanonicalizer;
+                validate_value_with(document, &canonicalizer, &schemas)
+            })
+            .await
+            .context("validate task failed")?;
+            if !report.violations.is_empty() {
+                let raw = tokio::fs::read(&input)
+                    .await
+                    .with_context(|| format!("locating violations in {input}"))?;
+                report.locate(raw.as_slice())?;
+            }
+
+            if format == ReportFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            if !report.is_valid() {
+                return Err(anyhow::Error::new(ValidationFailed(report))
+                    .context(format!("validating bookmarks: {input}")));
+            }
 
             // Emit an explicit schema validation success message for e2e tests.
             eprintln!("schema validation passed");
             eprintln!("ok: invariants validated");
             Ok(())
         }
+        Cli::BookmarksLint {
+            input,
+            config,
+            limits,
+            format,
+        } => {
+            let mut read = ReadOptions::default();
+            let mut options = LintOptions::default();
+            if let Some(path) = config.as_deref() {
+                let cfg = read_normalize_config(path)
+                    .await
+                    .with_context(|| format!("reading config: {path}"))?;
+                cfg.apply_lint(&mut options)?;
+                cfg.apply_limits(&mut read);
+            }
+            let read = limits.apply_to(read)?;
+            let format: ReportFormat = match format.as_deref() {
+                Some(v) => v.parse().context("parsing --format")?,
+                None => ReportFormat::default(),
+            };
+            let document = read_bookmarks_value(&input, &read)
+                .await
+                .with_context(|| format!("reading input bookmarks JSON: {input}"))?;
+
+            let report = tokio::task::spawn_blocking(move || {
+                let canonicalizer = DefaultUrlCanonicalizer;
+                lint_document(&document, &canonicalizer, &options)
+            })
+            .await
+            .context("lint task failed")?;
+
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                ReportFormat::Text if !report.warnings.is_empty() => {
+                    println!("{}", report.to_text())
+                }
+                ReportFormat::Text => {}
+            }
+            let counts: Vec<String> = report
+                .counts
+                .iter()
+                .map(|(rule, n)| format!("{rule}: {n}"))
+                .collect();
+            if counts.is_empty() {
+                eprintln!("lint: 0 warning(s)");
+            } else {
+                eprintln!(
+                    "lint: {} warning(s) ({})",
+                    report.warnings.len(),
+                    counts.join(", ")
+                );
+            }
+            Ok(())
+        }
+        Cli::BookmarksVerifyConservation {
+            input,
+            output,
+            provenance,
+            config,
+            schema,
+            schema_dir,
+        } => {
+            // The run's folder aliases decide which merge key each renamed group ends up under.
+            let mut options = NormalizeOptions::default();
+            let mut read = ReadOptions::default();
+            if let Some(path) = config.as_deref() {
+                let cfg = read_normalize_config(path)
+                    .await
+                    .with_context(|| format!("reading config: {path}"))?;
+                cfg.apply_to(&mut options)?;
+                cfg.apply_schema(&mut read)?;
+            }
+            if let Some(mode) = schema.as_deref() {
+                read.schema = mode.parse().context("parsing --schema")?;
+            }
+            if let Some(dir) = schema_dir.as_deref() {
+                read.schemas = schema_overrides(dir)?;
+            }
+            let before = read_bookmarks_file_with(&input, &read)
+                .await
+                .with_context(|| format!("reading input bookmarks JSON: {input}"))?;
+            let after = read_bookmarks_file_with(&output, &read)
+                .await
+                .with_context(|| format!("reading output bookmarks JSON: {output}"))?;
+            let provenance: Option<Provenance> = match provenance.as_deref() {
+                Some(path) => {
+                    let raw = tokio::fs::read_to_string(path)
+                        .await
+                        .with_context(|| format!("reading provenance: {path}"))?;
+                    Some(
+                        serde_json::from_str(&raw)
+                            .with_context(|| format!("parsing provenance: {path}"))?,
+                    )
+                }
+                None => None,
+            };
+
+            let report = tokio::task::spawn_blocking(move || {
+                let canonicalizer = DefaultUrlCanonicalizer;
+                verify_conservation(
+                    &before,
+                    &after,
+                    provenance.as_ref(),
+                    &options.folder_name,
+                    &canonicalizer,
+                )
+            })
+            .await
+            .context("verify task failed")?;
+
+            eprintln!(
+                "urls: {} in, {} kept, {} merged; folders: {} in, {} kept, {} merged, {} pruned",
+                report.urls_in,
+                report.urls_kept,
+                report.urls_merged,
+                report.folders_in,
+                report.folders_kept,
+                report.folders_merged,
+                report.folders_pruned
+            );
+            if !report.is_conserved() {
+                return Err(anyhow!(
+                    "{} node(s) of {input} are not accounted for in {output}:\n{}",
+                    report.violations.len(),
+                    report.violations.join("\n")
+                ));
+            }
+            eprintln!("ok: every input node is accounted for");
+            Ok(())
+        }
+        Cli::BookmarksSchemaPrint {
+            kind,
+            version,
+            schema_dir,
+        } => {
+            let kind: Option<SchemaKind> = kind
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .context("parsing schema kind")?;
+            let version: Option<i64> = version
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .context("parsing --version")?;
+            let schemas = match schema_dir.as_deref() {
+                Some(dir) => schema_overrides(dir)?,
+                None => Schemas::embedded(),
+            };
+            let set = schemas.for_version(version);
+            if let Some(v) = version.filter(|v| *v != set.version()) {
+                let known: Vec<String> = schemas
+                    .sets()
+                    .iter()
+                    .map(|s| s.version().to_string())
+                    .collect();
+                return Err(anyhow!(
+                    "no schema set for version {v} (known: {})",
+                    known.join(", ")
+                ));
+            }
+
+            let kinds = kind.map_or(SchemaKind::ALL.to_vec(), |k| vec![k]);
+            for k in &kinds {
+                eprintln!("{k}: {}", set.origin(*k));
+            }
+            let printed = match kind {
+                Some(k) => set.source(k).clone(),
+                None => serde_json::Value::Object(
+                    kinds
+                        .iter()
+                        .map(|k| (k.to_string(), set.source(*k).clone()))
+                        .collect(),
+                ),
+            };
+            println!("{}", serde_json::to_string_pretty(&printed)?);
+            Ok(())
+        }
     }
 }
 
+/// One variant per `bookmarks <subcommand>`.
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum Cli {
     BookmarksNormalize {
         input: String,
@@ -101,17 +441,50 @@ enum Cli {
         emit_events: bool,
         backup: bool,
         dry_run: bool,
+        report: Option<String>,
+        provenance: Option<String>,
+        streaming: bool,
+        check_idempotent: bool,
+        policy: Box<PolicyArgs>,
     },
     BookmarksValidate {
         input: String,
+        limits: LimitArgs,
+        format: Option<String>,
+        schema_dir: Option<String>,
+    },
+    BookmarksLint {
+        input: String,
+        config: Option<String>,
+        limits: LimitArgs,
+        format: Option<String>,
+    },
+    BookmarksVerifyConservation {
+        input: String,
+        output: String,
+        provenance: Option<String>,
+        config: Option<String>,
+        schema: Option<String>,
+        schema_dir: Option<String>,
+    },
+    BookmarksSchemaPrint {
+        kind: Option<String>,
+        version: Option<String>,
+        schema_dir: Option<String>,
     },
 }
 
 impl Cli {
     fn parse(args: &[String]) -> Result<Self> {
         // Expected:
-        // <bin> bookmarks normalize --in/--input <input.json> --out/--output <output.json> [--emit-events] [--backup]
-        // <bin> bookmarks validate --in/--input <input.json>
+        // <bin> bookmarks normalize --in/--input <input.json> --out/--output <output.json> [--emit-events] [--backup] [--report <report.json>] [--provenance <provenance.json>] [--streaming] [--check-idempotent]
+        //     [--config <config.json>] [--folder-winner <rules>] [--folder-name <rule>] [--folder-merge <preset>] [--url-winner <rules>] [--url-merge <preset>] [--scc <algorithm>] [--scc-cross-check <algorithm>] [--duplicate-identities <mode>] [--max-merge-iterations <n>]
+        //     [--max-removed-urls <limit>] [--max-removed-folders <limit>] [--force] [--schema strict|lenient|off] [--schema-dir <dir>]
+        //     [--max-file-bytes <n>] [--max-nodes <n>] [--max-depth <n>] [--max-name-bytes <n>] [--max-url-bytes <n>]
+        // <bin> bookmarks validate --in/--input <input.json> [--format text|json] [--schema-dir <dir>] [limit flags as above]
+        // <bin> bookmarks lint --in/--input <input.json> [--config <config.json>] [--format text|json] [limit flags as above]
+        // <bin> bookmarks verify-conservation --in/--input <input.json> --out/--output <output.json> [--provenance <provenance.json>] [--config <config.json>] [--schema strict|lenient|off] [--schema-dir <dir>]
+        // <bin> bookmarks schema print [bookmarks|folder|url] [--version <n>] [--schema-dir <dir>]
         if args.len() < 3 {
             return Err(anyhow!(usage()));
         }
@@ -123,17 +496,26 @@ impl Cli {
         match args[2].as_str() {
             "normalize" => Self::parse_normalize(args),
             "validate" => Self::parse_validate(args),
+            "lint" => Self::parse_lint(args),
+            "verify-conservation" => Self::parse_verify_conservation(args),
+            "schema" => Self::parse_schema(args),
             "-h" | "--help" => Err(anyhow!(usage())),
             _ => Err(anyhow!(usage())),
         }
     }
 
     fn parse_normalize(args: &[String]) -> Result<Self> {
+        let help = &NORMALIZE_HELP;
         let mut input: Option<String> = None;
         let mut output: Option<String> = None;
         let mut emit_events = false;
         let mut backup = false;
         let mut dry_run = false;
+        let mut report: Option<String> = None;
+        let mut provenance: Option<String> = None;
+        let mut streaming = false;
+        let mut check_idempotent = false;
+        let mut policy = PolicyArgs::default();
 
         let mut i = 3;
         while i < args.len() {
@@ -155,18 +537,95 @@ impl Cli {
                 "--backup" => {
                     backup = true;
                 }
-                "-h" | "--help" => return Err(anyhow!(usage())),
-                other => return Err(anyhow!(format!("unknown arg: {other}\n\n{}", usage()))),
+                "--streaming" => {
+                    streaming = true;
+                }
+                "--check-idempotent" => {
+                    check_idempotent = true;
+                }
+                "--report" => {
+                    i += 1;
+                    report = args.get(i).cloned();
+                }
+                "--provenance" => {
+                    i += 1;
+                    provenance = args.get(i).cloned();
+                }
+                "--config" => {
+                    i += 1;
+                    policy.config = args.get(i).cloned();
+                }
+                "--folder-winner" => {
+                    i += 1;
+                    policy.folder_winner = args.get(i).cloned();
+                }
+                "--folder-name" => {
+                    i += 1;
+                    policy.folder_name = args.get(i).cloned();
+                }
+                "--folder-merge" => {
+                    i += 1;
+                    policy.folder_merge = args.get(i).cloned();
+                }
+                "--url-winner" => {
+                    i += 1;
+                    policy.url_winner = args.get(i).cloned();
+                }
+                "--url-merge" => {
+                    i += 1;
+                    policy.url_merge = args.get(i).cloned();
+                }
+                "--scc" => {
+                    i += 1;
+                    policy.scc = args.get(i).cloned();
+                }
+                "--scc-cross-check" => {
+                    i += 1;
+                    policy.scc_cross_check = args.get(i).cloned();
+                }
+                "--duplicate-identities" => {
+                    i += 1;
+                    policy.duplicate_identities = args.get(i).cloned();
+                }
+                "--max-merge-iterations" => {
+                    i += 1;
+                    policy.max_merge_iterations = args.get(i).cloned();
+                }
+                "--max-removed-urls" => {
+                    i += 1;
+                    policy.max_removed_urls = args.get(i).cloned();
+                }
+                "--max-removed-folders" => {
+                    i += 1;
+                    policy.max_removed_folders = args.get(i).cloned();
+                }
+                "--force" => {
+                    policy.force = true;
+                }
+                "--schema" => {
+                    i += 1;
+                    policy.schema = args.get(i).cloned();
+                }
+                "--schema-dir" => {
+                    i += 1;
+                    policy.schema_dir = args.get(i).cloned();
+                }
+                flag if LimitArgs::is_flag(flag) => {
+                    i += 1;
+                    policy.limits.set(flag, args.get(i).cloned());
+                }
+                "-h" | "--help" => return Err(anyhow!(help.text())),
+                other => return Err(anyhow!("unknown arg: {other}\n\n{}", help.text())),
             }
             i += 1;
         }
 
-        let input = input.ok_or_else(|| anyhow!(format!("missing --in/--input\n\n{}", usage())))?;
+        let input = input.ok_or_else(|| anyhow!("missing --in/--input\n\n{}", help.text()))?;
         let output = if dry_run {
             // dry-run mode doesn't require an output path
             output.unwrap_or_default()
         } else {
-            output.ok_or_else(|| anyhow!(format!("missing --out/--output\n\n{}", usage())))?
+            output.ok_or_else(|| anyhow!("missing --out/--output\n\n{}", help.text()))?
         };
 
         Ok(Cli::BookmarksNormalize {
@@ -175,11 +634,20 @@ impl Cli {
             emit_events,
             backup,
             dry_run,
+            report,
+            provenance,
+            streaming,
+            check_idempotent,
+            policy: Box::new(policy),
         })
     }
 
     fn parse_validate(args: &[String]) -> Result<Self> {
+        let help = &VALIDATE_HELP;
         let mut input: Option<String> = None;
+        let mut limits = LimitArgs::default();
+        let mut format: Option<String> = None;
+        let mut schema_dir: Option<String> = None;
 
         let mut i = 3;
         while i < args.len() {
@@ -188,20 +656,563 @@ impl Cli {
                     i += 1;
                     input = args.get(i).cloned();
                 }
-                "-h" | "--help" => return Err(anyhow!(usage())),
-                other => return Err(anyhow!(format!("unknown arg: {other}\n\n{}", usage()))),
+                "--format" => {
+                    i += 1;
+                    format = args.get(i).cloned();
+                }
+                "--schema-dir" => {
+                    i += 1;
+                    schema_dir = args.get(i).cloned();
+                }
+                flag if LimitArgs::is_flag(flag) => {
+                    i += 1;
+                    limits.set(flag, args.get(i).cloned());
+                }
+                "-h" | "--help" => return Err(anyhow!(help.text())),
+                other => return Err(anyhow!("unknown arg: {other}\n\n{}", help.text())),
+            }
+            i += 1;
+        }
+
+        let input = input.ok_or_else(|| anyhow!("missing --in/--input\n\n{}", help.text()))?;
+
+        Ok(Cli::BookmarksValidate {
+            input,
+            limits,
+            format,
+            schema_dir,
+        })
+    }
+
+    fn parse_lint(args: &[String]) -> Result<Self> {
+        let help = &LINT_HELP;
+        let mut input: Option<String> = None;
+        let mut config: Option<String> = None;
+        let mut limits = LimitArgs::default();
+        let mut format: Option<String> = None;
+
+        let mut i = 3;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--in" | "--input" => {
+                    i += 1;
+                    input = args.get(i).cloned();
+                }
+                "--config" => {
+                    i += 1;
+                    config = args.get(i).cloned();
+                }
+                "--format" => {
+                    i += 1;
+                    format = args.get(i).cloned();
+                }
+                flag if LimitArgs::is_flag(flag) => {
+                    i += 1;
+                    limits.set(flag, args.get(i).cloned());
+                }
+                "-h" | "--help" => return Err(anyhow!(help.text())),
+                other => return Err(anyhow!("unknown arg: {other}\n\n{}", help.text())),
+            }
+            i += 1;
+        }
+
+        let input = input.ok_or_else(|| anyhow!("missing --in/--input\n\n{}", help.text()))?;
+
+        Ok(Cli::BookmarksLint {
+            input,
+            config,
+            limits,
+            format,
+        })
+    }
+
+    fn parse_verify_conservation(args: &[String]) -> Result<Self> {
+        let help = &VERIFY_CONSERVATION_HELP;
+        let mut input: Option<String> = None;
+        let mut output: Option<String> = None;
+        let mut provenance: Option<String> = None;
+        let mut config: Option<String> = None;
+        let mut schema: Option<String> = None;
+        let mut schema_dir: Option<String> = None;
+
+        let mut i = 3;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--in" | "--input" => {
+                    i += 1;
+                    input = args.get(i).cloned();
+                }
+                "--out" | "--output" => {
+                    i += 1;
+                    output = args.get(i).cloned();
+                }
+                "--provenance" => {
+                    i += 1;
+                    provenance = args.get(i).cloned();
+                }
+                "--config" => {
+                    i += 1;
+                    config = args.get(i).cloned();
+                }
+                "--schema" => {
+                    i += 1;
+                    schema = args.get(i).cloned();
+                }
+                "--schema-dir" => {
+                    i += 1;
+                    schema_dir = args.get(i).cloned();
+                }
+                "-h" | "--help" => return Err(anyhow!(help.text())),
+                other => return Err(anyhow!("unknown arg: {other}\n\n{}", help.text())),
+            }
+            i += 1;
+        }
+
+        let input = input.ok_or_else(|| anyhow!("missing --in/--input\n\n{}", help.text()))?;
+        let output = output.ok_or_else(|| anyhow!("missing --out/--output\n\n{}", help.text()))?;
+
+        Ok(Cli::BookmarksVerifyConservation {
+            input,
+            output,
+            provenance,
+            config,
+            schema,
+            schema_dir,
+        })
+    }
+
+    fn parse_schema(args: &[String]) -> Result<Self> {
+        let help = &SCHEMA_PRINT_HELP;
+        if args.get(3).map(String::as_str) != Some("print") {
+            return Err(anyhow!(help.text()));
+        }
+        let mut kind: Option<String> = None;
+        let mut version: Option<String> = None;
+        let mut schema_dir: Option<String> = None;
+
+        let mut i = 4;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--version" => {
+                    i += 1;
+                    version = args.get(i).cloned();
+                }
+                "--schema-dir" => {
+                    i += 1;
+                    schema_dir = args.get(i).cloned();
+                }
+                "-h" | "--help" => return Err(anyhow!(help.text())),
+                flag if flag.starts_with('-') => {
+                    return Err(anyhow!("unknown arg: {flag}\n\n{}", help.text()))
+                }
+                other if kind.is_none() => kind = Some(other.to_string()),
+                other => return Err(anyhow!("unknown arg: {other}\n\n{}", help.text())),
             }
             i += 1;
         }
 
-        let input = input.ok_or_else(|| anyhow!(format!("missing --in/--input\n\n{}", usage())))?;
+        Ok(Cli::BookmarksSchemaPrint {
+            kind,
+            version,
+            schema_dir,
+        })
+    }
+}
 
-        Ok(Cli::BookmarksValidate { input })
+/// Help for one subcommand: its synopsis and the note sections that apply to it.
+struct CommandHelp {
+    synopsis: &'static str,
+    notes: &'static [&'static str],
+}
+
+impl CommandHelp {
+    /// Help for this subcommand alone, shown for its `--help` and argument errors.
+    fn text(&self) -> String {
+        let mut text = format!("Usage:\n{}", self.synopsis);
+        for note in self.notes.iter().chain([&EXIT_CODES]) {
+            text.push_str("\n\n");
+            text.push_str(note);
+        }
+        text
     }
 }
 
+const NORMALIZE_HELP: CommandHelp = CommandHelp {
+    synopsis: r#"  bookmarks normalize --in/--input <input.json> --out/--output <output.json>
+      [--emit-events] [--backup] [--report <report.json>] [--provenance <provenance.json>]
+      [--streaming] [--check-idempotent] [--config <config.json>]
+      [--folder-winner <rules>] [--folder-name <rule>] [--folder-merge winner|accumulate|fold]
+      [--url-winner <rules>] [--url-merge winner|fold]
+      [--scc kosaraju|tarjan|path-based] [--scc-cross-check <algorithm>]
+      [--duplicate-identities report|merge|separate] [--max-merge-iterations <n>]
+      [--max-removed-urls <limit>] [--max-removed-folders <limit>] [--force]
+      [--schema strict|lenient|off] [--schema-dir <dir>] [<limits>]"#,
+    notes: &[
+        EVENTS,
+        REPORT,
+        IDEMPOTENCE,
+        CONSERVATION,
+        MEMORY,
+        POLICIES,
+        GUARDRAILS,
+        SCHEMA,
+        LIMITS,
+        SAFETY,
+    ],
+};
+
+const VALIDATE_HELP: CommandHelp = CommandHelp {
+    synopsis: r#"  bookmarks validate --in/--input <input.json> [--format text|json] [--schema-dir <dir>]
+      [<limits>]"#,
+    notes: &[VALIDATION, SCHEMA, LIMITS],
+};
+
+const LINT_HELP: CommandHelp = CommandHelp {
+    synopsis: r#"  bookmarks lint --in/--input <input.json> [--config <config.json>] [--format text|json]
+      [<limits>]"#,
+    notes: &[LINT, LIMITS],
+};
+
+const VERIFY_CONSERVATION_HELP: CommandHelp = CommandHelp {
+    synopsis: r#"  bookmarks verify-conservation --in/--input <input.json> --out/--output <output.json>
+      [--provenance <provenance.json>] [--config <config.json>]
+      [--schema strict|lenient|off] [--schema-dir <dir>]"#,
+    notes: &[CONSERVATION, SCHEMA],
+};
+
+const SCHEMA_PRINT_HELP: CommandHelp = CommandHelp {
+    synopsis: r#"  bookmarks schema print [bookmarks|folder|url] [--version <n>] [--schema-dir <dir>]"#,
+    notes: &[SCHEMA],
+};
+
+const EVENTS: &str = r#"Events:
+  If --emit-events is set, NDJSON events are written to stdout; summary goes to stderr."#;
+
+const REPORT: &str = r#"Report:
+  --report writes a JSON report: stats plus every identity cycle found (members, their JSON
+  pointers and a witness cycle) and every guid or id shared by more than one node."#;
+
+const VALIDATION: &str = r#"Validation:
+  validate lists every schema error and broken invariant with its rule id, JSON pointer and line
+  and column (schema errors also name the item's path); --format json prints the report to stdout
+  instead."#;
+
+const LINT: &str = r#"Lint:
+  lint reads any Bookmarks file, even one that fails validation, and prints warnings without
+  failing: duplicate-url, duplicate-folder, empty-folder, invalid-url, non-http-url,
+  timestamp-out-of-range, empty-name, unknown-node-type, huge-folder. The config file's lint
+  section turns rules off by id ({"rules": {"non-http-url": false}}) and sets
+  huge_folder_children (default 1000)."#;
+
+const EXIT_CODES: &str = r#"Exit codes:
+  0 success, 1 usage or other error, 2 invariant violations, 3 schema violations, 4 I/O error."#;
+
+const IDEMPOTENCE: &str = r#"Idempotence:
+  --check-idempotent normalizes the result a second time with the same settings before writing
+  it and fails, listing the changed JSON pointers, if anything changes."#;
+
+const CONSERVATION: &str = r#"Conservation:
+  --provenance writes the fate of every input node (kept, merged into another node, or removed),
+  keyed by JSON pointer. verify-conservation checks that every input URL is in the output or was
+  deduplicated into an output URL with the same canonical key, and every folder is in the output,
+  was merged into one with the same merge key or identity, or was pruned empty; with --provenance
+  it checks each recorded fate instead. URLs are counted per folder merge key, so a copy lost from
+  one folder is not hidden by the same URL elsewhere; --config reads the run's folder_aliases (and
+  schema settings) so renamed folders match. It fails listing every node it cannot account for."#;
+
+const MEMORY: &str = r#"Memory:
+  --streaming parses the input straight into the working tree, checking each node against its
+  schema as it is read, instead of loading the whole document first; the output is the same.
+  Peak memory is printed after the summary and reported as stats.peak_memory_bytes."#;
+
+const POLICIES: &str = r#"Policies:
+  --folder-winner takes a comma-separated rule list: outermost, most-children,
+    most-recently-modified, prefer-root:<key>, earliest-added, smallest-id, smallest-guid.
+  --folder-name picks the merged folder's spelling: winner, most-frequent, most-descendants,
+    title-case; explicit spellings go in the config file's folder_aliases.
+  --folder-merge sets how loser folder attributes reach the winner: accumulate (default; summed
+    visits, latest date_modified), fold (also earliest date_added and union of extra fields,
+    reporting conflicts) or winner.
+  --url-winner takes a comma-separated rule list: most-visited, recently-used, earliest-added
+    (undated copies last), longer-title, prefer-https, has-meta-info, smallest-id.
+  --url-merge fold keeps loser data on the surviving URL (earliest added, latest used, summed
+    visits, meta_info union, longest title); per-field rules live in the config file.
+  --scc picks the cycle detector (default kosaraju); --scc-cross-check runs a second one and
+    fails if their components differ.
+  --duplicate-identities decides what happens to a guid or id shared by several nodes: report
+    (default; they count as one identity), merge (folder copies collapse into the shallowest
+    one) or separate (later copies get fresh identities).
+  --max-merge-iterations caps the merge/dedup/prune loop (default 16); the run fails, naming the
+    folder keys that still collide, when the cap is hit or an iteration leaves the tree as it
+    already was before.
+  --config loads the same settings from a JSON file; flags override the file."#;
+
+const GUARDRAILS: &str = r#"Guardrails:
+  --max-removed-urls and --max-removed-folders stop the run before anything is written if it
+  would remove more than a count (250), a percentage of the input (5%) or either (5%,250); the
+  error lists the URL and folder groups that lost the most. --force applies the run anyway. Both
+  can be set in the config file."#;
+
+const SCHEMA: &str = r#"Schema:
+  --schema strict (default) rejects input with any schema violation, listing every one with its
+    JSON pointer, name path (/bookmark_bar/Dev/Rust) and line and column. lenient prints each
+    violation as a warning and normalizes anyway, leaving every node that failed its schema
+    exactly as read: it is not merged, deduplicated, pruned or given a new identity, though its
+    children are normalized as usual. off skips the schema. The config file's schema field sets
+    the same mode.
+  The file's version field picks the embedded schema set (newest for a missing or unknown
+    version). --schema-dir (config schema_dir) names a directory whose bookmarks_schema.json,
+    folder_schema.json and url_schema.json, where present, replace the embedded ones; normalize,
+    validate and verify-conservation take it. schema print writes the schemas in effect as JSON,
+    one kind or all three."#;
+
+const LIMITS: &str = r#"Limits:
+  --max-file-bytes (default 512 MiB), --max-nodes (2000000), --max-depth (48 folder levels),
+  --max-name-bytes (8192) and --max-url-bytes (65536) bound the input; a file over any of them is
+  rejected before it is processed, naming the limit and the offending node. normalize also reads
+  them from the config file's limits section."#;
+
+const SAFETY: &str = r#"Safety:
+  If output path equals input path, --backup is required and a timestamped backup is created in
+  the same directory."#;
+
+const COMMANDS: [&CommandHelp; 5] = [
+    &NORMALIZE_HELP,
+    &VALIDATE_HELP,
+    &LINT_HELP,
+    &VERIFY_CONSERVATION_HELP,
+    &SCHEMA_PRINT_HELP,
+];
+
+/// Note sections in the order the full help lists them.
+const NOTES: [&str; 13] = [
+    EVENTS,
+    REPORT,
+    VALIDATION,
+    LINT,
+    EXIT_CODES,
+    IDEMPOTENCE,
+    CONSERVATION,
+    MEMORY,
+    POLICIES,
+    GUARDRAILS,
+    SCHEMA,
+    LIMITS,
+    SAFETY,
+];
+
+/// Help for every subcommand, shown when the subcommand is missing or unknown.
 fn usage() -> &'static str {
-    "Usage:\n  bookmarks normalize --in/--input <input.json> --out/--output <output.json> [--emit-events] [--backup]\n  bookmarks validate --in/--input <input.json>\n\nEvents:\n  If --emit-events is set, NDJSON events are written to stdout; summary goes to stderr.\n\nSafety:\n  If output path equals input path, --backup is required and a timestamped backup is created in the same directory."
+    static USAGE: Lazy<String> = Lazy::new(|| {
+        let synopses: Vec<&str> = COMMANDS.iter().map(|c| c.synopsis).collect();
+        format!("Usage:\n{}\n\n{}", synopses.join("\n"), NOTES.join("\n\n"))
+    });
+    &USAGE
+}
+
+/// Schema sets with the files in `--schema-dir` in place of the embedded ones.
+fn schema_overrides(dir: &str) -> Result<Arc<Schemas>> {
+    let schemas = Schemas::with_overrides(Path::new(dir)).context("loading --schema-dir")?;
+    Ok(Arc::new(schemas))
+}
+
+/// Policy-related flags for `normalize`; each one overrides the matching `--config` field.
+#[derive(Debug, Default)]
+struct PolicyArgs {
+    config: Option<String>,
+    folder_winner: Option<String>,
+    folder_name: Option<String>,
+    folder_merge: Option<String>,
+    url_winner: Option<String>,
+    url_merge: Option<String>,
+    scc: Option<String>,
+    scc_cross_check: Option<String>,
+    duplicate_identities: Option<String>,
+    max_merge_iterations: Option<String>,
+    max_removed_urls: Option<String>,
+    max_removed_folders: Option<String>,
+    force: bool,
+    schema: Option<String>,
+    schema_dir: Option<String>,
+    limits: LimitArgs,
+}
+
+/// How `validate` and `lint` print their report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum ReportFormat {
+    /// One line per finding (for `validate`, in the error message).
+    #[default]
+    Text,
+    /// The whole report as JSON on stdout.
+    Json,
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        })
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(anyhow!(
+                "unknown report format: {other} (expected text, json)"
+            )),
+        }
+    }
+}
+
+/// Process exit codes, so scripts can tell failures apart.
+pub const EXIT_FAILURE: u8 = 1;
+pub const EXIT_INVARIANT_VIOLATION: u8 = 2;
+pub const EXIT_SCHEMA_VIOLATION: u8 = 3;
+pub const EXIT_IO_ERROR: u8 = 4;
+
+/// Exit code for a failed run: schema violations, from `validate` or a strict read, win
+/// over invariant violations (a file can have both); errors reading or writing files are
+/// I/O errors; everything else,
+/// including usage errors, is a plain failure.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    if let Some(ValidationFailed(report)) = err.downcast_ref::<ValidationFailed>() {
+        return if report.schema_errors() > 0 {
+            EXIT_SCHEMA_VIOLATION
+        } else {
+            EXIT_INVARIANT_VIOLATION
+        };
+    }
+    if err.is::<SchemaValidationFailed>() {
+        return EXIT_SCHEMA_VIOLATION;
+    }
+    if err.chain().any(|cause| cause.is::<std::io::Error>()) {
+        return EXIT_IO_ERROR;
+    }
+    EXIT_FAILURE
+}
+
+/// Input limit flags, shared by `normalize` and `validate`.
+#[derive(Debug, Default)]
+struct LimitArgs {
+    max_file_bytes: Option<String>,
+    max_nodes: Option<String>,
+    max_depth: Option<String>,
+    max_name_bytes: Option<String>,
+    max_url_bytes: Option<String>,
+}
+
+impl LimitArgs {
+    const FLAGS: [&'static str; 5] = [
+        "--max-file-bytes",
+        "--max-nodes",
+        "--max-depth",
+        "--max-name-bytes",
+        "--max-url-bytes",
+    ];
+
+    fn is_flag(flag: &str) -> bool {
+        Self::FLAGS.contains(&flag)
+    }
+
+    fn set(&mut self, flag: &str, value: Option<String>) {
+        let slot = match flag {
+            "--max-file-bytes" => &mut self.max_file_bytes,
+            "--max-nodes" => &mut self.max_nodes,
+            "--max-depth" => &mut self.max_depth,
+            "--max-name-bytes" => &mut self.max_name_bytes,
+            _ => &mut self.max_url_bytes,
+        };
+        *slot = value;
+    }
+
+    fn apply_to(&self, mut read: ReadOptions) -> Result<ReadOptions> {
+        if let Some(v) = self.max_file_bytes.as_deref() {
+            read.max_file_bytes = v.parse().context("parsing --max-file-bytes")?;
+        }
+        if let Some(v) = self.max_nodes.as_deref() {
+            read.max_nodes = v.parse().context("parsing --max-nodes")?;
+        }
+        if let Some(v) = self.max_depth.as_deref() {
+            read.max_depth = v.parse().context("parsing --max-depth")?;
+        }
+        if let Some(v) = self.max_name_bytes.as_deref() {
+            read.max_name_bytes = v.parse().context("parsing --max-name-bytes")?;
+        }
+        if let Some(v) = self.max_url_bytes.as_deref() {
+            read.max_url_bytes = v.parse().context("parsing --max-url-bytes")?;
+        }
+        Ok(read)
+    }
+}
+
+async fn load_normalize_options(
+    args: &PolicyArgs,
+) -> Result<(NormalizeOptions, SccSelection, ReadOptions)> {
+    let mut options = NormalizeOptions::default();
+    let mut scc = SccSelection::default();
+    let mut read = ReadOptions::default();
+    if let Some(path) = args.config.as_deref() {
+        let cfg = read_normalize_config(path)
+            .await
+            .with_context(|| format!("reading config: {path}"))?;
+        cfg.apply_to(&mut options)?;
+        cfg.apply_scc(&mut scc)?;
+        cfg.apply_limits(&mut read);
+        cfg.apply_schema(&mut read)?;
+    }
+    if let Some(mode) = args.schema.as_deref() {
+        read.schema = mode.parse().context("parsing --schema")?;
+    }
+    if let Some(dir) = args.schema_dir.as_deref() {
+        read.schemas = schema_overrides(dir)?;
+    }
+    if let Some(rules) = args.folder_winner.as_deref() {
+        options.folder_winner = rules.parse().context("parsing --folder-winner")?;
+    }
+    if let Some(rule) = args.folder_name.as_deref() {
+        options.folder_name.rule = rule.parse().context("parsing --folder-name")?;
+    }
+    if let Some(preset) = args.folder_merge.as_deref() {
+        options.folder_merge = preset.parse().context("parsing --folder-merge")?;
+    }
+    if let Some(rules) = args.url_winner.as_deref() {
+        options.url_winner = rules.parse().context("parsing --url-winner")?;
+    }
+    if let Some(preset) = args.url_merge.as_deref() {
+        options.url_merge = preset.parse().context("parsing --url-merge")?;
+    }
+    if let Some(algo) = args.scc.as_deref() {
+        scc.algorithm = algo.parse().context("parsing --scc")?;
+    }
+    if let Some(algo) = args.scc_cross_check.as_deref() {
+        scc.cross_check = Some(algo.parse().context("parsing --scc-cross-check")?);
+    }
+    if let Some(mode) = args.duplicate_identities.as_deref() {
+        options.duplicate_identities = mode.parse().context("parsing --duplicate-identities")?;
+    }
+    if let Some(limit) = args.max_merge_iterations.as_deref() {
+        let limit: usize = limit.parse().context("parsing --max-merge-iterations")?;
+        if limit == 0 {
+            return Err(anyhow!("--max-merge-iterations must be at least 1"));
+        }
+        options.max_merge_iterations = limit;
+    }
+    if let Some(limit) = args.max_removed_urls.as_deref() {
+        options.removal_limits.urls = Some(limit.parse().context("parsing --max-removed-urls")?);
+    }
+    if let Some(limit) = args.max_removed_folders.as_deref() {
+        options.removal_limits.folders =
+            Some(limit.parse().context("parsing --max-removed-folders")?);
+    }
+    options.removal_limits.force = args.force;
+    let read = args.limits.apply_to(read)?;
+    Ok((options, scc, read))
 }
 
 fn is_same_file(a: &str, b: &str) -> bool {
@@ -291,6 +1302,7 @@ mod tests {
                 emit_events,
                 backup,
                 dry_run,
+                ..
             } => {
                 assert_eq!(input, "a.json");
                 assert_eq!(output, "b.json");
@@ -302,6 +1314,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_normalize_policy_flags() {
+        let args = vec![
+            "bin".to_string(),
+            "bookmarks".to_string(),
+            "normalize".to_string(),
+            "--in".to_string(),
+            "a.json".to_string(),
+            "--out".to_string(),
+            "b.json".to_string(),
+            "--config".to_string(),
+            "cfg.json".to_string(),
+            "--folder-winner".to_string(),
+            "prefer-root:other,outermost".to_string(),
+            "--folder-name".to_string(),
+            "most-frequent".to_string(),
+            "--folder-merge".to_string(),
+            "fold".to_string(),
+            "--url-winner".to_string(),
+            "prefer-https,most-visited".to_string(),
+            "--url-merge".to_string(),
+            "fold".to_string(),
+            "--scc".to_string(),
+            "tarjan".to_string(),
+            "--scc-cross-check".to_string(),
+            "kosaraju".to_string(),
+        ];
+
+        match Cli::parse(&args).expect("parse") {
+            Cli::BookmarksNormalize { policy, .. } => {
+                assert_eq!(policy.config.as_deref(), Some("cfg.json"));
+                assert_eq!(
+                    policy.folder_winner.as_deref(),
+                    Some("prefer-root:other,outermost")
+                );
+                assert_eq!(policy.folder_name.as_deref(), Some("most-frequent"));
+                assert_eq!(policy.folder_merge.as_deref(), Some("fold"));
+                assert_eq!(
+                    policy.url_winner.as_deref(),
+                    Some("prefer-https,most-visited")
+                );
+                assert_eq!(policy.url_merge.as_deref(), Some("fold"));
+                assert_eq!(policy.scc.as_deref(), Some("tarjan"));
+                assert_eq!(policy.scc_cross_check.as_deref(), Some("kosaraju"));
+            }
+            _ => panic!("expected normalize"),
+        }
+    }
+
+    #[tokio::test]
+    async fn load_normalize_options_rejects_bad_policies() {
+        let args = PolicyArgs {
+            folder_winner: Some("loudest".to_string()),
+            ..PolicyArgs::default()
+        };
+        let err = load_normalize_options(&args).await.unwrap_err();
+        assert!(format!("{err:#}").contains("--folder-winner"));
+
+        let args = PolicyArgs {
+            url_winner: Some("loudest".to_string()),
+            ..PolicyArgs::default()
+        };
+        let err = load_normalize_options(&args).await.unwrap_err();
+        assert!(format!("{err:#}").contains("--url-winner"));
+
+        let args = PolicyArgs {
+            folder_name: Some("loudest".to_string()),
+            ..PolicyArgs::default()
+        };
+        let err = load_normalize_options(&args).await.unwrap_err();
+        assert!(format!("{err:#}").contains("--folder-name"));
+
+        let args = PolicyArgs {
+            scc_cross_check: Some("dfs".to_string()),
+            ..PolicyArgs::default()
+        };
+        let err = load_normalize_options(&args).await.unwrap_err();
+        assert!(format!("{err:#}").contains("--scc-cross-check"));
+
+        let args = PolicyArgs {
+            max_merge_iterations: Some("0".to_string()),
+            ..PolicyArgs::default()
+        };
+        let err = load_normalize_options(&args).await.unwrap_err();
+        assert!(format!("{err:#}").contains("--max-merge-iterations"));
+
+        let args = PolicyArgs {
+            schema: Some("loose".to_string()),
+            ..PolicyArgs::default()
+        };
+        let err = load_normalize_options(&args).await.unwrap_err();
+        assert!(format!("{err:#}").contains("--schema"));
+    }
+
     #[test]
     fn parse_validate_success() {
         let args = vec![
@@ -314,7 +1420,7 @@ mod tests {
 
         let cmd = Cli::parse(&args).expect("parse");
         match cmd {
-            Cli::BookmarksValidate { input } => assert_eq!(input, "a.json"),
+            Cli::BookmarksValidate { input, .. } => assert_eq!(input, "a.json"),
             _ => panic!("expected validate"),
         }
     }
@@ -329,6 +1435,24 @@ mod tests {
         ];
         let err = Cli::parse(&args).unwrap_err().to_string();
         assert!(err.contains("Usage"));
+        assert!(err.contains("--folder-winner") && err.contains("Exit codes:"));
+        assert!(!err.contains("bookmarks lint"), "{err}");
+
+        let lint = ["bin", "bookmarks", "lint", "-h"].map(String::from);
+        let err = Cli::parse(&lint).unwrap_err().to_string();
+        assert!(err.contains("Lint:") && !err.contains("Policies:"), "{err}");
+
+        // Without a known subcommand every one is listed.
+        let err = Cli::parse(&["bin".to_string()]).unwrap_err().to_string();
+        for command in [
+            "normalize",
+            "validate",
+            "lint",
+            "verify-conservation",
+            "schema print",
+        ] {
+            assert!(err.contains(&format!("bookmarks {command}")), "{command}");
+        }
     }
 
     #[tokio::test]
@@ -459,6 +1583,449 @@ mod tests {
         run_with_args(&args).await.expect("validate");
     }
 
+    #[tokio::test]
+    async fn run_with_args_rejects_input_over_limits() {
+        let dir = tempdir().expect("tempdir");
+        let input_path = dir.path().join("Bookmarks.json");
+        let output_path = dir.path().join("out.json");
+        std::fs::write(
+            &input_path,
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "A", "children": [
+                    {"type": "url", "name": "x", "url": "https://example.com/"}
+                ]}
+            ]}}}"#,
+        )
+        .expect("write input");
+        let input = input_path.to_str().unwrap().to_string();
+        let output = output_path.to_str().unwrap().to_string();
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["bin", "bookmarks"]
+                .iter()
+                .chain(extra)
+                .map(|s| s.to_string())
+                .collect()
+        };
+
+        let err = run_with_args(&args(&["validate", "--in", &input, "--max-depth", "1"]))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{err:#}").contains(
+                "input exceeds max_depth (2 > 1) at /roots/bookmark_bar/children/0/children/0"
+            ),
+            "{err:#}"
+        );
+
+        let err = run_with_args(&args(&[
+            "normalize",
+            "--in",
+            &input,
+            "--out",
+            &output,
+            "--streaming",
+            "--max-nodes",
+            "2",
+        ]))
+        .await
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("input exceeds max_nodes (3 > 2)"),
+            "{err:#}"
+        );
+
+        let err = run_with_args(&args(&[
+            "validate",
+            "--in",
+            &input,
+            "--max-file-bytes",
+            "10",
+        ]))
+        .await
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("input exceeds max_file_bytes"),
+            "{err:#}"
+        );
+
+        let err = run_with_args(&args(&["validate", "--in", &input, "--max-depth", "two"]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("parsing --max-depth"), "{err}");
+        assert!(!output_path.exists());
+    }
+
+    #[tokio::test]
+    async fn run_with_args_aborts_over_removal_limit_unless_forced() {
+        let dir = tempdir().expect("tempdir");
+        let input_path = dir.path().join("Bookmarks.json");
+        let output_path = dir.path().join("out.json");
+        std::fs::write(
+            &input_path,
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "name": "a", "url": "https://example.com/"},
+                {"type": "url", "name": "b", "url": "https://EXAMPLE.com/"},
+                {"type": "url", "name": "c", "url": "https://example.org/"}
+            ]}}}"#,
+        )
+        .expect("write input");
+        let input = input_path.to_str().unwrap().to_string();
+        let output = output_path.to_str().unwrap().to_string();
+        let args = |extra: &[&str]| -> Vec<String> {
+            [
+                "bin",
+                "bookmarks",
+                "normalize",
+                "--in",
+                &input,
+                "--out",
+                &output,
+            ]
+            .iter()
+            .chain(extra)
+            .map(|s| s.to_string())
+            .collect()
+        };
+
+        let err = run_with_args(&args(&["--max-removed-urls", "0"]))
+            .await
+            .unwrap_err();
+        let msg = format!("{err:#}");
+        assert!(msg.contains("--force"), "{msg}");
+        assert!(
+            msg.contains("run would remove 1 of 3 URLs (limit 0)"),
+            "{msg}"
+        );
+        assert!(msg.contains("url \"https://example.com/\" -1"), "{msg}");
+        assert!(!output_path.exists());
+
+        run_with_args(&args(&["--max-removed-urls", "50%"]))
+            .await
+            .expect("within limit");
+        std::fs::remove_file(&output_path).expect("remove output");
+
+        run_with_args(&args(&["--max-removed-urls", "0", "--force"]))
+            .await
+            .expect("forced");
+        assert!(output_path.exists());
+    }
+
+    #[tokio::test]
+    async fn run_with_args_verifies_conservation_with_and_without_provenance() {
+        let dir = tempdir().expect("tempdir");
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        let (input, output, provenance) = (path("in.json"), path("out.json"), path("prov.json"));
+        std::fs::copy("tests/resources/BookmarksMiniNested", &input).expect("copy input");
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["bin", "bookmarks"]
+                .iter()
+                .chain(extra)
+                .map(|s| s.to_string())
+                .collect()
+        };
+
+        run_with_args(&args(&[
+            "normalize",
+            "--in",
+            &input,
+            "--out",
+            &output,
+            "--provenance",
+            &provenance,
+            "--check-idempotent",
+        ]))
+        .await
+        .expect("normalize");
+        let verify = ["verify-conservation", "--in", &input, "--out", &output];
+        run_with_args(&args(&verify)).await.expect("content check");
+        let with_provenance = [&verify[..], &["--provenance", &provenance]].concat();
+        run_with_args(&args(&with_provenance))
+            .await
+            .expect("provenance check");
+
+        // Drop a URL that had no duplicate: both checks must notice.
+        let raw = std::fs::read_to_string(&output).expect("read output");
+        std::fs::write(
+            &output,
+            raw.replace("https://kubernetes.io", "https://example.net"),
+        )
+        .expect("tamper output");
+        for check in [&verify[..], &with_provenance[..]] {
+            let err = run_with_args(&args(check)).await.unwrap_err().to_string();
+            assert!(err.contains("(https://kubernetes.io)"), "{err}");
+        }
+
+        // A group renamed by an alias is found under its new name once the config is given.
+        let config = path("config.json");
+        std::fs::write(&config, r#"{"folder_aliases": {"work": "Job"}}"#).expect("write config");
+        std::fs::write(
+            &input,
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "name": "Bar", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"}
+                ]},
+                {"type": "folder", "name": "work", "children": [
+                    {"type": "url", "name": "b", "url": "https://example.org/"}
+                ]}
+            ]}}, "version": 1}"#,
+        )
+        .expect("write input");
+        run_with_args(&args(&[
+            "normalize",
+            "--in",
+            &input,
+            "--out",
+            &output,
+            "--provenance",
+            &provenance,
+            "--config",
+            &config,
+        ]))
+        .await
+        .expect("normalize with aliases");
+        for check in [&verify[..], &with_provenance[..]] {
+            let err = run_with_args(&args(check)).await.unwrap_err().to_string();
+            assert!(err.contains("(\"Work\")"), "{err}");
+            let aliased = [check, &["--config", &config]].concat();
+            run_with_args(&args(&aliased)).await.expect("aliased check");
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_failures_map_to_distinct_exit_codes() {
+        let dir = tempdir().expect("tempdir");
+        let write = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, body).expect("write input");
+            path.to_str().unwrap().to_string()
+        };
+        let duplicate = write(
+            "dup.json",
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "url": "https://example.com/"},
+                {"type": "url", "url": "https://example.com/"}
+            ]}}}"#,
+        );
+        let schema = write(
+            "schema.json",
+            r#"{"version": 7, "roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "url": "https://example.com/"},
+                {"type": "url", "url": "https://example.com/"}
+            ]}}}"#,
+        );
+        let missing = dir
+            .path()
+            .join("missing.json")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let validate = |input: &str, extra: &[&str]| -> Vec<String> {
+            ["bin", "bookmarks", "validate", "--in", input]
+                .iter()
+                .chain(extra)
+                .map(|s| s.to_string())
+                .collect()
+        };
+
+        let err = run_with_args(&validate(&duplicate, &[])).await.unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_INVARIANT_VIOLATION);
+        assert!(
+            format!("{err:#}").contains(
+                "error [unique-url-in-folder] /roots/bookmark_bar/children/1 (line 3, column 17)"
+            ),
+            "{err:#}"
+        );
+
+        let err = run_with_args(&validate(&schema, &["--format", "json"]))
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_SCHEMA_VIOLATION);
+        assert!(format!("{err:#}").contains("1 schema error(s), 1 invariant error(s)"));
+
+        let err = run_with_args(&validate(&missing, &[])).await.unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_IO_ERROR);
+
+        let err = run_with_args(&validate(&duplicate, &["--format", "yaml"]))
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_FAILURE);
+        assert!(err.to_string().contains("parsing --format"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn strict_normalize_lists_every_schema_error_with_its_location() {
+        let dir = tempdir().expect("tempdir");
+        let input = dir.path().join("in.json");
+        std::fs::write(
+            &input,
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+  {"type": "folder", "name": "Dev", "children": [
+    {"type": "url", "name": "Rust", "url": "https://rust-lang.org/", "visit_count": -1}
+  ]},
+  {"type": "url", "name": "Docs", "url": 5}
+]}}}"#,
+        )
+        .expect("write input");
+        let input = input.to_str().unwrap();
+        let output = dir.path().join("out.json");
+        let output = output.to_str().unwrap();
+
+        for extra in [&[][..], &["--streaming"][..]] {
+            let args: Vec<String> = ["bin", "bookmarks", "normalize", "--in", input]
+                .iter()
+                .chain(["--out", output].iter())
+                .chain(extra)
+                .map(|s| s.to_string())
+                .collect();
+            let err = run_with_args(&args).await.unwrap_err();
+            assert_eq!(exit_code(&err), EXIT_SCHEMA_VIOLATION);
+            let message = format!("{err:#}");
+            assert!(
+                message.contains("input failed schema validation (2 violation(s))"),
+                "{message}"
+            );
+            assert!(
+                message.contains(
+                    "/roots/bookmark_bar/children/0/children/0/visit_count \
+                     (/bookmark_bar/Dev/Rust, line 3, column 85)"
+                ),
+                "{message}"
+            );
+            assert!(
+                message.contains(
+                    "/roots/bookmark_bar/children/1/url (/bookmark_bar/Docs, line 5, column 42)"
+                ),
+                "{message}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn schema_dir_overrides_the_embedded_schemas_and_schema_print_shows_them() {
+        let dir = tempdir().expect("tempdir");
+        let schemas = dir.path().join("schemas");
+        std::fs::create_dir(&schemas).expect("schema dir");
+        // A variant whose URL items may carry a negative visit count.
+        std::fs::write(
+            schemas.join("url_schema.json"),
+            r#"{"type": "object", "required": ["type", "url"]}"#,
+        )
+        .expect("write schema");
+        let schemas = schemas.to_str().unwrap();
+        let input = dir.path().join("in.json");
+        std::fs::write(
+            &input,
+            r#"{"version": 1, "roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "name": "x", "url": "https://x.example/", "visit_count": -1}
+            ]}}}"#,
+        )
+        .expect("write input");
+        let input = input.to_str().unwrap();
+        let output = dir.path().join("out.json");
+        let output = output.to_str().unwrap();
+        let args = |cmd: &[&str]| -> Vec<String> {
+            ["bin", "bookmarks"]
+                .iter()
+                .chain(cmd)
+                .map(|s| s.to_string())
+                .collect()
+        };
+
+        let err = run_with_args(&args(&["normalize", "--in", input, "--out", output]))
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_SCHEMA_VIOLATION);
+        for extra in [&[][..], &["--streaming"][..]] {
+            let mut cmd = vec![
+                "normalize",
+                "--in",
+                input,
+                "--out",
+                output,
+                "--schema-dir",
+                schemas,
+            ];
+            cmd.extend(extra);
+            run_with_args(&args(&cmd))
+                .await
+                .expect("normalize with overrides");
+        }
+        let err = run_with_args(&args(&["validate", "--in", input]))
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_SCHEMA_VIOLATION);
+        run_with_args(&args(&["validate", "--in", input, "--schema-dir", schemas]))
+            .await
+            .expect("validate with overrides");
+
+        run_with_args(&args(&["schema", "print", "url", "--schema-dir", schemas]))
+            .await
+            .expect("print");
+        let err = run_with_args(&args(&["schema", "print", "--version", "2"]))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("no schema set for version 2"),
+            "{err}"
+        );
+        let err = run_with_args(&args(&["schema", "print", "bookmark"]))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("unknown schema: bookmark"),
+            "{err:#}"
+        );
+        assert!(run_with_args(&args(&["schema", "show"])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn lint_warns_on_files_validate_rejects_and_reads_rules_from_config() {
+        let dir = tempdir().expect("tempdir");
+        let write = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, body).expect("write file");
+            path.to_str().unwrap().to_string()
+        };
+        let input = write(
+            "Bookmarks.json",
+            r#"{"version": 7, "roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "url": "javascript:alert(1)"},
+                {"type": "folder", "name": "", "children": []}
+            ]}}}"#,
+        );
+        let lint = |extra: &[&str]| -> Vec<String> {
+            ["bin", "bookmarks", "lint", "--in", &input]
+                .iter()
+                .chain(extra)
+                .map(|s| s.to_string())
+                .collect()
+        };
+
+        run_with_args(&lint(&[]))
+            .await
+            .expect("lint never fails on warnings");
+        run_with_args(&lint(&["--format", "json"]))
+            .await
+            .expect("lint json");
+
+        let config = write(
+            "config.json",
+            r#"{"lint": {"rules": {"non-http-url": false}}}"#,
+        );
+        run_with_args(&lint(&["--config", &config]))
+            .await
+            .expect("lint with config");
+
+        let config = write("bad.json", r#"{"lint": {"rules": {"http-only": false}}}"#);
+        let err = run_with_args(&lint(&["--config", &config]))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("unknown lint rule: http-only"),
+            "{err:#}"
+        );
+    }
+
     #[tokio::test]
     async fn run_with_args_refuses_overwrite_without_backup() {
         let dir = tempdir().expect("tempdir");
@@ -6,20 +6,29 @@
 //! - infrastructure: serde + async IO + implementations of ports
 //! - interface: CLI wiring
 
-use anyhow::Result;
+use microsoft_edge_bookmark_sorter_flattener::interface::cli;
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    microsoft_edge_bookmark_sorter_flattener::interface::cli::run().await
+async fn main() -> ExitCode {
+    match cli::run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(cli::exit_code(&err))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn main_returns_usage_error_under_test_harness_args() {
         // When executed under `cargo test`, env::args() does not match the CLI contract.
         // We assert a graceful usage error instead of panicking.
-        let res = super::main();
-        assert!(res.is_err());
+        let code = super::main();
+        assert_eq!(code, ExitCode::from(cli::EXIT_FAILURE));
     }
 }
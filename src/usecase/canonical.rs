@@ -0,0 +1,53 @@
+use crate::domain::traits::UrlCanonicalizer;
+use std::thread;
+
+/// Below this many URLs the threads cost more than they save.
+const PARALLEL_THRESHOLD: usize = 4096;
+
+/// Canonicalizes `urls` across the available cores; `out[i]` belongs to `urls[i]`.
+pub fn canonicalize_all(canonicalizer: &dyn UrlCanonicalizer, urls: &[&str]) -> Vec<String> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    if threads == 1 || urls.len() < PARALLEL_THRESHOLD {
+        return urls.iter().map(|u| canonicalizer.canonicalize(u)).collect();
+    }
+
+    let chunk = urls.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = urls
+            .chunks(chunk)
+            .map(|part| {
+                scope.spawn(move || {
+                    part.iter()
+                        .map(|u| canonicalizer.canonicalize(u))
+                        .collect::<Vec<String>>()
+                })
+            })
+            .collect();
+
+        // Joined in spawn order, so the output keeps the input order.
+        let mut out = Vec::with_capacity(urls.len());
+        for worker in workers {
+            out.extend(worker.join().expect("canonicalization worker panicked"));
+        }
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
+
+    #[test]
+    fn parallel_result_matches_serial_order() {
+        let owned: Vec<String> = (0..PARALLEL_THRESHOLD * 3 + 7)
+            .map(|i| format!("HTTPS://Example{}.com/p?q={i}#frag", i % 13))
+            .collect();
+        let urls: Vec<&str> = owned.iter().map(String::as_str).collect();
+
+        let canonicalizer = DefaultUrlCanonicalizer;
+        let serial: Vec<String> = urls.iter().map(|u| canonicalizer.canonicalize(u)).collect();
+
+        assert_eq!(canonicalize_all(&canonicalizer, &urls), serial);
+    }
+}
@@ -0,0 +1,588 @@
+use crate::domain::traits::UrlCanonicalizer;
+use crate::infrastructure::serde_json_adapter::{BookmarkNodeDto, BookmarksFileDto};
+use crate::usecase::normalize::folder_key;
+use crate::usecase::normalize::folder_name::FolderNamePolicy;
+use crate::usecase::normalize::provenance::{Fate, Provenance};
+use crate::usecase::validate::canonical_urls;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Outcome of `verify-conservation`: how every non-root input node is accounted for in
+/// the output, plus each node that is not.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConservationReport {
+    pub urls_in: usize,
+    /// Present in the output.
+    pub urls_kept: usize,
+    /// Dedup losers of an output URL with the same canonical key.
+    pub urls_merged: usize,
+    pub folders_in: usize,
+    pub folders_kept: usize,
+    /// Merge losers of an output folder with the same merge key, guid or id.
+    pub folders_merged: usize,
+    /// Folders with no surviving node to absorb them (pruned once empty).
+    pub folders_pruned: usize,
+    pub violations: Vec<String>,
+}
+
+impl ConservationReport {
+    pub fn is_conserved(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// A node of the input file with its JSON pointer.
+struct Located<'a> {
+    pointer: String,
+    node: &'a BookmarkNodeDto,
+    /// Some URL exists in this node's input subtree.
+    has_urls: bool,
+}
+
+/// Checks that no URL or folder of `input` is lost in `output`. Folder merge keys are
+/// compared as `folder_names` leaves them, so a group renamed by an alias still matches.
+///
+/// Without `provenance`, URLs are counted per bucket: the root container or folder merge
+/// key they sit under, where merged folders pool their children. A URL counts as kept
+/// while its bucket holds an unclaimed copy of the exact URL and as merged when the bucket
+/// only holds its canonical key; a folder counts as kept when its merge key is in the
+/// output, merged when its guid or id is, and pruned when its subtree held no URLs. With
+/// `provenance`, every node must carry the fate the run recorded, and each merged node
+/// must share its canonical key, merge key or identity with the node it went into, whose
+/// own chain ends at a kept node.
+pub fn verify_conservation(
+    input: &BookmarksFileDto,
+    output: &BookmarksFileDto,
+    provenance: Option<&Provenance>,
+    folder_names: &FolderNamePolicy,
+    canonicalizer: &dyn UrlCanonicalizer,
+) -> ConservationReport {
+    let nodes = locate(input);
+    let in_canon = canonical_urls(input, canonicalizer);
+    let out_canon = canonical_urls(output, canonicalizer);
+    let canon_of = |node: &BookmarkNodeDto| {
+        node.url
+            .as_deref()
+            .and_then(|url| in_canon.get(url))
+            .map(String::as_str)
+    };
+    let merged_key =
+        |node: &BookmarkNodeDto| node.name.as_deref().map(|n| folder_names.merged_key(n));
+
+    // Output side: folder keys, the bucket of each folder and identity, and per bucket the
+    // multiplicity of each URL and its canonical keys.
+    let out_nodes = locate(output);
+    let mut out_folders: HashSet<String> = HashSet::new();
+    let mut out_identities: HashMap<&str, String> = HashMap::new();
+    let mut folder_buckets: HashMap<&str, String> = HashMap::new();
+    for Located { pointer, node, .. } in out_nodes.iter() {
+        if node.node_type != "folder" {
+            continue;
+        }
+        let key = node.name.as_deref().map(folder_key);
+        let bucket = folder_bucket(key.as_deref());
+        out_folders.extend(key);
+        for identity in identities(node) {
+            out_identities.insert(identity, bucket.clone());
+        }
+        folder_buckets.insert(pointer, bucket);
+    }
+    let mut out_urls: HashMap<&str, usize> = HashMap::new();
+    let mut bucket_urls: HashMap<(String, Option<&str>), usize> = HashMap::new();
+    let mut bucket_keys: HashSet<(String, &str)> = HashSet::new();
+    for Located { pointer, node, .. } in out_nodes.iter() {
+        if node.node_type != "url" {
+            continue;
+        }
+        let url = node.url.as_deref();
+        let parent = parent_pointer(pointer);
+        let bucket = folder_buckets
+            .get(parent)
+            .cloned()
+            .unwrap_or_else(|| parent.to_string());
+        *out_urls.entry(url.unwrap_or_default()).or_default() += 1;
+        if let Some(key) = url.and_then(|u| out_canon.get(u)) {
+            bucket_keys.insert((bucket.clone(), key.as_str()));
+        }
+        *bucket_urls.entry((bucket, url)).or_default() += 1;
+    }
+
+    let mut report = ConservationReport::default();
+    let fates: Option<HashMap<&str, &Fate>> = provenance.map(|p| {
+        p.nodes
+            .iter()
+            .map(|e| (e.pointer.as_str(), &e.fate))
+            .collect()
+    });
+    let by_pointer: HashMap<&str, &BookmarkNodeDto> =
+        nodes.iter().map(|l| (l.pointer.as_str(), l.node)).collect();
+    // Folders sharing a guid or id, directly or through other folders, may be merged into
+    // one another, so their children can end up in the bucket of any of them or of an
+    // output folder carrying one of their identities.
+    let folders: Vec<&Located> = nodes
+        .iter()
+        .filter(|l| l.node.node_type == "folder")
+        .collect();
+    let mut by_identity: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, folder) in folders.iter().enumerate() {
+        for identity in identities(folder.node) {
+            by_identity.entry(identity).or_default().push(i);
+        }
+    }
+    let mut class_of: Vec<Option<usize>> = vec![None; folders.len()];
+    let mut class_buckets: Vec<Vec<String>> = Vec::new();
+    for start in 0..folders.len() {
+        if class_of[start].is_some() {
+            continue;
+        }
+        let class = class_buckets.len();
+        let mut buckets: Vec<String> = Vec::new();
+        let mut stack = vec![start];
+        class_of[start] = Some(class);
+        while let Some(i) = stack.pop() {
+            let folder = folders[i].node;
+            let own = folder_bucket(merged_key(folder).as_deref());
+            let shared = identities(folder).filter_map(|id| out_identities.get(id));
+            for bucket in std::iter::once(&own).chain(shared) {
+                if !buckets.contains(bucket) {
+                    buckets.push(bucket.clone());
+                }
+            }
+            for j in identities(folder).flat_map(|id| &by_identity[id]) {
+                if class_of[*j].is_none() {
+                    class_of[*j] = Some(class);
+                    stack.push(*j);
+                }
+            }
+        }
+        class_buckets.push(buckets);
+    }
+    let folder_classes: HashMap<&str, usize> = folders
+        .iter()
+        .zip(&class_of)
+        .filter_map(|(l, class)| Some((l.pointer.as_str(), (*class)?)))
+        .collect();
+    // Where the children of the input node at `pointer` can end up: its root container,
+    // or the buckets of its identity class.
+    let buckets_of = |pointer: &str| -> Vec<String> {
+        match folder_classes.get(pointer) {
+            Some(class) => class_buckets[*class].clone(),
+            None => vec![pointer.to_string()],
+        }
+    };
+
+    for located in nodes.iter() {
+        let Located {
+            pointer,
+            node,
+            has_urls,
+        } = located;
+        let is_url = match node.node_type.as_str() {
+            "url" => true,
+            "folder" => false,
+            _ => continue,
+        };
+        if is_url {
+            report.urls_in += 1;
+        } else {
+            report.folders_in += 1;
+        }
+        let describe = || {
+            if is_url {
+                format!(
+                    "URL {pointer} ({})",
+                    node.url.as_deref().unwrap_or_default()
+                )
+            } else {
+                format!(
+                    "folder {pointer} ({:?})",
+                    node.name.as_deref().unwrap_or_default()
+                )
+            }
+        };
+
+        let Some(fates) = fates.as_ref() else {
+            // No provenance: account for the node by what the output contains.
+            if is_url {
+                let buckets = buckets_of(parent_pointer(pointer));
+                let url = node.url.as_deref();
+                let kept = buckets.iter().any(|bucket| {
+                    match bucket_urls.get_mut(&(bucket.clone(), url)) {
+                        Some(n) if *n > 0 => {
+                            *n -= 1;
+                            true
+                        }
+                        _ => false,
+                    }
+                });
+                if kept {
+                    report.urls_kept += 1;
+                } else if canon_of(node).is_some_and(|key| {
+                    buckets
+                        .iter()
+                        .any(|bucket| bucket_keys.contains(&(bucket.clone(), key)))
+                }) {
+                    report.urls_merged += 1;
+                } else {
+                    report
+                        .violations
+                        .push(format!("{} is missing from the output", describe()));
+                }
+            } else if merged_key(node).is_some_and(|k| out_folders.contains(&k)) {
+                report.folders_kept += 1;
+            } else if identities(node).any(|i| out_identities.contains_key(i)) {
+                report.folders_merged += 1;
+            } else if !has_urls {
+                report.folders_pruned += 1;
+            } else {
+                report
+                    .violations
+                    .push(format!("{} is missing from the output", describe()));
+            }
+            continue;
+        };
+
+        match fates.get(pointer.as_str()) {
+            None => report
+                .violations
+                .push(format!("{} has no provenance entry", describe())),
+            Some(Fate::Kept) if is_url => {
+                let url = node.url.as_deref().unwrap_or_default();
+                match out_urls.get_mut(url) {
+                    Some(n) if *n > 0 => {
+                        *n -= 1;
+                        report.urls_kept += 1;
+                    }
+                    _ => report.violations.push(format!(
+                        "{} is recorded as kept but is not in the output",
+                        describe()
+                    )),
+                }
+            }
+            Some(Fate::Kept) => {
+                if merged_key(node).is_some_and(|k| out_folders.contains(&k))
+                    || identities(node).any(|i| out_identities.contains_key(i))
+                {
+                    report.folders_kept += 1;
+                } else {
+                    report.violations.push(format!(
+                        "{} is recorded as kept but is not in the output",
+                        describe()
+                    ));
+                }
+            }
+            Some(Fate::Merged { into }) => {
+                let winner = by_pointer.get(into.as_str()).copied();
+                let kept = ends_kept(fates, into);
+                let matches = winner.is_some_and(|w| {
+                    w.node_type == node.node_type
+                        && if is_url {
+                            canon_of(w).is_some() && canon_of(w) == canon_of(node)
+                        } else {
+                            same_folder(w, node, folder_names)
+                        }
+                });
+                match (kept, matches) {
+                    (true, true) if is_url => report.urls_merged += 1,
+                    (true, true) => report.folders_merged += 1,
+                    (false, _) => report.violations.push(format!(
+                        "{} was merged into {into}, which does not end up in the output",
+                        describe()
+                    )),
+                    (true, false) => report.violations.push(format!(
+                        "{} was merged into {into}, which does not share its {}",
+                        describe(),
+                        if is_url {
+                            "canonical URL"
+                        } else {
+                            "merge key or identity"
+                        }
+                    )),
+                }
+            }
+            Some(Fate::Removed) if !is_url => report.folders_pruned += 1,
+            Some(Fate::Removed) => report.violations.push(format!(
+                "{} was removed without a surviving duplicate",
+                describe()
+            )),
+        }
+    }
+
+    if let Some(fates) = fates.as_ref() {
+        let known: BTreeSet<&str> = by_pointer.keys().copied().collect();
+        let roots: BTreeSet<String> = input.roots.keys().map(|k| root_pointer(k)).collect();
+        for pointer in fates.keys() {
+            if !known.contains(pointer) && !roots.contains(*pointer) {
+                report.violations.push(format!(
+                    "provenance entry {pointer} is not a node of the input"
+                ));
+            }
+        }
+    }
+    report.violations.sort();
+    report
+}
+
+/// Follows recorded merges from `pointer` until a kept node, a dead end or a loop.
+fn ends_kept(fates: &HashMap<&str, &Fate>, pointer: &str) -> bool {
+    let mut cur = pointer;
+    for _ in 0..=fates.len() {
+        match fates.get(cur) {
+            Some(Fate::Kept) => return true,
+            Some(Fate::Merged { into }) => cur = into,
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn identities(node: &BookmarkNodeDto) -> impl Iterator<Item = &str> {
+    node.guid.as_deref().into_iter().chain(node.id.as_deref())
+}
+
+fn same_folder(a: &BookmarkNodeDto, b: &BookmarkNodeDto, names: &FolderNamePolicy) -> bool {
+    let key = |n: &BookmarkNodeDto| n.name.as_deref().map(|name| names.merged_key(name));
+    (key(a).is_some() && key(a) == key(b)) || identities(a).any(|i| identities(b).any(|j| i == j))
+}
+
+/// URLs under folders sharing a merge key end up together, so they are counted together.
+fn folder_bucket(key: Option<&str>) -> String {
+    format!("folder:{}", key.unwrap_or_default())
+}
+
+/// The pointer of the node holding `pointer` in its `children`.
+fn parent_pointer(pointer: &str) -> &str {
+    pointer
+        .rsplit_once("/children/")
+        .map_or(pointer, |(parent, _)| parent)
+}
+
+fn root_pointer(root_key: &str) -> String {
+    format!("/roots/{}", root_key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Every node below the root containers, in pre-order, with its JSON pointer.
+fn locate(dto: &BookmarksFileDto) -> Vec<Located<'_>> {
+    let mut out = Vec::new();
+    let mut stack: Vec<(String, &BookmarkNodeDto)> = Vec::new();
+    for (key, root) in dto.roots.iter().rev() {
+        let pointer = root_pointer(key);
+        for (i, child) in root.children.iter().enumerate().rev() {
+            stack.push((format!("{pointer}/children/{i}"), child));
+        }
+    }
+    while let Some((pointer, node)) = stack.pop() {
+        for (i, child) in node.children.iter().enumerate().rev() {
+            stack.push((format!("{pointer}/children/{i}"), child));
+        }
+        out.push(Located {
+            pointer,
+            node,
+            has_urls: false,
+        });
+    }
+
+    // Pre-order: a node's subtree is the run of entries up to the next one at its level,
+    // so walking backwards with a depth stack propagates "has URLs" to every ancestor.
+    let depth = |p: &str| p.matches("/children/").count();
+    let mut pending: Vec<(usize, bool)> = Vec::new();
+    for i in (0..out.len()).rev() {
+        let d = depth(&out[i].pointer);
+        let mut has_urls = out[i].node.node_type == "url";
+        while let Some(&(child_depth, child_has)) = pending.last() {
+            if child_depth <= d {
+                break;
+            }
+            has_urls |= child_has;
+            pending.pop();
+        }
+        out[i].has_urls = has_urls;
+        pending.push((d, has_urls));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
+    use serde_json::json;
+
+    fn dto(value: serde_json::Value) -> BookmarksFileDto {
+        serde_json::from_value(value).expect("dto")
+    }
+
+    #[test]
+    fn accounts_for_dedup_and_merge_losers_and_flags_lost_urls() {
+        let input = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"},
+                    {"type": "url", "name": "b", "url": "https://EXAMPLE.com/"}
+                ]},
+                {"type": "folder", "name": "work ", "children": [
+                    {"type": "url", "name": "c", "url": "https://example.org/"}
+                ]},
+                {"type": "folder", "name": "Empty", "children": []}
+            ]}}}),
+        );
+        let output = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"},
+                    {"type": "url", "name": "c", "url": "https://example.org/"}
+                ]}
+            ]}}}),
+        );
+        let c = DefaultUrlCanonicalizer;
+        let names = FolderNamePolicy::default();
+
+        let report = verify_conservation(&input, &output, None, &names, &c);
+        assert!(report.is_conserved(), "{:?}", report.violations);
+        assert_eq!((report.urls_kept, report.urls_merged), (2, 1));
+        assert_eq!((report.folders_kept, report.folders_pruned), (2, 1));
+
+        let lossy = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"}
+                ]}
+            ]}}}),
+        );
+        let report = verify_conservation(&input, &lossy, None, &names, &c);
+        assert_eq!(
+            report.violations,
+            vec![
+                "URL /roots/bookmark_bar/children/1/children/0 (https://example.org/) is missing from the output"
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_each_recorded_fate_against_the_files() {
+        let input = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "name": "a", "url": "https://example.com/"},
+                {"type": "url", "name": "b", "url": "https://example.com/#x"},
+                {"type": "url", "name": "c", "url": "https://example.org/"}
+            ]}}}),
+        );
+        let output = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "name": "a", "url": "https://example.com/"},
+                {"type": "url", "name": "c", "url": "https://example.org/"}
+            ]}}}),
+        );
+        let provenance: Provenance = serde_json::from_value(json!({"nodes": [
+            {"pointer": "/roots/bookmark_bar", "fate": "kept"},
+            {"pointer": "/roots/bookmark_bar/children/0", "fate": "kept"},
+            {"pointer": "/roots/bookmark_bar/children/1", "fate": "merged", "into": "/roots/bookmark_bar/children/2"},
+            {"pointer": "/roots/bookmark_bar/children/2", "fate": "kept"}
+        ]}))
+        .expect("provenance");
+
+        let report = verify_conservation(
+            &input,
+            &output,
+            Some(&provenance),
+            &FolderNamePolicy::default(),
+            &DefaultUrlCanonicalizer,
+        );
+        assert_eq!(
+            report.violations,
+            vec![
+                "URL /roots/bookmark_bar/children/1 (https://example.com/#x) was merged into /roots/bookmark_bar/children/2, which does not share its canonical URL"
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_copies_per_folder_and_flags_url_nodes_without_a_url() {
+        let input = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"}
+                ]},
+                {"type": "folder", "name": "Home", "children": [
+                    {"type": "url", "name": "b", "url": "https://example.com/"},
+                    {"type": "url", "name": "no url"}
+                ]}
+            ]}}}),
+        );
+        // Home's copy is gone even though Work still holds the same URL.
+        let output = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"}
+                ]},
+                {"type": "folder", "name": "Home", "children": []}
+            ]}}}),
+        );
+        let names = FolderNamePolicy::default();
+        let c = DefaultUrlCanonicalizer;
+
+        let report = verify_conservation(&input, &output, None, &names, &c);
+        assert_eq!(
+            report.violations,
+            vec![
+                "URL /roots/bookmark_bar/children/1/children/0 (https://example.com/) is missing from the output",
+                "URL /roots/bookmark_bar/children/1/children/1 () is missing from the output",
+            ]
+        );
+
+        let provenance: Provenance = serde_json::from_value(json!({"nodes": [
+            {"pointer": "/roots/bookmark_bar/children/0", "fate": "kept"},
+            {"pointer": "/roots/bookmark_bar/children/0/children/0", "fate": "kept"},
+            {"pointer": "/roots/bookmark_bar/children/1", "fate": "kept"},
+            {"pointer": "/roots/bookmark_bar/children/1/children/0", "fate": "merged", "into": "/roots/bookmark_bar/children/0/children/0"},
+            {"pointer": "/roots/bookmark_bar/children/1/children/1", "fate": "merged", "into": "/roots/bookmark_bar/children/0/children/0"}
+        ]}))
+        .expect("provenance");
+        let report = verify_conservation(&input, &output, Some(&provenance), &names, &c);
+        assert_eq!(
+            report.violations,
+            vec![
+                "URL /roots/bookmark_bar/children/1/children/1 () was merged into /roots/bookmark_bar/children/0/children/0, which does not share its canonical URL"
+            ]
+        );
+    }
+
+    #[test]
+    fn folders_renamed_by_an_alias_are_matched_under_the_alias() {
+        let input = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"}
+                ]},
+                {"type": "folder", "name": "work", "children": [
+                    {"type": "url", "name": "b", "url": "https://example.org/"}
+                ]}
+            ]}}}),
+        );
+        let output = dto(
+            json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Job", "children": [
+                    {"type": "url", "name": "a", "url": "https://example.com/"},
+                    {"type": "url", "name": "b", "url": "https://example.org/"}
+                ]}
+            ]}}}),
+        );
+        let provenance: Provenance = serde_json::from_value(json!({"nodes": [
+            {"pointer": "/roots/bookmark_bar/children/0", "fate": "kept"},
+            {"pointer": "/roots/bookmark_bar/children/0/children/0", "fate": "kept"},
+            {"pointer": "/roots/bookmark_bar/children/1", "fate": "merged", "into": "/roots/bookmark_bar/children/0"},
+            {"pointer": "/roots/bookmark_bar/children/1/children/0", "fate": "kept"}
+        ]}))
+        .expect("provenance");
+        let aliased = FolderNamePolicy::default().with_aliases([("work", "Job")]);
+        let c = DefaultUrlCanonicalizer;
+
+        for evidence in [None, Some(&provenance)] {
+            let report = verify_conservation(&input, &output, evidence, &aliased, &c);
+            assert!(report.is_conserved(), "{:?}", report.violations);
+            assert_eq!(report.urls_kept, 2);
+        }
+        let report = verify_conservation(&input, &output, None, &FolderNamePolicy::default(), &c);
+        assert!(!report.is_conserved());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::usecase::normalize::cycles::{CycleMember, WitnessEdge};
+use crate::usecase::normalize::identities::IdentityInstance;
 use crate::usecase::stats::NormalizeStats;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -12,6 +14,14 @@ pub enum AppEvent {
         name: String,
     },
 
+    /// A `guid` or `id` is carried by more than one node in the input.
+    DuplicateIdentity {
+        field: String,
+        value: String,
+        identical: bool,
+        instances: Vec<IdentityInstance>,
+    },
+
     SccComputed {
         nodes: usize,
         edges: usize,
@@ -19,6 +29,26 @@ pub enum AppEvent {
         cyclic_components: usize,
     },
 
+    /// A cyclic component of the folder identity graph, located in the input file.
+    CycleDetected {
+        members: Vec<CycleMember>,
+        /// Folder names along one concrete cycle, e.g. `A → B → A`.
+        witness: String,
+        witness_edges: Vec<WitnessEdge>,
+    },
+
+    /// A folder repeating an identity already present higher up was cut out of a cycle;
+    /// its children moved into the canonical instance at `moved_to`.
+    ///
+    /// Event `*_path` fields are name paths (`bookmark_bar / A / B`) of the node's location
+    /// at the time of the event, so they reflect earlier merges and moves.
+    IdentityEdgeCut {
+        identity: String,
+        parent_path: String,
+        child_path: String,
+        moved_to: String,
+    },
+
     FolderMergePlanned {
         normalized_name: String,
         group_size: usize,
@@ -28,6 +58,24 @@ pub enum AppEvent {
         normalized_name: String,
         winner_path: String,
         losers: Vec<String>,
+        /// Winner policy in effect, e.g. `outermost`.
+        policy: String,
+        /// One entry per loser (same order): the rule that ranked the winner ahead.
+        rationale: Vec<String>,
+        /// Display name given to the surviving folder.
+        name: String,
+        /// Naming rule that chose `name` (`alias` for explicit alias-table entries).
+        name_rule: String,
+    },
+
+    /// A loser folder value that differs from the winner's and was dropped during merge.
+    FolderAttributeConflict {
+        folder_path: String,
+        loser_path: String,
+        /// Field name; nested keys are dotted, e.g. `meta_info.sync_key`.
+        field: String,
+        kept: serde_json::Value,
+        dropped: serde_json::Value,
     },
 
     UrlDeduped {
@@ -40,6 +88,16 @@ pub enum AppEvent {
         folder_path: String,
     },
 
+    /// The run removes more URLs or folders than allowed; it stops unless `forced`.
+    RemovalLimitExceeded {
+        /// `URLs` or `folders`.
+        kind: String,
+        removed: usize,
+        total: usize,
+        limit: String,
+        forced: bool,
+    },
+
     Finished {
         stats: NormalizeStats,
     },
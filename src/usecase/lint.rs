@@ -0,0 +1,472 @@
+use crate::domain::traits::UrlCanonicalizer;
+use crate::usecase::canonical::canonicalize_all;
+use crate::usecase::normalize::folder_key;
+use crate::usecase::validate::Severity;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Chrome timestamps count microseconds since 1601-01-01 UTC; this is 1990-01-01.
+const EARLIEST_TIMESTAMP: u64 = 12_275_625_600_000_000;
+/// 2100-01-01 in the same unit.
+const LATEST_TIMESTAMP: u64 = 15_746_918_400_000_000;
+/// Seconds from 1601-01-01 to the Unix epoch.
+const WINDOWS_TO_UNIX_SECS: i64 = 11_644_473_600;
+
+/// Health checks `lint` runs on an input file, before any normalization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// Same canonical URL twice in one folder; normalize would dedup it.
+    DuplicateUrl,
+    /// Same folder merge key in more than one place; normalize would merge them.
+    DuplicateFolder,
+    /// A folder below the root containers with no children; normalize would prune it.
+    EmptyFolder,
+    /// A URL without a scheme, with whitespace, or an http(s) URL without a host.
+    InvalidUrl,
+    /// A well-formed URL whose scheme is not http or https (`javascript:`, `file:`, ...).
+    NonHttpUrl,
+    /// A `date_*` value that is not a number or falls outside 1990..2100 (0 means unset).
+    TimestampOutOfRange,
+    /// A folder or URL whose name is missing or blank.
+    EmptyName,
+    /// A node whose `type` is neither `folder` nor `url`.
+    UnknownNodeType,
+    /// A folder with more direct children than [`LintOptions::huge_folder_children`].
+    HugeFolder,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 9] = [
+        LintRule::DuplicateUrl,
+        LintRule::DuplicateFolder,
+        LintRule::EmptyFolder,
+        LintRule::InvalidUrl,
+        LintRule::NonHttpUrl,
+        LintRule::TimestampOutOfRange,
+        LintRule::EmptyName,
+        LintRule::UnknownNodeType,
+        LintRule::HugeFolder,
+    ];
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LintRule::DuplicateUrl => "duplicate-url",
+            LintRule::DuplicateFolder => "duplicate-folder",
+            LintRule::EmptyFolder => "empty-folder",
+            LintRule::InvalidUrl => "invalid-url",
+            LintRule::NonHttpUrl => "non-http-url",
+            LintRule::TimestampOutOfRange => "timestamp-out-of-range",
+            LintRule::EmptyName => "empty-name",
+            LintRule::UnknownNodeType => "unknown-node-type",
+            LintRule::HugeFolder => "huge-folder",
+        })
+    }
+}
+
+impl FromStr for LintRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        LintRule::ALL
+            .into_iter()
+            .find(|rule| rule.to_string() == s)
+            .ok_or_else(|| {
+                let known: Vec<String> = LintRule::ALL.iter().map(ToString::to_string).collect();
+                anyhow!(
+                    "unknown lint rule: {s} (expected one of {})",
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+/// Which rules run, and their thresholds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintOptions {
+    pub disabled: BTreeSet<LintRule>,
+    pub huge_folder_children: usize,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            disabled: BTreeSet::new(),
+            huge_folder_children: 1000,
+        }
+    }
+}
+
+impl LintOptions {
+    pub fn set_enabled(&mut self, rule: LintRule, enabled: bool) {
+        if enabled {
+            self.disabled.remove(&rule);
+        } else {
+            self.disabled.insert(rule);
+        }
+    }
+
+    fn enabled(&self, rule: LintRule) -> bool {
+        !self.disabled.contains(&rule)
+    }
+}
+
+/// One finding, located by JSON pointer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintWarning {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}: {}",
+            self.severity, self.rule, self.path, self.message
+        )
+    }
+}
+
+/// Every finding in document order, with a count per rule.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LintReport {
+    pub warnings: Vec<LintWarning>,
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl LintReport {
+    /// One line per warning.
+    pub fn to_text(&self) -> String {
+        self.warnings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Checks any parsed Bookmarks document, however far it is from the schema, for problems
+/// worth knowing about before normalizing it. Nodes are walked iteratively.
+pub fn lint_document(
+    document: &Value,
+    canonicalizer: &dyn UrlCanonicalizer,
+    options: &LintOptions,
+) -> LintReport {
+    let nodes = located_nodes(document);
+    let canonical = canonical_keys(&nodes, canonicalizer);
+
+    let mut warnings: Vec<LintWarning> = Vec::new();
+    let mut warn = |rule: LintRule, path: &str, message: String| {
+        if options.enabled(rule) {
+            warnings.push(LintWarning {
+                rule,
+                severity: Severity::Warning,
+                path: path.to_string(),
+                message,
+            });
+        }
+    };
+
+    // First pointer seen for each folder merge key.
+    let mut folder_owner: HashMap<String, &str> = HashMap::new();
+
+    for (pointer, node, is_root) in nodes.iter() {
+        let (pointer, is_root) = (pointer.as_str(), *is_root);
+        let node_type = node.get("type").and_then(Value::as_str);
+        let name = node.get("name").and_then(Value::as_str);
+        let children = node.get("children").and_then(Value::as_array);
+
+        match node_type {
+            Some("folder") | Some("url") => {}
+            Some(other) => warn(
+                LintRule::UnknownNodeType,
+                pointer,
+                format!("node type {other:?} is neither folder nor url"),
+            ),
+            None => warn(
+                LintRule::UnknownNodeType,
+                pointer,
+                "node has no type".to_string(),
+            ),
+        }
+
+        if !is_root
+            && matches!(node_type, Some("folder") | Some("url"))
+            && name.is_none_or(|n| n.trim().is_empty())
+        {
+            warn(
+                LintRule::EmptyName,
+                pointer,
+                format!("{} has no name", node_type.unwrap_or_default()),
+            );
+        }
+
+        for field in ["date_added", "date_modified", "date_last_used"] {
+            if let Some(value) = node.get(field) {
+                if let Some(problem) = timestamp_problem(value) {
+                    warn(
+                        LintRule::TimestampOutOfRange,
+                        pointer,
+                        format!("{field} {value} {problem}"),
+                    );
+                }
+            }
+        }
+
+        if node_type == Some("url") {
+            let url = node.get("url").and_then(Value::as_str).unwrap_or_default();
+            match url_problem(url) {
+                Some(UrlProblem::Invalid(why)) => {
+                    warn(LintRule::InvalidUrl, pointer, format!("{url:?} {why}"))
+                }
+                Some(UrlProblem::NonHttp(scheme)) => warn(
+                    LintRule::NonHttpUrl,
+                    pointer,
+                    format!("{url:?} uses the {scheme}: scheme"),
+                ),
+                None => {}
+            }
+        }
+
+        if node_type != Some("folder") {
+            continue;
+        }
+
+        if !is_root {
+            if let Some(name) = name.filter(|n| !n.trim().is_empty()) {
+                let key = folder_key(name);
+                match folder_owner.get(key.as_str()) {
+                    Some(first) => warn(
+                        LintRule::DuplicateFolder,
+                        pointer,
+                        format!("folder {name:?} would be merged with {first}"),
+                    ),
+                    None => {
+                        folder_owner.insert(key, pointer);
+                    }
+                }
+            }
+            if children.is_none_or(|c| c.is_empty()) {
+                warn(
+                    LintRule::EmptyFolder,
+                    pointer,
+                    format!("folder {:?} is empty", name.unwrap_or_default()),
+                );
+            }
+        }
+
+        let Some(children) = children else {
+            continue;
+        };
+        if children.len() > options.huge_folder_children {
+            warn(
+                LintRule::HugeFolder,
+                pointer,
+                format!(
+                    "folder has {} children (more than {})",
+                    children.len(),
+                    options.huge_folder_children
+                ),
+            );
+        }
+
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (i, child) in children.iter().enumerate() {
+            if child.get("type").and_then(Value::as_str) != Some("url") {
+                continue;
+            }
+            let Some(key) = child
+                .get("url")
+                .and_then(Value::as_str)
+                .and_then(|u| canonical.get(u))
+            else {
+                continue;
+            };
+            if let Some(first) = seen.insert(key.as_str(), i) {
+                seen.insert(key.as_str(), first);
+                warn(
+                    LintRule::DuplicateUrl,
+                    &format!("{pointer}/children/{i}"),
+                    format!("duplicate of {pointer}/children/{first} ({key})"),
+                );
+            }
+        }
+    }
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for w in warnings.iter() {
+        *counts.entry(w.rule.to_string()).or_default() += 1;
+    }
+    LintReport { warnings, counts }
+}
+
+/// Every node under `roots` in pre-order, with its JSON pointer and whether it is a root
+/// container.
+fn located_nodes(document: &Value) -> Vec<(String, &Value, bool)> {
+    let mut out = Vec::new();
+    let Some(roots) = document.get("roots").and_then(Value::as_object) else {
+        return out;
+    };
+    let mut stack: Vec<(String, &Value, bool)> = roots
+        .iter()
+        .rev()
+        .map(|(key, root)| {
+            let key = key.replace('~', "~0").replace('/', "~1");
+            (format!("/roots/{key}"), root, true)
+        })
+        .collect();
+    while let Some((pointer, node, is_root)) = stack.pop() {
+        if let Some(children) = node.get("children").and_then(Value::as_array) {
+            for (i, child) in children.iter().enumerate().rev() {
+                stack.push((format!("{pointer}/children/{i}"), child, false));
+            }
+        }
+        out.push((pointer, node, is_root));
+    }
+    out
+}
+
+fn canonical_keys<'a>(
+    nodes: &[(String, &'a Value, bool)],
+    canonicalizer: &dyn UrlCanonicalizer,
+) -> HashMap<&'a str, String> {
+    let distinct: BTreeSet<&str> = nodes
+        .iter()
+        .filter(|(_, n, _)| n.get("type").and_then(Value::as_str) == Some("url"))
+        .filter_map(|(_, n, _)| n.get("url").and_then(Value::as_str))
+        .collect();
+    let urls: Vec<&str> = distinct.into_iter().collect();
+    let keys = canonicalize_all(canonicalizer, &urls);
+    urls.into_iter().zip(keys).collect()
+}
+
+enum UrlProblem {
+    Invalid(&'static str),
+    NonHttp(String),
+}
+
+/// Dependency-free URL sanity check: an RFC 3986 scheme, no whitespace, and a host for
+/// http(s).
+fn url_problem(url: &str) -> Option<UrlProblem> {
+    if url.is_empty() {
+        return Some(UrlProblem::Invalid("is empty"));
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Some(UrlProblem::Invalid(
+            "contains whitespace or control characters",
+        ));
+    }
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return Some(UrlProblem::Invalid("has no scheme"));
+    };
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return Some(UrlProblem::Invalid("has no scheme"));
+    }
+    let scheme = scheme.to_ascii_lowercase();
+    if scheme != "http" && scheme != "https" {
+        return Some(UrlProblem::NonHttp(scheme));
+    }
+    let host = rest
+        .strip_prefix("//")
+        .map(|r| r.split(['/', '?', '#']).next().unwrap_or_default())
+        .map(|authority| authority.rsplit('@').next().unwrap_or_default())
+        .unwrap_or_default();
+    if host.is_empty() || host.starts_with(':') {
+        return Some(UrlProblem::Invalid("has no host"));
+    }
+    None
+}
+
+/// Why a `date_*` value is suspicious, if it is. `"0"` (never set) is fine.
+fn timestamp_problem(value: &Value) -> Option<String> {
+    let Some(text) = value.as_str() else {
+        return Some("is not a string".to_string());
+    };
+    let Ok(micros) = text.parse::<u64>() else {
+        return Some("is not a number".to_string());
+    };
+    if micros == 0 || (EARLIEST_TIMESTAMP..LATEST_TIMESTAMP).contains(&micros) {
+        return None;
+    }
+    let secs = (micros / 1_000_000) as i64 - WINDOWS_TO_UNIX_SECS;
+    let when = chrono::DateTime::from_timestamp(secs, 0)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "an unrepresentable date".to_string());
+    Some(format!("is {when}, outside 1990..2100"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
+    use serde_json::json;
+
+    #[test]
+    fn reports_each_rule_once_by_pointer() {
+        let doc = json!({"roots": {"bookmark_bar": {"type": "folder", "children": [
+            {"type": "folder", "name": "Work", "children": [
+                {"type": "url", "name": "a", "url": "https://example.com/", "date_added": "13300000000000000"},
+                {"type": "url", "name": "b", "url": "https://EXAMPLE.com/#top"},
+                {"type": "url", "name": "c", "url": "javascript:void(0)"},
+                {"type": "url", "name": " ", "url": "https:///path", "date_added": "42"}
+            ]},
+            {"type": "folder", "name": "work ", "children": []},
+            {"type": "separator"}
+        ]}}});
+
+        let report = lint_document(&doc, &DefaultUrlCanonicalizer, &LintOptions::default());
+        let found: Vec<String> = report
+            .warnings
+            .iter()
+            .map(|w| format!("{} {}", w.rule, w.path))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "duplicate-url /roots/bookmark_bar/children/0/children/1",
+                "non-http-url /roots/bookmark_bar/children/0/children/2",
+                "empty-name /roots/bookmark_bar/children/0/children/3",
+                "timestamp-out-of-range /roots/bookmark_bar/children/0/children/3",
+                "invalid-url /roots/bookmark_bar/children/0/children/3",
+                "duplicate-folder /roots/bookmark_bar/children/1",
+                "empty-folder /roots/bookmark_bar/children/1",
+                "unknown-node-type /roots/bookmark_bar/children/2",
+            ]
+        );
+        assert!(report.warnings[3]
+            .message
+            .contains("is 1601-01-01, outside 1990..2100"));
+        assert_eq!(report.counts["duplicate-url"], 1);
+
+        let mut options = LintOptions {
+            huge_folder_children: 3,
+            ..LintOptions::default()
+        };
+        options.set_enabled("duplicate-url".parse().unwrap(), false);
+        let report = lint_document(&doc, &DefaultUrlCanonicalizer, &options);
+        assert!(!report.counts.contains_key("duplicate-url"));
+        assert_eq!(report.counts["huge-folder"], 1);
+    }
+
+    #[test]
+    fn rule_ids_round_trip() {
+        for rule in LintRule::ALL {
+            assert_eq!(rule.to_string().parse::<LintRule>().unwrap(), rule);
+        }
+        assert!("no-such-rule".parse::<LintRule>().is_err());
+    }
+}
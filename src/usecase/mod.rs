@@ -1,6 +1,10 @@
 //! Usecase layer: application workflows + events.
 
+pub mod canonical;
+pub mod conservation;
 pub mod event;
+pub mod lint;
 pub mod normalize;
+pub mod report;
 pub mod stats;
 pub mod validate;
@@ -1,13 +1,21 @@
-use std::collections::BTreeMap;
+use crate::domain::model::NodeKind;
+use crate::domain::traits::UrlCanonicalizer;
+use crate::usecase::canonical::canonicalize_all;
+use crate::usecase::normalize::convergence::FolderCollision;
+use crate::usecase::normalize::folder_merge::folder_key;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Handle(pub usize);
 
 #[derive(Debug, Clone, Default)]
 pub struct ArenaNode {
-    pub node_type: String,
+    pub kind: NodeKind,
     pub name: Option<String>,
     pub url: Option<String>,
+    /// Canonical form of `url`, computed once by [`Arena::canonicalize_urls`].
+    pub canonical_url: Option<String>,
     pub children: Vec<Handle>,
 
     pub date_added: Option<String>,
@@ -21,12 +29,20 @@ pub struct ArenaNode {
     pub show_icon: Option<bool>,
 
     pub extra: BTreeMap<String, serde_json::Value>,
+    /// `type` as read, for a node whose own did not fit; see `BookmarkNodeDto::raw_type`.
+    pub raw_type: Option<Option<serde_json::Value>>,
 
-    pub root_key: Option<String>,
-    pub path: String,
+    /// Shared per root container rather than copied into every node.
+    pub root_key: Option<Arc<str>>,
+    /// Parent in the input file; unlike `Arena::parent` it never changes.
+    pub origin_parent: Option<Handle>,
+    /// Index among the input parent's children.
+    pub origin_index: usize,
     pub depth: usize,
 
     pub deleted: bool,
+    /// Left exactly as read: no phase merges, deduplicates, prunes or re-identifies it.
+    pub preserved: bool,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -34,10 +50,411 @@ pub struct Arena {
     pub nodes: Vec<ArenaNode>,
     pub parent: Vec<Option<Handle>>,
     pub root_container: BTreeMap<String, Handle>,
+    /// Live folder counts by merge key; kept current by [`Arena::delete`] and [`Arena::rename`].
+    pub folders: FolderRegistry,
+    /// Parents whose child list holds tombstones, awaiting [`Arena::compact_children`].
+    dirty: Vec<Handle>,
+    /// Deleted nodes and the node their data went into; see [`Arena::absorb`].
+    absorbed_by: HashMap<Handle, Handle>,
 }
 
 impl Arena {
     pub fn is_root_container(&self, h: Handle) -> bool {
         self.nodes[h.0].depth == 0
     }
+
+    /// Counts every live folder once. Called after the arena is built; later changes go
+    /// through `delete` and `rename`, so the loop never has to recount.
+    pub fn index_folders(&mut self) {
+        let mut folders = FolderRegistry::default();
+        for h in 0..self.nodes.len() {
+            if let Some(key) = self.registry_key(Handle(h)) {
+                folders.add(key);
+            }
+        }
+        self.folders = folders;
+    }
+
+    /// Marks the nodes at `pointers` (JSON pointers into the input) preserved and recounts
+    /// the folders without them; returns how many were found.
+    pub fn preserve(&mut self, pointers: &BTreeSet<String>) -> usize {
+        let mut found = 0;
+        for h in 0..self.nodes.len() {
+            if pointers.contains(&self.json_pointer(Handle(h))) {
+                self.nodes[h].preserved = true;
+                found += 1;
+            }
+        }
+        self.index_folders();
+        found
+    }
+
+    /// Fills `canonical_url` for every URL node, so dedup and sorting never canonicalize again.
+    pub fn canonicalize_urls(&mut self, canonicalizer: &dyn UrlCanonicalizer) {
+        let (handles, urls): (Vec<usize>, Vec<&str>) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.kind == NodeKind::Url)
+            .filter_map(|(i, n)| n.url.as_deref().map(|u| (i, u)))
+            .unzip();
+        let keys = canonicalize_all(canonicalizer, &urls);
+        for (i, key) in handles.into_iter().zip(keys) {
+            self.nodes[i].canonical_url = Some(key);
+        }
+    }
+
+    /// Marks `h` deleted, leaving a tombstone in its parent's child list. Readers skip
+    /// deleted children; each phase ends with one [`Arena::compact_children`] pass, so
+    /// removing many siblings stays linear.
+    pub fn delete(&mut self, h: Handle) {
+        if let Some(key) = self.registry_key(h) {
+            self.folders.remove(&key);
+        }
+        self.nodes[h.0].deleted = true;
+        if let Some(p) = self.parent[h.0] {
+            self.dirty.push(p);
+        }
+    }
+
+    /// Deletes `loser` as a merge or dedup loser of `winner`, remembering where it went.
+    pub fn absorb(&mut self, loser: Handle, winner: Handle) {
+        self.absorbed_by.insert(loser, winner);
+        self.delete(loser);
+    }
+
+    /// The node `h` was absorbed into, if it was deleted by [`Arena::absorb`].
+    pub fn absorber(&self, h: Handle) -> Option<Handle> {
+        self.absorbed_by.get(&h).copied()
+    }
+
+    /// Marks every node still reachable from a root container.
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut live = vec![false; self.nodes.len()];
+        let mut stack: Vec<Handle> = self.root_container.values().copied().collect();
+        while let Some(h) = stack.pop() {
+            if self.nodes[h.0].deleted {
+                continue;
+            }
+            live[h.0] = true;
+            stack.extend(self.nodes[h.0].children.iter().copied());
+        }
+        live
+    }
+
+    /// Appends the live children of `from` to `to` and reparents them.
+    pub fn move_children(&mut self, from: Handle, to: Handle) {
+        let children = std::mem::take(&mut self.nodes[from.0].children);
+        for ch in children {
+            if self.nodes[ch.0].deleted {
+                continue;
+            }
+            self.parent[ch.0] = Some(to);
+            self.nodes[to.0].children.push(ch);
+        }
+    }
+
+    /// Drops tombstones from every child list touched since the last compaction.
+    pub fn compact_children(&mut self) {
+        let mut dirty = std::mem::take(&mut self.dirty);
+        dirty.sort_unstable();
+        dirty.dedup();
+        for p in dirty {
+            let mut children = std::mem::take(&mut self.nodes[p.0].children);
+            children.retain(|c| !self.nodes[c.0].deleted);
+            self.nodes[p.0].children = children;
+        }
+    }
+
+    pub fn rename(&mut self, h: Handle, name: String) {
+        if let Some(key) = self.registry_key(h) {
+            self.folders.remove(&key);
+        }
+        self.nodes[h.0].name = Some(name);
+        if let Some(key) = self.registry_key(h) {
+            self.folders.add(key);
+        }
+    }
+
+    /// Merge key under which `h` is counted: live, named folders outside the root containers
+    /// that are not preserved.
+    fn registry_key(&self, h: Handle) -> Option<String> {
+        let node = &self.nodes[h.0];
+        if node.deleted
+            || node.preserved
+            || node.kind != NodeKind::Folder
+            || self.is_root_container(h)
+        {
+            return None;
+        }
+        node.name.as_deref().map(folder_key)
+    }
+}
+
+/// Folder counts by [`folder_key`], the same key the merge groups by.
+/// Root containers are never merged, so they are not counted.
+#[derive(Debug, Clone, Default)]
+pub struct FolderRegistry {
+    counts: HashMap<String, usize>,
+    /// Number of keys with a count above one.
+    colliding: usize,
+}
+
+impl FolderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(&mut self, key: String) {
+        let count = self.counts.entry(key).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            self.colliding += 1;
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        let Some(count) = self.counts.get_mut(key) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 1 {
+            self.colliding -= 1;
+        } else if *count == 0 {
+            self.counts.remove(key);
+        }
+    }
+
+    pub fn counts(&self) -> &HashMap<String, usize> {
+        &self.counts
+    }
+
+    pub fn all_unique(&self) -> bool {
+        self.colliding == 0
+    }
+
+    /// Keys carried by more than one folder, sorted by key.
+    pub fn collisions(&self) -> Vec<FolderCollision> {
+        let mut out: Vec<FolderCollision> = self
+            .counts
+            .iter()
+            .filter(|(_, &count)| count > 1)
+            .map(|(key, &count)| FolderCollision {
+                key: key.clone(),
+                count,
+            })
+            .collect();
+        out.sort_by(|a, b| a.key.cmp(&b.key));
+        out
+    }
+
+    pub fn print_counts(&self) {
+        let mut sorted: Vec<_> = self.counts.iter().collect();
+        sorted.sort_by_key(|(name, _)| (*name).clone());
+        eprintln!("Folder counts:");
+        for (name, count) in sorted {
+            eprintln!("  {name}: {count}");
+        }
+        let all = self.all_unique();
+        eprintln!("All unique: {all}");
+    }
+
+    pub fn print_final_registry(&self) {
+        let mut sorted: Vec<_> = self.counts.iter().collect();
+        sorted.sort_by_key(|(name, _)| (*name).clone());
+        eprintln!("\nFinal Folder Registry:");
+        eprintln!("folder_name | count (number of occurrences)");
+        eprintln!("------------|-----------------------------");
+        for (name, count) in sorted {
+            eprintln!("{name:<12} | {count} (number of occurrences)");
+        }
+        let all = self.all_unique();
+        eprintln!("All unique: {all}");
+    }
+}
+
+impl Arena {
+    /// Folder names from the root container down to `h` at its current location, joined
+    /// with ` / `. A root container without a name shows its root key.
+    pub fn name_path(&self, h: Handle) -> String {
+        let mut names = Vec::new();
+        let mut cur = Some(h);
+        while let Some(c) = cur {
+            let node = &self.nodes[c.0];
+            let name = match (&node.name, &node.root_key) {
+                (Some(name), _) => name.as_str(),
+                (None, Some(root)) if self.is_root_container(c) => root,
+                (None, _) => "",
+            };
+            names.push(name);
+            cur = self.parent[c.0];
+        }
+        names.reverse();
+        names.join(" / ")
+    }
+
+    /// Child indices from the root container down to `h` in the input file.
+    fn origin_indices(&self, h: Handle) -> Vec<usize> {
+        let mut out = Vec::new();
+        let mut cur = h;
+        while let Some(p) = self.nodes[cur.0].origin_parent {
+            out.push(self.nodes[cur.0].origin_index);
+            cur = p;
+        }
+        out.reverse();
+        out
+    }
+
+    /// Provenance: the input location as the root key followed by child indices, e.g.
+    /// `bookmark_bar/0/2`. Computed on demand and unaffected by merges.
+    pub fn origin_path(&self, h: Handle) -> String {
+        let mut out = self.nodes[h.0]
+            .root_key
+            .as_deref()
+            .unwrap_or_default()
+            .to_string();
+        for idx in self.origin_indices(h) {
+            out.push('/');
+            out.push_str(&idx.to_string());
+        }
+        out
+    }
+
+    /// The input location by name: the root key, then the names of the folders below it and
+    /// of `h`, e.g. `/bookmark_bar/Dev/Rust`. Like [`Arena::origin_path`] it ignores merges.
+    pub fn origin_name_path(&self, h: Handle) -> String {
+        let mut names = Vec::new();
+        let mut cur = h;
+        while let Some(p) = self.nodes[cur.0].origin_parent {
+            names.push(self.nodes[cur.0].name.as_deref().unwrap_or_default());
+            cur = p;
+        }
+        names.push(self.nodes[h.0].root_key.as_deref().unwrap_or_default());
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    /// RFC 6901 pointer to `h` in the input file, e.g.
+    /// `/roots/bookmark_bar/children/0/children/2`.
+    pub fn json_pointer(&self, h: Handle) -> String {
+        let root = self.nodes[h.0].root_key.as_deref().unwrap_or_default();
+        let mut out = format!("/roots/{}", root.replace('~', "~0").replace('/', "~1"));
+        for idx in self.origin_indices(h) {
+            out.push_str("/children/");
+            out.push_str(&idx.to_string());
+        }
+        out
+    }
+
+    /// True when `a` is `b` or one of its ancestors.
+    pub fn is_ancestor_of(&self, a: Handle, b: Handle) -> bool {
+        let mut cur = Some(b);
+        while let Some(c) = cur {
+            if c == a {
+                return true;
+            }
+            cur = self.parent[c.0];
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// bookmark_bar -> Work (index 0) -> Docs (index 2); a/b is a second, unnamed root.
+    fn located() -> Arena {
+        let mut arena = Arena::default();
+        for (name, root, origin_parent, origin_index, depth) in [
+            (Some("Bookmarks bar"), "bookmark_bar", None, 0, 0),
+            (Some("Work"), "bookmark_bar", Some(Handle(0)), 0, 1),
+            (Some("Docs"), "bookmark_bar", Some(Handle(1)), 2, 2),
+            (None, "a/b", None, 0, 0),
+        ] {
+            arena.nodes.push(ArenaNode {
+                name: name.map(str::to_string),
+                root_key: Some(root.into()),
+                origin_parent,
+                origin_index,
+                depth,
+                ..ArenaNode::default()
+            });
+            arena.parent.push(origin_parent);
+        }
+        arena
+    }
+
+    #[test]
+    fn json_pointer_follows_child_indices_and_escapes_root_key() {
+        let arena = located();
+        assert_eq!(
+            arena.json_pointer(Handle(2)),
+            "/roots/bookmark_bar/children/0/children/2"
+        );
+        assert_eq!(arena.origin_path(Handle(2)), "bookmark_bar/0/2");
+        assert_eq!(arena.json_pointer(Handle(3)), "/roots/a~1b");
+    }
+
+    #[test]
+    fn name_path_follows_current_parent_and_provenance_does_not() {
+        let mut arena = located();
+        assert_eq!(arena.name_path(Handle(2)), "Bookmarks bar / Work / Docs");
+        assert_eq!(arena.name_path(Handle(3)), "a/b");
+
+        // Docs moves directly under the root container, e.g. by a merge.
+        arena.parent[2] = Some(Handle(0));
+        assert_eq!(arena.name_path(Handle(2)), "Bookmarks bar / Docs");
+        assert_eq!(arena.origin_path(Handle(2)), "bookmark_bar/0/2");
+        assert_eq!(arena.origin_name_path(Handle(2)), "/bookmark_bar/Work/Docs");
+    }
+
+    #[test]
+    fn deletes_leave_tombstones_until_compaction() {
+        let mut arena = Arena::default();
+        for i in 0..5 {
+            arena.nodes.push(ArenaNode {
+                kind: NodeKind::Url,
+                ..ArenaNode::default()
+            });
+            arena.parent.push((i > 0).then_some(Handle(0)));
+        }
+        arena.nodes[0].children = (1..5).map(Handle).collect();
+
+        arena.delete(Handle(1));
+        arena.delete(Handle(3));
+        assert_eq!(arena.nodes[0].children.len(), 4);
+
+        arena.compact_children();
+        assert_eq!(arena.nodes[0].children, vec![Handle(2), Handle(4)]);
+    }
+
+    #[test]
+    fn folder_registry_tracks_deletes_and_renames() {
+        let mut arena = Arena::default();
+        for (name, depth) in [("Bar", 0), ("Work", 1), ("work ", 1), ("Home", 2)] {
+            arena.nodes.push(ArenaNode {
+                kind: NodeKind::Folder,
+                name: Some(name.to_string()),
+                depth,
+                ..ArenaNode::default()
+            });
+            arena.parent.push(None);
+        }
+        arena.index_folders();
+        assert!(!arena.folders.all_unique());
+        assert_eq!(arena.folders.collisions()[0].key, "work");
+        assert!(!arena.folders.counts().contains_key("bar"));
+
+        arena.rename(Handle(3), "Work".to_string());
+        assert_eq!(arena.folders.counts()["work"], 3);
+
+        arena.delete(Handle(1));
+        arena.delete(Handle(2));
+        assert!(arena.folders.all_unique());
+        assert!(!arena.folders.counts().contains_key("home"));
+
+        arena.delete(Handle(3));
+        assert!(arena.folders.counts().is_empty());
+    }
 }
@@ -1,73 +1,86 @@
+use crate::domain::model::NodeKind;
 use crate::infrastructure::serde_json_adapter::{BookmarkNodeDto, BookmarksFileDto};
 use crate::usecase::normalize::arena::{Arena, ArenaNode, Handle};
 use crate::usecase::stats::NormalizeStats;
+use std::sync::Arc;
 
 pub fn build_arena_from_dto(input: &BookmarksFileDto, stats: &mut NormalizeStats) -> Arena {
     let mut arena = Arena::default();
 
     // Stable iteration over roots (BTreeMap).
     for (root_key, root_node) in input.roots.iter() {
+        // One shared allocation per root key, referenced by every node below it.
+        let root: Arc<str> = Arc::from(root_key.as_str());
+
         // Root container nodes are special: we allocate the container itself here,
         // and then allocate its immediate children separately so they start in merge space.
-        // Prevent `alloc_node` from also expanding `root_node.children`, which would
-        // duplicate the entire subtree and create unreachable nodes.
-        let mut root_shallow = root_node.clone();
-        root_shallow.children.clear();
+        let container = Handle(arena.nodes.len());
+        let mut node = arena_node(root_node, root.clone(), None, 0, 0);
         // Ensure root containers are treated as folders for processing
-        root_shallow.node_type = "folder".to_string();
-
-        let container = alloc_node(
-            &mut arena,
-            None,
-            root_key.clone(),
-            root_key.clone(),
-            0,
-            &root_shallow,
-        );
+        node.kind = NodeKind::Folder;
+        arena.nodes.push(node);
+        arena.parent.push(None);
         arena.root_container.insert(root_key.clone(), container);
 
         // Child folders under root containers are in merge space.
         let mut child_handles = Vec::with_capacity(root_node.children.len());
         for (i, child) in root_node.children.iter().enumerate() {
-            let child_path = format!("{root_key}/{i}");
-            let h = alloc_node(
-                &mut arena,
-                Some(container),
-                root_key.clone(),
-                child_path,
-                1,
-                child,
-            );
+            let h = alloc_node(&mut arena, container, root.clone(), i, child);
             child_handles.push(h);
         }
         arena.nodes[container.0].children = child_handles;
     }
 
+    index_arena(&mut arena, stats);
+    arena
+}
+
+/// Counts the input nodes and indexes folders once every node is allocated.
+pub fn index_arena(arena: &mut Arena, stats: &mut NormalizeStats) {
     for node in arena.nodes.iter() {
-        if node.node_type == "folder" {
+        if node.kind == NodeKind::Folder {
             stats.folders_seen += 1;
-        } else if node.node_type == "url" {
+        } else if node.kind == NodeKind::Url {
             stats.urls_seen += 1;
         }
     }
+    arena.index_folders();
+}
 
-    arena
+/// Handles in the order [`build_arena_from_dto`] allocates nodes: roots by key, each
+/// top-level child followed by its subtree, expanded level by level in reverse.
+pub fn allocation_order(arena: &Arena) -> Vec<Handle> {
+    let mut order = Vec::with_capacity(arena.nodes.len());
+    for &container in arena.root_container.values() {
+        order.push(container);
+        for &top in arena.nodes[container.0].children.iter() {
+            order.push(top);
+            let mut stack = vec![top];
+            while let Some(h) = stack.pop() {
+                for &kid in arena.nodes[h.0].children.iter().rev() {
+                    order.push(kid);
+                    if !arena.nodes[kid.0].children.is_empty() {
+                        stack.push(kid);
+                    }
+                }
+            }
+        }
+    }
+    order
 }
 
-fn alloc_node(
-    arena: &mut Arena,
-    parent: Option<Handle>,
-    root_key: String,
-    path: String,
-    depth: usize,
+pub fn arena_node(
     dto: &BookmarkNodeDto,
-) -> Handle {
-    let handle = Handle(arena.nodes.len());
-
-    arena.nodes.push(ArenaNode {
-        node_type: dto.node_type.clone(),
+    root_key: Arc<str>,
+    origin_parent: Option<Handle>,
+    origin_index: usize,
+    depth: usize,
+) -> ArenaNode {
+    ArenaNode {
+        kind: NodeKind::from(dto.node_type.as_str()),
         name: dto.name.clone(),
         url: dto.url.clone(),
+        canonical_url: None,
         children: Vec::new(),
         date_added: dto.date_added.clone(),
         date_modified: dto.date_modified.clone(),
@@ -78,66 +91,60 @@ fn alloc_node(
         source: dto.source.clone(),
         show_icon: dto.show_icon,
         extra: dto.extra.clone(),
+        raw_type: dto.raw_type.clone(),
         root_key: Some(root_key),
-        path,
+        origin_parent,
+        origin_index,
         depth,
         deleted: false,
-    });
-    arena.parent.push(parent);
-
-    if dto.children.is_empty() {
-        return handle;
+        preserved: false,
     }
+}
+
+/// Allocates `dto` as child `index` of `parent`, then its whole subtree.
+fn alloc_node(
+    arena: &mut Arena,
+    parent: Handle,
+    root_key: Arc<str>,
+    index: usize,
+    dto: &BookmarkNodeDto,
+) -> Handle {
+    let handle = Handle(arena.nodes.len());
+    let depth = arena.nodes[parent.0].depth + 1;
 
-    // Iterative expansion (no recursion).
-    type BuildStackItem = (Handle, Vec<(usize, BookmarkNodeDto)>, String, usize);
-    let mut stack: Vec<BuildStackItem> = Vec::new();
-    stack.push((
-        handle,
-        dto.children.iter().cloned().enumerate().collect(),
-        arena.nodes[handle.0].path.clone(),
+    arena.nodes.push(arena_node(
+        dto,
+        root_key.clone(),
+        Some(parent),
+        index,
         depth,
     ));
+    arena.parent.push(Some(parent));
 
-    while let Some((parent_h, mut kids, parent_path, parent_depth)) = stack.pop() {
-        if kids.is_empty() {
-            continue;
-        }
-        kids.reverse();
+    if dto.children.is_empty() {
+        return handle;
+    }
 
+    // Iterative expansion (no recursion). The stack borrows child slices from the input
+    // instead of cloning subtrees level by level.
+    let mut stack: Vec<(Handle, &[BookmarkNodeDto], usize)> = vec![(handle, &dto.children, depth)];
+
+    while let Some((parent_h, kids, parent_depth)) = stack.pop() {
         let mut built: Vec<Handle> = Vec::with_capacity(kids.len());
-        for (idx, kid) in kids {
-            let kid_path = format!("{parent_path}/{idx}");
+        for (idx, kid) in kids.iter().enumerate().rev() {
             let kid_h = Handle(arena.nodes.len());
 
-            arena.nodes.push(ArenaNode {
-                node_type: kid.node_type.clone(),
-                name: kid.name.clone(),
-                url: kid.url.clone(),
-                children: Vec::new(),
-                date_added: kid.date_added.clone(),
-                date_modified: kid.date_modified.clone(),
-                date_last_used: kid.date_last_used.clone(),
-                visit_count: kid.visit_count,
-                guid: kid.guid.clone(),
-                id: kid.id.clone(),
-                source: kid.source.clone(),
-                show_icon: kid.show_icon,
-                extra: kid.extra.clone(),
-                root_key: arena.nodes[parent_h.0].root_key.clone(),
-                path: kid_path.clone(),
-                depth: parent_depth + 1,
-                deleted: false,
-            });
+            arena.nodes.push(arena_node(
+                kid,
+                root_key.clone(),
+                Some(parent_h),
+                idx,
+                parent_depth + 1,
+            ));
             arena.parent.push(Some(parent_h));
 
             if !kid.children.is_empty() {
-                stack.push((
-                    kid_h,
-                    kid.children.iter().cloned().enumerate().collect(),
-                    kid_path,
-                    parent_depth + 1,
-                ));
+                stack.push((kid_h, &kid.children, parent_depth + 1));
             }
 
             built.push(kid_h);
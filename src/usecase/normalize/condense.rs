@@ -0,0 +1,385 @@
+use crate::domain::graph::{Graph, SccError, SccResult};
+use crate::domain::model::NodeKind;
+use crate::domain::traits::SccDetector;
+use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::cycles::{diagnose, CycleDiagnostic};
+use crate::usecase::normalize::graph::{build_identity_graph, IdentityGraph};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/// A parent→child folder edge removed to break a reused-identity cycle. `parent_path` and
+/// `child_path` locate the edge as found, before any cut of the same pass moved it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CutEdge {
+    pub identity: String,
+    pub parent_path: String,
+    pub child_path: String,
+    /// Canonical (shallowest) instance of `identity`; it received the cut folder's children.
+    pub moved_to: String,
+}
+
+/// Identity graph size as first seen, before any cycle was cut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphSummary {
+    pub nodes: usize,
+    pub edges: usize,
+    pub components: usize,
+    pub cyclic_components: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Condensed {
+    pub summary: GraphSummary,
+    /// Cyclic components found before cutting; filled only by [`condense`], whose arena still
+    /// mirrors the input file.
+    pub cycles: Vec<CycleDiagnostic>,
+    pub cuts: Vec<CutEdge>,
+    pub order: ProcessingOrder,
+}
+
+/// Processing order taken from the topologically sorted condensation of the identity graph.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingOrder {
+    /// Live folders reachable from a root, in breadth-first order.
+    live: Vec<Handle>,
+    /// Per handle: topological position of the folder's SCC (`usize::MAX` for non-folders).
+    rank: Vec<usize>,
+    /// Per handle: depth below its root container, measured on the current tree.
+    depth: Vec<usize>,
+}
+
+impl ProcessingOrder {
+    pub fn rank(&self, h: Handle) -> usize {
+        self.rank.get(h.0).copied().unwrap_or(usize::MAX)
+    }
+
+    /// Live folders with descendants before ancestors: reverse topological rank, then deepest first.
+    ///
+    /// A containment edge never points to an earlier component, and inside one component the
+    /// child is deeper than its parent, so this is a valid bottom-up order.
+    pub fn children_first(&self) -> Vec<Handle> {
+        let mut out = self.live.clone();
+        out.sort_by_key(|h| Reverse((self.rank[h.0], self.depth[h.0], h.0)));
+        out
+    }
+}
+
+/// Builds the identity graph, cuts every reused-identity cycle and returns the
+/// condensed processing order for the resulting (acyclic) graph.
+///
+/// This is the first condensation of a run: every cycle is also diagnosed against the
+/// input file.
+pub fn condense(arena: &mut Arena, scc: &dyn SccDetector) -> Result<Condensed, SccError> {
+    condense_with(arena, scc, true)
+}
+
+/// Like [`condense`], for a tree that merges have already reshaped: cycles are cut but not
+/// diagnosed, since JSON pointers into the current arena no longer locate the input file.
+pub fn recondense(arena: &mut Arena, scc: &dyn SccDetector) -> Result<Condensed, SccError> {
+    condense_with(arena, scc, false)
+}
+
+fn condense_with(
+    arena: &mut Arena,
+    scc: &dyn SccDetector,
+    diagnose_cycles: bool,
+) -> Result<Condensed, SccError> {
+    let mut ig = build_identity_graph(arena);
+    let mut res = scc.try_compute_scc(&ig.graph)?;
+    let summary = GraphSummary {
+        nodes: ig.graph.node_count(),
+        edges: ig.graph.edge_count(),
+        components: res.components.len(),
+        cyclic_components: res.cyclic_component.iter().filter(|&&b| b).count(),
+    };
+
+    let mut cycles = Vec::new();
+    let mut cuts = Vec::new();
+    if summary.cyclic_components > 0 {
+        if diagnose_cycles {
+            cycles = diagnose(arena, &ig, &res);
+        }
+        cuts = break_identity_cycles(arena, &ig, &res);
+        ig = build_identity_graph(arena);
+        res = scc.try_compute_scc(&ig.graph)?;
+    }
+
+    let comp_rank = component_ranks(&ig.graph, &res);
+    let (live, depth) = walk(arena);
+    let rank = ig
+        .identity_of
+        .iter()
+        .map(|i| i.map_or(usize::MAX, |i| comp_rank[res.component_of[i]]))
+        .collect();
+
+    Ok(Condensed {
+        summary,
+        cycles,
+        cuts,
+        order: ProcessingOrder { live, rank, depth },
+    })
+}
+
+/// Cuts, inside each cyclic component, every edge whose parent identity does not come
+/// strictly before the child identity. Identities are ordered by their shallowest instance
+/// (breadth-first position), so the edge from the shallowest parent is the one kept.
+///
+/// The cut child folder is a repeat of an identity already present higher up: its children
+/// move into that canonical instance and the repeat is deleted.
+fn break_identity_cycles(arena: &mut Arena, ig: &IdentityGraph, res: &SccResult) -> Vec<CutEdge> {
+    let (live, _) = walk(arena);
+
+    let mut pos = vec![usize::MAX; arena.nodes.len()];
+    let mut canonical: HashMap<usize, Handle> = HashMap::new();
+    for (i, h) in live.iter().enumerate() {
+        pos[h.0] = i;
+        if let Some(id) = ig.identity_of[h.0] {
+            canonical.entry(id).or_insert(*h);
+        }
+    }
+    let key = |id: usize| pos[canonical[&id].0];
+
+    let candidates: Vec<(Handle, usize)> = live
+        .iter()
+        .filter_map(|&c| {
+            let p = arena.parent[c.0]?;
+            let (pi, ci) = (ig.identity_of[p.0]?, ig.identity_of[c.0]?);
+            let comp = res.component_of[pi];
+            let cut =
+                comp == res.component_of[ci] && res.cyclic_component[comp] && key(pi) >= key(ci);
+            cut.then_some((c, ci))
+        })
+        .collect();
+
+    // Each cut moves the cut folder's children, so locate every edge before cutting any.
+    let found: Vec<(String, String)> = candidates
+        .iter()
+        .map(|&(c, _)| {
+            let parent = arena.parent[c.0].expect("candidates have a parent");
+            (arena.name_path(parent), arena.name_path(c))
+        })
+        .collect();
+
+    let mut cuts = Vec::new();
+    for ((c, ci), (parent_path, child_path)) in candidates.into_iter().zip(found) {
+        // Canonical instances are never cut: their parent always has an earlier key.
+        let target = canonical[&ci];
+
+        arena.move_children(c, target);
+        arena.absorb(c, target);
+
+        cuts.push(CutEdge {
+            identity: ig.ids[ci].clone(),
+            parent_path,
+            child_path,
+            moved_to: arena.name_path(target),
+        });
+    }
+    arena.compact_children();
+    cuts
+}
+
+/// Kahn's algorithm over the condensation; ready components are taken by their smallest
+/// identity so the order is deterministic.
+fn component_ranks(graph: &Graph, res: &SccResult) -> Vec<usize> {
+    let n = res.components.len();
+    let mut out: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (u, outs) in graph.edges.iter().enumerate() {
+        for &v in outs {
+            let (cu, cv) = (res.component_of[u], res.component_of[v]);
+            if cu != cv {
+                out[cu].push(cv);
+            }
+        }
+    }
+
+    let mut indegree = vec![0usize; n];
+    for outs in out.iter_mut() {
+        outs.sort_unstable();
+        outs.dedup();
+        for &v in outs.iter() {
+            indegree[v] += 1;
+        }
+    }
+
+    let first = |c: usize| res.components[c].first().copied().unwrap_or(usize::MAX);
+    let mut ready: BinaryHeap<Reverse<(usize, usize)>> = (0..n)
+        .filter(|&c| indegree[c] == 0)
+        .map(|c| Reverse((first(c), c)))
+        .collect();
+
+    let mut rank = vec![usize::MAX; n];
+    let mut next = 0;
+    while let Some(Reverse((_, c))) = ready.pop() {
+        rank[c] = next;
+        next += 1;
+        for &v in out[c].iter() {
+            indegree[v] -= 1;
+            if indegree[v] == 0 {
+                ready.push(Reverse((first(v), v)));
+            }
+        }
+    }
+    rank
+}
+
+/// Breadth-first walk over live folders from every root container.
+fn walk(arena: &Arena) -> (Vec<Handle>, Vec<usize>) {
+    let mut live = Vec::new();
+    let mut depth = vec![0usize; arena.nodes.len()];
+    let mut queue: VecDeque<Handle> = arena.root_container.values().copied().collect();
+
+    while let Some(h) = queue.pop_front() {
+        let node = &arena.nodes[h.0];
+        if node.deleted || node.kind != NodeKind::Folder {
+            continue;
+        }
+        live.push(h);
+        for ch in node.children.iter() {
+            depth[ch.0] = depth[h.0] + 1;
+            queue.push_back(*ch);
+        }
+    }
+    (live, depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::scc_kosaraju::KosarajuSccDetector;
+    use crate::usecase::normalize::arena::ArenaNode;
+
+    fn push(arena: &mut Arena, parent: Option<usize>, name: &str, guid: &str) -> Handle {
+        let h = Handle(arena.nodes.len());
+        arena.nodes.push(ArenaNode {
+            kind: NodeKind::Folder,
+            name: Some(name.to_string()),
+            guid: Some(guid.to_string()),
+            ..ArenaNode::default()
+        });
+        arena.parent.push(parent.map(Handle));
+        if let Some(p) = parent {
+            arena.nodes[p].children.push(h);
+        } else {
+            arena.root_container.insert(name.to_string(), h);
+        }
+        h
+    }
+
+    /// root -> A(g1) -> B(g2) -> A'(g1) -> C(g3)
+    fn cyclic() -> Arena {
+        let mut arena = Arena::default();
+        push(&mut arena, None, "bookmark_bar", "root");
+        push(&mut arena, Some(0), "A", "g1");
+        push(&mut arena, Some(1), "B", "g2");
+        push(&mut arena, Some(2), "A", "g1");
+        push(&mut arena, Some(3), "C", "g3");
+        arena
+    }
+
+    #[test]
+    fn cuts_back_edge_and_moves_children_to_shallowest_instance() {
+        let mut arena = cyclic();
+        let condensed = condense(&mut arena, &KosarajuSccDetector).expect("condense");
+
+        assert_eq!(condensed.summary.cyclic_components, 1);
+        assert_eq!(
+            condensed.cuts,
+            vec![CutEdge {
+                identity: "g1".to_string(),
+                parent_path: "bookmark_bar / A / B".to_string(),
+                child_path: "bookmark_bar / A / B / A".to_string(),
+                moved_to: "bookmark_bar / A".to_string(),
+            }]
+        );
+        assert!(arena.nodes[3].deleted);
+        assert!(arena.nodes[2].children.is_empty());
+        assert_eq!(arena.nodes[1].children, vec![Handle(2), Handle(4)]);
+        assert_eq!(arena.parent[4], Some(Handle(1)));
+
+        let again = condense(&mut arena, &KosarajuSccDetector).expect("condense");
+        assert_eq!(again.summary.cyclic_components, 0);
+        assert!(again.cuts.is_empty());
+    }
+
+    #[test]
+    fn recondense_cuts_cycles_without_diagnosing_them() {
+        let mut first = cyclic();
+        let condensed = condense(&mut first, &KosarajuSccDetector).expect("condense");
+        assert_eq!(condensed.cycles.len(), 1);
+
+        let mut later = cyclic();
+        let recondensed = recondense(&mut later, &KosarajuSccDetector).expect("recondense");
+        assert!(recondensed.cycles.is_empty());
+        assert_eq!(recondensed.cuts, condensed.cuts);
+        assert!(later.nodes[3].deleted);
+    }
+
+    #[test]
+    fn self_loop_is_cut() {
+        let mut arena = Arena::default();
+        push(&mut arena, None, "bookmark_bar", "root");
+        push(&mut arena, Some(0), "A", "g1");
+        push(&mut arena, Some(1), "A", "g1");
+
+        let condensed = condense(&mut arena, &KosarajuSccDetector).expect("condense");
+        assert_eq!(condensed.cuts.len(), 1);
+        assert!(arena.nodes[2].deleted);
+    }
+
+    #[test]
+    fn cut_paths_are_recorded_before_earlier_cuts_move_them() {
+        // root -> A -> A -> A, all g1: the first cut moves the innermost A up a level.
+        let mut arena = Arena::default();
+        push(&mut arena, None, "bookmark_bar", "root");
+        push(&mut arena, Some(0), "A", "g1");
+        push(&mut arena, Some(1), "A", "g1");
+        push(&mut arena, Some(2), "A", "g1");
+
+        let condensed = condense(&mut arena, &KosarajuSccDetector).expect("condense");
+        let paths: Vec<(&str, &str)> = condensed
+            .cuts
+            .iter()
+            .map(|c| (c.parent_path.as_str(), c.child_path.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("bookmark_bar / A", "bookmark_bar / A / A"),
+                ("bookmark_bar / A / A", "bookmark_bar / A / A / A"),
+            ]
+        );
+    }
+
+    #[test]
+    fn preserved_repeat_inside_a_cycle_is_left_as_read() {
+        let mut arena = cyclic();
+        arena.nodes[3].date_added = Some("not-a-date".to_string());
+        arena.nodes[3].preserved = true;
+
+        let condensed = condense(&mut arena, &KosarajuSccDetector).expect("condense");
+        assert_eq!(condensed.summary.cyclic_components, 0);
+        assert!(condensed.cycles.is_empty());
+        assert!(condensed.cuts.is_empty());
+        assert!(!arena.nodes[3].deleted);
+        assert_eq!(arena.nodes[2].children, vec![Handle(3)]);
+        assert_eq!(arena.nodes[3].children, vec![Handle(4)]);
+        assert_eq!(arena.nodes[3].date_added.as_deref(), Some("not-a-date"));
+    }
+
+    #[test]
+    fn children_first_orders_descendants_before_ancestors() {
+        let mut arena = cyclic();
+        let order = condense(&mut arena, &KosarajuSccDetector)
+            .expect("condense")
+            .order;
+
+        let seq = order.children_first();
+        let at = |h: usize| seq.iter().position(|x| x.0 == h).expect("live");
+        assert!(at(4) < at(1));
+        assert!(at(2) < at(1));
+        assert!(at(1) < at(0));
+        assert!(order.rank(Handle(0)) < order.rank(Handle(1)));
+        assert!(!seq.contains(&Handle(3)), "cut folder is gone");
+    }
+}
@@ -0,0 +1,174 @@
+use crate::usecase::normalize::arena::Arena;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Merge iterations allowed before the run gives up; real exports settle in two or three.
+pub const DEFAULT_MAX_MERGE_ITERATIONS: usize = 16;
+
+/// A folder key still carried by more than one folder after an iteration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FolderCollision {
+    pub key: String,
+    pub count: usize,
+}
+
+/// Why the merge loop stopped before every folder key was unique.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeLoopError {
+    /// An iteration left the live tree as it was before the first iteration or after an
+    /// earlier one, so the loop would never end.
+    NoProgress {
+        iteration: usize,
+        collisions: Vec<FolderCollision>,
+    },
+    /// `max_merge_iterations` was reached with collisions left.
+    IterationLimit {
+        limit: usize,
+        collisions: Vec<FolderCollision>,
+    },
+}
+
+impl MergeLoopError {
+    pub fn collisions(&self) -> &[FolderCollision] {
+        match self {
+            Self::NoProgress { collisions, .. } | Self::IterationLimit { collisions, .. } => {
+                collisions
+            }
+        }
+    }
+}
+
+impl fmt::Display for MergeLoopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoProgress { iteration, .. } => {
+                write!(f, "folder merge made no progress in iteration {iteration}")?
+            }
+            Self::IterationLimit { limit, .. } => {
+                write!(f, "folder merge did not converge within {limit} iterations")?
+            }
+        }
+        let keys: Vec<String> = self
+            .collisions()
+            .iter()
+            .map(|c| format!("{:?} x{}", c.key, c.count))
+            .collect();
+        write!(f, "; colliding folder keys: {}", keys.join(", "))
+    }
+}
+
+impl std::error::Error for MergeLoopError {}
+
+/// Fingerprint of the merge loop's state: every live node with its parent and name. Merges,
+/// dedups, prunes and cuts delete nodes, cuts move them and merges rename folders, so the
+/// same fingerprint twice means the tree came back to where it was.
+pub(crate) fn loop_state(arena: &Arena) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (h, node) in arena.nodes.iter().enumerate() {
+        if !node.deleted {
+            h.hash(&mut hasher);
+            arena.parent[h].hash(&mut hasher);
+            node.name.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Decides, after each iteration that left collisions, whether another one may run.
+#[derive(Debug, Clone)]
+pub struct ConvergenceGuard {
+    limit: usize,
+    seen: HashSet<u64>,
+}
+
+impl ConvergenceGuard {
+    /// `start` is the state before the first iteration.
+    pub fn new(limit: usize, start: u64) -> Self {
+        Self {
+            limit: limit.max(1),
+            seen: HashSet::from([start]),
+        }
+    }
+
+    /// `state` is the [`loop_state`] after the iteration. The pipeline is deterministic, so
+    /// an iteration that returns to any earlier state, the starting one included, would
+    /// repeat forever.
+    pub fn check(
+        &mut self,
+        iteration: usize,
+        state: u64,
+        collisions: Vec<FolderCollision>,
+    ) -> Result<(), MergeLoopError> {
+        if !self.seen.insert(state) {
+            return Err(MergeLoopError::NoProgress {
+                iteration,
+                collisions,
+            });
+        }
+        if iteration >= self.limit {
+            return Err(MergeLoopError::IterationLimit {
+                limit: self.limit,
+                collisions,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collisions() -> Vec<FolderCollision> {
+        vec![FolderCollision {
+            key: "work".to_string(),
+            count: 2,
+        }]
+    }
+
+    #[test]
+    fn returning_to_an_earlier_state_is_no_progress() {
+        let mut guard = ConvergenceGuard::new(10, 0);
+        assert!(guard.check(1, 3, collisions()).is_ok());
+        assert!(guard.check(2, 5, collisions()).is_ok());
+
+        // Iteration 3 brings back the tree iteration 1 left.
+        let err = guard.check(3, 3, collisions()).unwrap_err();
+        assert_eq!(
+            err,
+            MergeLoopError::NoProgress {
+                iteration: 3,
+                collisions: collisions(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "folder merge made no progress in iteration 3; colliding folder keys: \"work\" x2"
+        );
+    }
+
+    #[test]
+    fn first_iteration_that_changes_nothing_is_no_progress() {
+        let mut guard = ConvergenceGuard::new(10, 7);
+        let err = guard.check(1, 7, collisions()).unwrap_err();
+        assert!(matches!(
+            err,
+            MergeLoopError::NoProgress { iteration: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn stops_at_iteration_limit() {
+        let mut guard = ConvergenceGuard::new(2, 0);
+        assert!(guard.check(1, 1, collisions()).is_ok());
+        let err = guard.check(2, 2, collisions()).unwrap_err();
+        assert!(matches!(
+            err,
+            MergeLoopError::IterationLimit { limit: 2, .. }
+        ));
+        assert_eq!(err.collisions()[0].key, "work");
+    }
+}
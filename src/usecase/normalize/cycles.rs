@@ -0,0 +1,240 @@
+use crate::domain::graph::SccResult;
+use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::graph::IdentityGraph;
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+/// One folder instance, located in the original file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FolderRef {
+    pub identity: String,
+    pub name: Option<String>,
+    /// JSON pointer into the input file.
+    pub json_pointer: String,
+    /// Folder names from the root container down, e.g. `Bookmarks bar / A / B`.
+    pub name_path: String,
+}
+
+/// An identity taking part in a cycle, with every place it occurs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CycleMember {
+    pub identity: String,
+    pub guid: Option<String>,
+    pub id: Option<String>,
+    /// Distinct names used by its instances.
+    pub names: Vec<String>,
+    /// JSON pointers of its instances.
+    pub instances: Vec<String>,
+}
+
+/// A containment edge of the witness cycle: `child` is nested directly in `parent`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WitnessEdge {
+    pub parent: FolderRef,
+    pub child: FolderRef,
+}
+
+/// A cyclic strongly connected component of the folder identity graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CycleDiagnostic {
+    pub members: Vec<CycleMember>,
+    /// One concrete cycle by folder name, e.g. `A → B → A`.
+    pub witness: String,
+    pub witness_edges: Vec<WitnessEdge>,
+}
+
+/// Describes every cyclic component of `res`. Must run before cycles are cut and before any
+/// merge, while the arena still mirrors the input file; only the first condensation calls it.
+pub fn diagnose(arena: &Arena, ig: &IdentityGraph, res: &SccResult) -> Vec<CycleDiagnostic> {
+    // First concrete (parent, child) instance pair for each identity edge inside a cyclic component.
+    let mut edge_instance: HashMap<(usize, usize), (Handle, Handle)> = HashMap::new();
+    let mut instances: HashMap<usize, Vec<Handle>> = HashMap::new();
+    for (h, id) in ig.identity_of.iter().enumerate() {
+        let Some(ci) = *id else {
+            continue;
+        };
+        if !res.cyclic_component[res.component_of[ci]] {
+            continue;
+        }
+        instances.entry(ci).or_default().push(Handle(h));
+        let Some(p) = arena.parent[h] else {
+            continue;
+        };
+        if let Some(pi) = ig.identity_of[p.0] {
+            if res.component_of[pi] == res.component_of[ci] {
+                edge_instance.entry((pi, ci)).or_insert((p, Handle(h)));
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for (cid, comp) in res.components.iter().enumerate() {
+        if !res.cyclic_component[cid] {
+            continue;
+        }
+
+        let members = comp
+            .iter()
+            .map(|&i| member(arena, ig, i, instances.get(&i).map_or(&[][..], |v| v)))
+            .collect();
+
+        let cycle = witness_cycle(ig, res, cid, comp[0]);
+        let witness_edges: Vec<WitnessEdge> = cycle
+            .windows(2)
+            .filter_map(|w| edge_instance.get(&(w[0], w[1])))
+            .map(|(p, c)| WitnessEdge {
+                parent: folder_ref(arena, ig, *p),
+                child: folder_ref(arena, ig, *c),
+            })
+            .collect();
+        let mut names: Vec<String> = witness_edges
+            .iter()
+            .map(|e| e.parent.name.clone().unwrap_or_default())
+            .collect();
+        if let Some(last) = witness_edges.last() {
+            names.push(last.child.name.clone().unwrap_or_default());
+        }
+
+        out.push(CycleDiagnostic {
+            members,
+            witness: names.join(" → "),
+            witness_edges,
+        });
+    }
+    out
+}
+
+/// Shortest cycle through `start`, as identity indices beginning and ending with `start`.
+fn witness_cycle(ig: &IdentityGraph, res: &SccResult, cid: usize, start: usize) -> Vec<usize> {
+    if ig.graph.edges[start].contains(&start) {
+        return vec![start, start];
+    }
+
+    let mut prev: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(u) = queue.pop_front() {
+        for &v in ig.graph.edges[u].iter() {
+            if res.component_of[v] != cid {
+                continue;
+            }
+            if v == start {
+                let mut path = vec![start, u];
+                let mut cur = u;
+                while let Some(&p) = prev.get(&cur) {
+                    path.push(p);
+                    cur = p;
+                }
+                path.reverse();
+                return path;
+            }
+            if let Entry::Vacant(e) = prev.entry(v) {
+                e.insert(u);
+                queue.push_back(v);
+            }
+        }
+    }
+    vec![start]
+}
+
+fn member(arena: &Arena, ig: &IdentityGraph, i: usize, handles: &[Handle]) -> CycleMember {
+    let first = handles.first().map(|h| &arena.nodes[h.0]);
+    let names: BTreeSet<String> = handles
+        .iter()
+        .filter_map(|h| arena.nodes[h.0].name.clone())
+        .collect();
+    CycleMember {
+        identity: ig.ids[i].clone(),
+        guid: first.and_then(|n| n.guid.clone()),
+        id: first.and_then(|n| n.id.clone()),
+        names: names.into_iter().collect(),
+        instances: handles.iter().map(|h| arena.json_pointer(*h)).collect(),
+    }
+}
+
+fn folder_ref(arena: &Arena, ig: &IdentityGraph, h: Handle) -> FolderRef {
+    let node = &arena.nodes[h.0];
+    FolderRef {
+        identity: ig.identity_of[h.0]
+            .map(|i| ig.ids[i].clone())
+            .unwrap_or_default(),
+        name: node.name.clone(),
+        json_pointer: arena.json_pointer(h),
+        name_path: arena.name_path(h),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::traits::SccDetector;
+    use crate::infrastructure::scc_kosaraju::KosarajuSccDetector;
+    use crate::usecase::normalize::build::build_arena_from_dto;
+    use crate::usecase::normalize::graph::build_identity_graph;
+    use crate::usecase::stats::NormalizeStats;
+
+    #[test]
+    fn describes_members_and_witness_by_input_location() {
+        // bookmark_bar -> A(g1) -> B(g2) -> "A copy"(g1) -> C(g3)
+        let input = serde_json::from_str(
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "url", "name": "u", "url": "https://u.example/"},
+                {"type": "folder", "name": "A", "guid": "g1", "children": [
+                    {"type": "folder", "name": "B", "guid": "g2", "children": [
+                        {"type": "folder", "name": "A copy", "guid": "g1", "children": [
+                            {"type": "folder", "name": "C", "guid": "g3", "children": []}
+                        ]}
+                    ]}
+                ]}
+            ]}}}"#,
+        )
+        .expect("input");
+        let arena = build_arena_from_dto(&input, &mut NormalizeStats::default());
+        let ig = build_identity_graph(&arena);
+        let res = KosarajuSccDetector.try_compute_scc(&ig.graph).expect("scc");
+
+        let diagnostics = diagnose(&arena, &ig, &res);
+        assert_eq!(diagnostics.len(), 1);
+        let cycle = &diagnostics[0];
+
+        let a = "/roots/bookmark_bar/children/1";
+        let b = "/roots/bookmark_bar/children/1/children/0";
+        let a_copy = "/roots/bookmark_bar/children/1/children/0/children/0";
+        assert_eq!(
+            cycle.members,
+            vec![
+                CycleMember {
+                    identity: "g1".to_string(),
+                    guid: Some("g1".to_string()),
+                    id: None,
+                    names: vec!["A".to_string(), "A copy".to_string()],
+                    instances: vec![a.to_string(), a_copy.to_string()],
+                },
+                CycleMember {
+                    identity: "g2".to_string(),
+                    guid: Some("g2".to_string()),
+                    id: None,
+                    names: vec!["B".to_string()],
+                    instances: vec![b.to_string()],
+                },
+            ]
+        );
+
+        assert_eq!(cycle.witness, "A → B → A copy");
+        let edges: Vec<(&str, &str)> = cycle
+            .witness_edges
+            .iter()
+            .map(|e| {
+                (
+                    e.parent.json_pointer.as_str(),
+                    e.child.json_pointer.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(edges, vec![(a, b), (b, a_copy)]);
+        assert_eq!(
+            cycle.witness_edges[1].child.name_path,
+            "bookmark_bar / A / B / A copy"
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::url_merge::{DateAddedMerge, VisitCountMerge};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// How the surviving folder's `date_modified` is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateModifiedMerge {
+    Winner,
+    #[default]
+    Latest,
+}
+
+/// How loser fields kept in `extra` (including `meta_info`) reach the surviving folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtraMerge {
+    #[default]
+    Winner,
+    /// Keys missing on the winner are copied over; object values such as `meta_info` are
+    /// merged key by key. Differing values keep the winner's and are reported as conflicts.
+    Union,
+}
+
+/// A loser value that could not be merged because the winner holds a different one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeConflict {
+    /// Field name; nested object keys are dotted, e.g. `meta_info.last_visited_desktop`.
+    pub field: String,
+    pub kept: Value,
+    pub dropped: Value,
+}
+
+/// Attribute merge rules applied to the winner of a folder merge group, once per loser.
+///
+/// The default accumulates `visit_count` and keeps the latest `date_modified`;
+/// everything else stays as the winner had it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FolderMergePolicy {
+    pub date_added: DateAddedMerge,
+    pub date_modified: DateModifiedMerge,
+    pub visit_count: VisitCountMerge,
+    pub extra: ExtraMerge,
+}
+
+impl Default for FolderMergePolicy {
+    fn default() -> Self {
+        Self {
+            date_added: DateAddedMerge::Winner,
+            date_modified: DateModifiedMerge::Latest,
+            visit_count: VisitCountMerge::Sum,
+            extra: ExtraMerge::Winner,
+        }
+    }
+}
+
+impl FolderMergePolicy {
+    /// Keeps the winner's attributes untouched.
+    pub fn winner() -> Self {
+        Self {
+            date_added: DateAddedMerge::Winner,
+            date_modified: DateModifiedMerge::Winner,
+            visit_count: VisitCountMerge::Winner,
+            extra: ExtraMerge::Winner,
+        }
+    }
+
+    /// Folds every source into the winner: earliest added, latest modified,
+    /// summed visits and the union of extra fields.
+    pub fn fold() -> Self {
+        Self {
+            date_added: DateAddedMerge::Earliest,
+            date_modified: DateModifiedMerge::Latest,
+            visit_count: VisitCountMerge::Sum,
+            extra: ExtraMerge::Union,
+        }
+    }
+
+    /// Merges `loser`'s attributes into `winner` and returns the conflicts found.
+    pub fn apply(
+        &self,
+        arena: &mut Arena,
+        winner: Handle,
+        loser: Handle,
+    ) -> Vec<AttributeConflict> {
+        let loser = arena.nodes[loser.0].clone();
+        let w = &mut arena.nodes[winner.0];
+        let mut conflicts = Vec::new();
+
+        if self.date_added == DateAddedMerge::Earliest
+            && is_earlier(w.date_added.as_deref(), loser.date_added.as_deref())
+        {
+            w.date_added = loser.date_added.clone();
+        }
+
+        if self.date_modified == DateModifiedMerge::Latest
+            && parse_time(loser.date_modified.as_deref()) > parse_time(w.date_modified.as_deref())
+        {
+            w.date_modified = loser.date_modified.clone();
+        }
+
+        match (self.visit_count, w.visit_count, loser.visit_count) {
+            (VisitCountMerge::Sum, current, Some(l)) => {
+                w.visit_count = Some(current.unwrap_or(0).saturating_add(l));
+            }
+            (VisitCountMerge::Max, current, Some(l)) if current.is_none_or(|c| l > c) => {
+                w.visit_count = Some(l);
+            }
+            _ => {}
+        }
+
+        if self.extra == ExtraMerge::Union {
+            union_into(&mut w.extra, &loser.extra, "", &mut conflicts);
+        }
+
+        conflicts
+    }
+}
+
+impl FromStr for FolderMergePolicy {
+    type Err = anyhow::Error;
+
+    /// Parses a preset name: `winner`, `accumulate` (the default) or `fold`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::winner()),
+            "accumulate" => Ok(Self::default()),
+            "fold" => Ok(Self::fold()),
+            other => Err(anyhow!(
+                "unknown folder merge preset: {other} (expected winner, accumulate, fold)"
+            )),
+        }
+    }
+}
+
+impl FromStr for DateModifiedMerge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "latest" => Ok(Self::Latest),
+            other => Err(anyhow!(
+                "unknown date_modified merge rule: {other} (expected winner, latest)"
+            )),
+        }
+    }
+}
+
+impl FromStr for ExtraMerge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "union" => Ok(Self::Union),
+            other => Err(anyhow!(
+                "unknown extra merge rule: {other} (expected winner, union)"
+            )),
+        }
+    }
+}
+
+/// Unparseable or missing timestamps count as 0, matching the winner rules.
+fn parse_time(s: Option<&str>) -> u64 {
+    s.and_then(|v| v.parse::<u64>().ok()).unwrap_or(0)
+}
+
+/// True when `loser` parses and is earlier than `current` (or `current` does not parse).
+fn is_earlier(current: Option<&str>, loser: Option<&str>) -> bool {
+    let Some(l) = loser.and_then(|v| v.parse::<u64>().ok()) else {
+        return false;
+    };
+    current
+        .and_then(|v| v.parse::<u64>().ok())
+        .is_none_or(|c| l < c)
+}
+
+fn union_into(
+    target: &mut BTreeMap<String, Value>,
+    source: &BTreeMap<String, Value>,
+    prefix: &str,
+    conflicts: &mut Vec<AttributeConflict>,
+) {
+    for (k, v) in source.iter() {
+        let field = format!("{prefix}{k}");
+        match target.get_mut(k) {
+            None => {
+                target.insert(k.clone(), v.clone());
+            }
+            Some(Value::Object(existing)) if v.is_object() && prefix.is_empty() => {
+                // One level deep covers `meta_info`-style objects; deeper values are compared whole.
+                let mut nested: BTreeMap<String, Value> = existing
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                let incoming: BTreeMap<String, Value> = v
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                union_into(&mut nested, &incoming, &format!("{field}."), conflicts);
+                *existing = nested.into_iter().collect();
+            }
+            Some(existing) if existing != v => conflicts.push(AttributeConflict {
+                field,
+                kept: existing.clone(),
+                dropped: v.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::NodeKind;
+    use crate::usecase::normalize::arena::ArenaNode;
+    use serde_json::json;
+
+    fn two_folders() -> Arena {
+        let mut arena = Arena::default();
+        for n in [
+            ArenaNode {
+                kind: NodeKind::Folder,
+                date_added: Some("200".to_string()),
+                date_modified: Some("300".to_string()),
+                visit_count: Some(2),
+                extra: [
+                    ("meta_info".to_string(), json!({"a": "1", "b": "winner"})),
+                    ("sync_id".to_string(), json!("w")),
+                ]
+                .into(),
+                ..ArenaNode::default()
+            },
+            ArenaNode {
+                kind: NodeKind::Folder,
+                date_added: Some("100".to_string()),
+                date_modified: Some("900".to_string()),
+                visit_count: Some(5),
+                extra: [
+                    ("meta_info".to_string(), json!({"b": "loser", "c": "3"})),
+                    ("sync_id".to_string(), json!("l")),
+                    ("color".to_string(), json!("red")),
+                ]
+                .into(),
+                ..ArenaNode::default()
+            },
+        ] {
+            arena.nodes.push(n);
+            arena.parent.push(None);
+        }
+        arena
+    }
+
+    #[test]
+    fn default_accumulates_visits_and_latest_modified_only() {
+        let mut arena = two_folders();
+        let conflicts = FolderMergePolicy::default().apply(&mut arena, Handle(0), Handle(1));
+        let w = &arena.nodes[0];
+        assert!(conflicts.is_empty());
+        assert_eq!(w.visit_count, Some(7));
+        assert_eq!(w.date_modified.as_deref(), Some("900"));
+        assert_eq!(w.date_added.as_deref(), Some("200"));
+        assert!(!w.extra.contains_key("color"));
+    }
+
+    #[test]
+    fn fold_unions_extra_and_reports_conflicts() {
+        let mut arena = two_folders();
+        let conflicts = FolderMergePolicy::fold().apply(&mut arena, Handle(0), Handle(1));
+        let w = &arena.nodes[0];
+        assert_eq!(w.date_added.as_deref(), Some("100"));
+        assert_eq!(w.extra["color"], json!("red"));
+        assert_eq!(w.extra["sync_id"], json!("w"));
+        assert_eq!(
+            w.extra["meta_info"],
+            json!({"a": "1", "b": "winner", "c": "3"})
+        );
+
+        let fields: Vec<&str> = conflicts.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["meta_info.b", "sync_id"]);
+        assert_eq!(conflicts[0].dropped, json!("loser"));
+    }
+
+    #[test]
+    fn winner_preset_leaves_winner_untouched() {
+        let mut arena = two_folders();
+        let before = arena.nodes[0].clone();
+        FolderMergePolicy::winner().apply(&mut arena, Handle(0), Handle(1));
+        assert_eq!(arena.nodes[0].visit_count, before.visit_count);
+        assert_eq!(arena.nodes[0].date_modified, before.date_modified);
+    }
+
+    #[test]
+    fn parse_presets_and_field_rules() {
+        assert_eq!(
+            "accumulate".parse::<FolderMergePolicy>().unwrap(),
+            FolderMergePolicy::default()
+        );
+        assert!("sum".parse::<FolderMergePolicy>().is_err());
+        assert_eq!("union".parse::<ExtraMerge>().unwrap(), ExtraMerge::Union);
+        assert!("earliest".parse::<DateModifiedMerge>().is_err());
+    }
+}
@@ -1,20 +1,27 @@
+use crate::domain::model::NodeKind;
 use crate::usecase::event::AppEvent;
 use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::condense::ProcessingOrder;
+use crate::usecase::normalize::folder_attrs::{AttributeConflict, FolderMergePolicy};
+use crate::usecase::normalize::folder_name::FolderNamePolicy;
+use crate::usecase::normalize::folder_winner::FolderWinnerPolicy;
 use crate::usecase::stats::NormalizeStats;
-use std::cmp::Ordering;
 use std::collections::HashMap;
 use tokio::sync::mpsc;
 
 pub async fn global_folder_merge(
     arena: &mut Arena,
+    policy: &FolderWinnerPolicy,
+    naming: &FolderNamePolicy,
+    attrs: &FolderMergePolicy,
+    order: &ProcessingOrder,
     sink: &Option<mpsc::Sender<AppEvent>>,
     stats: &mut NormalizeStats,
 ) {
     let mut by_name: HashMap<String, Vec<Handle>> = HashMap::new();
 
-    use unicode_normalization::UnicodeNormalization;
     for (h, node) in arena.nodes.iter().enumerate() {
-        if node.deleted || node.node_type != "folder" {
+        if node.deleted || node.preserved || node.kind != NodeKind::Folder {
             continue;
         }
         if node.depth == 0 {
@@ -23,15 +30,22 @@ pub async fn global_folder_merge(
         let Some(name) = node.name.as_ref() else {
             continue;
         };
-        // Unicode NFC normalization, trim, lowercase
-        let norm = name.nfc().collect::<String>().trim().to_lowercase();
-        by_name.entry(norm).or_default().push(Handle(h));
+        by_name.entry(folder_key(name)).or_default().push(Handle(h));
     }
 
-    let mut keys: Vec<String> = by_name.keys().cloned().collect();
+    // Groups closer to the top of the condensed DAG merge first; the name breaks ties.
+    let mut keys: Vec<(usize, String)> = by_name
+        .iter()
+        .map(|(k, hs)| {
+            (
+                hs.iter().map(|h| order.rank(*h)).min().unwrap_or(0),
+                k.clone(),
+            )
+        })
+        .collect();
     keys.sort();
 
-    for key in keys {
+    for (_, key) in keys {
         let handles = by_name.get(&key).cloned().unwrap_or_default();
         if handles.len() <= 1 {
             continue;
@@ -47,122 +61,109 @@ pub async fn global_folder_merge(
         .await;
 
         let mut sorted = handles;
-        sorted.sort_by(|a, b| compare_folder_instance(arena, *a, *b));
+        sorted.sort_by(|a, b| policy.compare(arena, *a, *b));
 
-        let winner = sorted[0];
+        // A folder never merges into its own descendant: the policy's pick gives way to the
+        // outermost instance above it.
+        let mut winner = sorted[0];
+        while let Some(&above) = sorted
+            .iter()
+            .find(|h| **h != winner && arena.is_ancestor_of(**h, winner))
+        {
+            winner = above;
+        }
+        sorted.retain(|h| *h != winner);
+        sorted.insert(0, winner);
         let losers = sorted[1..].to_vec();
 
+        // Rationale is computed before merging, while losers still hold their own children.
+        let rationale: Vec<String> = losers
+            .iter()
+            .map(|l| {
+                if policy.compare(arena, *l, winner).is_lt() && arena.is_ancestor_of(winner, *l) {
+                    "ancestor: contains the loser".to_string()
+                } else {
+                    policy.explain(arena, winner, *l)
+                }
+            })
+            .collect();
+        let (name, name_rule) = naming.choose(arena, &key, &sorted);
+
+        let mut conflicts: Vec<(Handle, Vec<AttributeConflict>)> = Vec::new();
         for loser in losers.iter().copied() {
-            merge_folder_into(arena, loser, winner);
+            conflicts.push((loser, merge_folder_into(arena, loser, winner, attrs)));
             stats.folders_merged += 1;
         }
+        arena.rename(winner, name.clone());
 
-        let losers_paths: Vec<String> = losers
-            .iter()
-            .map(|h| arena.nodes[h.0].path.clone())
-            .collect();
+        let losers_paths: Vec<String> = losers.iter().map(|h| arena.name_path(*h)).collect();
         emit(
             sink,
             AppEvent::FolderMerged {
                 normalized_name: key,
-                winner_path: arena.nodes[winner.0].path.clone(),
+                winner_path: arena.name_path(winner),
                 losers: losers_paths,
+                policy: policy.to_string(),
+                rationale,
+                name,
+                name_rule,
             },
         )
         .await;
-    }
-}
 
-async fn emit(sink: &Option<mpsc::Sender<AppEvent>>, ev: AppEvent) {
-    if let Some(tx) = sink {
-        let _ = tx.send(ev).await;
+        for (loser, found) in conflicts {
+            for c in found {
+                emit(
+                    sink,
+                    AppEvent::FolderAttributeConflict {
+                        folder_path: arena.name_path(winner),
+                        loser_path: arena.name_path(loser),
+                        field: c.field,
+                        kept: c.kept,
+                        dropped: c.dropped,
+                    },
+                )
+                .await;
+            }
+        }
     }
+    arena.compact_children();
 }
 
-fn compare_folder_instance(arena: &Arena, a: Handle, b: Handle) -> Ordering {
-    let aa = &arena.nodes[a.0];
-    let bb = &arena.nodes[b.0];
-
-    aa.depth
-        .cmp(&bb.depth)
-        .then_with(|| cmp_date_added(aa, bb))
-        .then_with(|| cmp_id(aa, bb))
-        .then_with(|| aa.guid.cmp(&bb.guid))
-}
-
-fn cmp_date_added(
-    a: &crate::usecase::normalize::arena::ArenaNode,
-    b: &crate::usecase::normalize::arena::ArenaNode,
-) -> Ordering {
-    parse_edge_time(a.date_added.as_deref()).cmp(&parse_edge_time(b.date_added.as_deref()))
-}
-
-fn parse_edge_time(s: Option<&str>) -> u64 {
-    match s {
-        Some(v) => v.parse::<u64>().unwrap_or(u64::MAX),
-        None => u64::MAX,
-    }
+/// Merge key for folder names: Unicode NFC, trimmed, lowercased.
+pub fn folder_key(name: &str) -> String {
+    use unicode_normalization::UnicodeNormalization;
+    name.nfc().collect::<String>().trim().to_lowercase()
 }
 
-fn cmp_id(
-    a: &crate::usecase::normalize::arena::ArenaNode,
-    b: &crate::usecase::normalize::arena::ArenaNode,
-) -> Ordering {
-    let pa = a.id.as_deref().and_then(|s| s.parse::<u64>().ok());
-    let pb = b.id.as_deref().and_then(|s| s.parse::<u64>().ok());
-
-    match (pa, pb) {
-        (Some(aa), Some(bb)) => aa.cmp(&bb),
-        (Some(_), None) => Ordering::Less,
-        (None, Some(_)) => Ordering::Greater,
-        (None, None) => a.id.cmp(&b.id),
+async fn emit(sink: &Option<mpsc::Sender<AppEvent>>, ev: AppEvent) {
+    if let Some(tx) = sink {
+        let _ = tx.send(ev).await;
     }
 }
 
-fn merge_folder_into(arena: &mut Arena, loser: Handle, winner: Handle) {
+fn merge_folder_into(
+    arena: &mut Arena,
+    loser: Handle,
+    winner: Handle,
+    attrs: &FolderMergePolicy,
+) -> Vec<AttributeConflict> {
     if loser.0 == winner.0 {
-        return;
+        return Vec::new();
     }
     if arena.nodes[loser.0].deleted {
-        return;
+        return Vec::new();
     }
 
-    let _loser_path = arena.nodes[loser.0].path.clone();
-
     // Merge children from loser into winner
-    let mut children = std::mem::take(&mut arena.nodes[loser.0].children);
-    for ch in children.iter() {
-        arena.parent[ch.0] = Some(winner);
-    }
-    arena.nodes[winner.0].children.append(&mut children);
-
-    // Merge attributes - keep winner's attributes but accumulate visit counts
-    if let Some(loser_visit_count) = arena.nodes[loser.0].visit_count {
-        arena.nodes[winner.0].visit_count =
-            Some(arena.nodes[winner.0].visit_count.unwrap_or(0) + loser_visit_count);
-    }
-
-    // Update date_modified to the most recent
-    let winner_date = arena.nodes[winner.0]
-        .date_modified
-        .as_deref()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
-    let loser_date = arena.nodes[loser.0]
-        .date_modified
-        .as_deref()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
-    if loser_date > winner_date {
-        arena.nodes[winner.0].date_modified = arena.nodes[loser.0].date_modified.clone();
-    }
+    arena.move_children(loser, winner);
 
-    // Remove loser from parent's children
-    if let Some(parent) = arena.parent[loser.0] {
-        arena.nodes[parent.0].children.retain(|h| h.0 != loser.0);
-    }
+    // Merge attributes according to the folder merge policy
+    let conflicts = attrs.apply(arena, winner, loser);
 
-    arena.nodes[loser.0].deleted = true;
+    // The loser stays in its parent's child list as a tombstone until the phase compacts.
+    arena.absorb(loser, winner);
+    conflicts
 }
 
 #[cfg(test)]
@@ -170,89 +171,23 @@ mod tests {
     use super::*;
     use crate::usecase::normalize::arena::ArenaNode;
 
-    #[test]
-    fn parse_edge_time_handles_none_and_invalid() {
-        assert_eq!(parse_edge_time(None), u64::MAX);
-        assert_eq!(parse_edge_time(Some("not_a_number")), u64::MAX);
-        assert_eq!(parse_edge_time(Some("42")), 42);
-    }
-
-    #[test]
-    fn cmp_id_orders_numeric_before_missing_and_text() {
-        let a = ArenaNode {
-            id: Some("10".to_string()),
-            ..ArenaNode::default()
-        };
-        let b = ArenaNode {
-            id: None,
-            ..ArenaNode::default()
-        };
-        assert_eq!(cmp_id(&a, &b), Ordering::Less);
-        assert_eq!(cmp_id(&b, &a), Ordering::Greater);
-
-        let c = ArenaNode {
-            id: Some("abc".to_string()),
-            ..ArenaNode::default()
-        };
-        let d = ArenaNode {
-            id: Some("def".to_string()),
-            ..ArenaNode::default()
-        };
-        assert_eq!(cmp_id(&c, &d), Ordering::Less);
-    }
-
     #[test]
     fn merge_folder_into_noops_on_self_or_deleted_loser() {
         let mut arena = Arena::default();
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             name: Some("a".to_string()),
             depth: 1,
             ..ArenaNode::default()
         });
         arena.parent.push(None);
 
-        merge_folder_into(&mut arena, Handle(0), Handle(0));
+        let attrs = FolderMergePolicy::default();
+        merge_folder_into(&mut arena, Handle(0), Handle(0), &attrs);
         assert!(!arena.nodes[0].deleted);
 
         arena.nodes[0].deleted = true;
-        merge_folder_into(&mut arena, Handle(0), Handle(0));
+        merge_folder_into(&mut arena, Handle(0), Handle(0), &attrs);
         assert!(arena.nodes[0].deleted);
     }
-
-    #[test]
-    fn compare_folder_instance_uses_depth_then_date_then_id_then_guid() {
-        let mut arena = Arena::default();
-
-        arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
-            depth: 2,
-            date_added: Some("10".to_string()),
-            id: Some("7".to_string()),
-            guid: Some("a".to_string()),
-            ..ArenaNode::default()
-        });
-        arena.parent.push(None);
-
-        arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
-            depth: 2,
-            date_added: Some("10".to_string()),
-            id: Some("7".to_string()),
-            guid: Some("b".to_string()),
-            ..ArenaNode::default()
-        });
-        arena.parent.push(None);
-
-        assert_eq!(
-            compare_folder_instance(&arena, Handle(0), Handle(1)),
-            Ordering::Less
-        );
-
-        arena.nodes[1].depth = 3;
-        assert_eq!(
-            compare_folder_instance(&arena, Handle(0), Handle(1)),
-            Ordering::Less
-        );
-    }
 }
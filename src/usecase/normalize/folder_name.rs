@@ -0,0 +1,243 @@
+use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::folder_merge::folder_key;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the surviving folder of a merge group is spelled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FolderNameRule {
+    /// Keep the winner's own name.
+    #[default]
+    Winner,
+    /// The (trimmed) spelling used by most instances in the group.
+    MostFrequent,
+    /// The spelling of the instance with the most descendants.
+    MostDescendants,
+    /// The winner's name in title case (`work stuff` -> `Work Stuff`).
+    TitleCase,
+}
+
+impl fmt::Display for FolderNameRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Winner => "winner",
+            Self::MostFrequent => "most-frequent",
+            Self::MostDescendants => "most-descendants",
+            Self::TitleCase => "title-case",
+        })
+    }
+}
+
+impl FromStr for FolderNameRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "most-frequent" => Ok(Self::MostFrequent),
+            "most-descendants" => Ok(Self::MostDescendants),
+            "title-case" => Ok(Self::TitleCase),
+            other => Err(anyhow!(
+                "unknown folder name rule: {other} (expected winner, most-frequent, most-descendants, title-case)"
+            )),
+        }
+    }
+}
+
+/// Display-name policy for merge groups.
+///
+/// An alias entry for the group's normalized name is an explicit choice and always wins;
+/// otherwise `rule` decides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FolderNamePolicy {
+    pub rule: FolderNameRule,
+    aliases: BTreeMap<String, String>,
+}
+
+impl FolderNamePolicy {
+    pub fn new(rule: FolderNameRule) -> Self {
+        Self {
+            rule,
+            aliases: BTreeMap::new(),
+        }
+    }
+
+    /// Registers explicit spellings; keys are normalized like folder names, so
+    /// `"Work"` and `" work "` address the same group.
+    pub fn with_aliases<I, K, V>(mut self, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        for (k, v) in aliases {
+            self.aliases.insert(folder_key(k.as_ref()), v.into());
+        }
+        self
+    }
+
+    /// The merge key a folder named `name` carries once its group has been renamed: the
+    /// alias's key when one is registered, else its own (every rule keeps the key).
+    pub fn merged_key(&self, name: &str) -> String {
+        let key = folder_key(name);
+        match self.aliases.get(&key) {
+            Some(alias) => folder_key(alias),
+            None => key,
+        }
+    }
+
+    /// Chooses the display name for the group `key`. `group` is ordered winner-first and
+    /// must be evaluated before losers are merged away.
+    ///
+    /// Returns the chosen name and a short reason for events.
+    pub fn choose(&self, arena: &Arena, key: &str, group: &[Handle]) -> (String, String) {
+        let winner_name = name_of(arena, group[0]);
+
+        if let Some(alias) = self.aliases.get(key) {
+            return (alias.clone(), "alias".to_string());
+        }
+
+        let name = match self.rule {
+            FolderNameRule::Winner => winner_name,
+            FolderNameRule::TitleCase => title_case(winner_name.trim()),
+            FolderNameRule::MostFrequent => {
+                let mut counts: HashMap<&str, usize> = HashMap::new();
+                for h in group.iter() {
+                    if let Some(n) = arena.nodes[h.0].name.as_deref() {
+                        *counts.entry(n.trim()).or_insert(0) += 1;
+                    }
+                }
+                // Highest count; the winner's spelling, then lexicographic order, break ties.
+                let winner_trimmed = winner_name.trim();
+                counts
+                    .into_iter()
+                    .max_by(|(a, ca), (b, cb)| {
+                        ca.cmp(cb)
+                            .then_with(|| (*a == winner_trimmed).cmp(&(*b == winner_trimmed)))
+                            .then_with(|| b.cmp(a))
+                    })
+                    .map(|(n, _)| n.to_string())
+                    .unwrap_or(winner_name)
+            }
+            FolderNameRule::MostDescendants => {
+                // First maximum in winner-first order, so the winner keeps its name on ties.
+                let mut best = (group[0], descendant_count(arena, group[0]));
+                for h in group.iter().skip(1).copied() {
+                    let d = descendant_count(arena, h);
+                    if d > best.1 {
+                        best = (h, d);
+                    }
+                }
+                name_of(arena, best.0)
+            }
+        };
+        (name, self.rule.to_string())
+    }
+}
+
+fn name_of(arena: &Arena, h: Handle) -> String {
+    arena.nodes[h.0].name.clone().unwrap_or_default()
+}
+
+fn descendant_count(arena: &Arena, h: Handle) -> usize {
+    let mut count = 0;
+    let mut stack: Vec<Handle> = arena.nodes[h.0].children.clone();
+    while let Some(c) = stack.pop() {
+        if arena.nodes[c.0].deleted {
+            continue;
+        }
+        count += 1;
+        stack.extend(arena.nodes[c.0].children.iter().copied());
+    }
+    count
+}
+
+fn title_case(s: &str) -> String {
+    s.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::NodeKind;
+    use crate::usecase::normalize::arena::ArenaNode;
+
+    fn folder(name: &str, children: Vec<Handle>) -> ArenaNode {
+        ArenaNode {
+            kind: NodeKind::Folder,
+            name: Some(name.to_string()),
+            children,
+            ..ArenaNode::default()
+        }
+    }
+
+    /// Group: [work (winner, 0 children), WORK (2 children), Work, Work]
+    fn sample() -> (Arena, Vec<Handle>) {
+        let mut arena = Arena::default();
+        for n in [
+            folder("work", vec![]),
+            folder("WORK", vec![Handle(4), Handle(5)]),
+            folder(" Work", vec![]),
+            folder("Work ", vec![]),
+            ArenaNode::default(),
+            ArenaNode::default(),
+        ] {
+            arena.nodes.push(n);
+            arena.parent.push(None);
+        }
+        (arena, vec![Handle(0), Handle(1), Handle(2), Handle(3)])
+    }
+
+    #[test]
+    fn rules_choose_expected_spelling() {
+        let (arena, group) = sample();
+        let pick =
+            |rule: FolderNameRule| FolderNamePolicy::new(rule).choose(&arena, "work", &group);
+
+        assert_eq!(pick(FolderNameRule::Winner).0, "work");
+        assert_eq!(pick(FolderNameRule::MostFrequent).0, "Work");
+        assert_eq!(pick(FolderNameRule::MostDescendants).0, "WORK");
+        assert_eq!(pick(FolderNameRule::TitleCase).0, "Work");
+        assert_eq!(pick(FolderNameRule::TitleCase).1, "title-case");
+    }
+
+    #[test]
+    fn alias_overrides_rule_and_keys_are_normalized() {
+        let (arena, group) = sample();
+        let policy =
+            FolderNamePolicy::new(FolderNameRule::MostFrequent).with_aliases([(" WORK ", "Job")]);
+        assert_eq!(
+            policy.choose(&arena, "work", &group),
+            ("Job".to_string(), "alias".to_string())
+        );
+    }
+
+    #[test]
+    fn title_case_handles_words_and_unicode() {
+        assert_eq!(title_case("my  RUST notes"), "My  Rust Notes");
+        assert_eq!(title_case("élan"), "Élan");
+    }
+
+    #[test]
+    fn parse_rejects_unknown_rule() {
+        assert_eq!(
+            "most-frequent".parse::<FolderNameRule>().unwrap(),
+            FolderNameRule::MostFrequent
+        );
+        assert!("loudest".parse::<FolderNameRule>().is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use crate::usecase::normalize::arena::{Arena, ArenaNode, Handle};
+use anyhow::{anyhow, Result};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// One criterion used to rank folder instances that share a normalized name.
+///
+/// Rules are applied in order; the first rule that distinguishes two instances decides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FolderWinnerRule {
+    /// Minimal path depth from any root ("outermost wins").
+    Outermost,
+    /// Most live direct children.
+    MostChildren,
+    /// Latest `date_modified`.
+    MostRecentlyModified,
+    /// Instances located under the given root container (e.g. `bookmark_bar`).
+    PreferRoot(String),
+    /// Earliest `date_added`.
+    EarliestAdded,
+    /// Smallest numeric `id` (numeric ids before textual, textual before missing).
+    SmallestId,
+    /// Smallest `guid`, lexicographically.
+    SmallestGuid,
+}
+
+impl FolderWinnerRule {
+    fn compare(&self, a: &ArenaNode, b: &ArenaNode, arena: &Arena) -> Ordering {
+        match self {
+            Self::Outermost => a.depth.cmp(&b.depth),
+            Self::MostChildren => live_children(arena, b).cmp(&live_children(arena, a)),
+            Self::MostRecentlyModified => parse_modified(b).cmp(&parse_modified(a)),
+            Self::PreferRoot(root) => {
+                let a_in = a.root_key.as_deref() == Some(root.as_str());
+                let b_in = b.root_key.as_deref() == Some(root.as_str());
+                b_in.cmp(&a_in)
+            }
+            Self::EarliestAdded => cmp_date_added(a, b),
+            Self::SmallestId => cmp_id(a, b),
+            Self::SmallestGuid => a.guid.cmp(&b.guid),
+        }
+    }
+
+    fn describe(&self, winner: &ArenaNode, loser: &ArenaNode, arena: &Arena) -> String {
+        match self {
+            Self::Outermost => format!("{self}: depth {} < {}", winner.depth, loser.depth),
+            Self::MostChildren => format!(
+                "{self}: {} > {} children",
+                live_children(arena, winner),
+                live_children(arena, loser)
+            ),
+            Self::MostRecentlyModified => format!(
+                "{self}: date_modified {} > {}",
+                winner.date_modified.as_deref().unwrap_or("-"),
+                loser.date_modified.as_deref().unwrap_or("-")
+            ),
+            Self::PreferRoot(_) => format!(
+                "{self}: root {} over {}",
+                winner.root_key.as_deref().unwrap_or("-"),
+                loser.root_key.as_deref().unwrap_or("-")
+            ),
+            Self::EarliestAdded => format!(
+                "{self}: date_added {} < {}",
+                winner.date_added.as_deref().unwrap_or("-"),
+                loser.date_added.as_deref().unwrap_or("-")
+            ),
+            Self::SmallestId => format!(
+                "{self}: id {} < {}",
+                winner.id.as_deref().unwrap_or("-"),
+                loser.id.as_deref().unwrap_or("-")
+            ),
+            Self::SmallestGuid => format!(
+                "{self}: guid {} < {}",
+                winner.guid.as_deref().unwrap_or("-"),
+                loser.guid.as_deref().unwrap_or("-")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for FolderWinnerRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Outermost => f.write_str("outermost"),
+            Self::MostChildren => f.write_str("most-children"),
+            Self::MostRecentlyModified => f.write_str("most-recently-modified"),
+            Self::PreferRoot(root) => write!(f, "prefer-root:{root}"),
+            Self::EarliestAdded => f.write_str("earliest-added"),
+            Self::SmallestId => f.write_str("smallest-id"),
+            Self::SmallestGuid => f.write_str("smallest-guid"),
+        }
+    }
+}
+
+impl FromStr for FolderWinnerRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(root) = s.strip_prefix("prefer-root:") {
+            if root.is_empty() {
+                return Err(anyhow!(
+                    "prefer-root requires a root key, e.g. prefer-root:bookmark_bar"
+                ));
+            }
+            return Ok(Self::PreferRoot(root.to_string()));
+        }
+        match s {
+            "outermost" => Ok(Self::Outermost),
+            "most-children" => Ok(Self::MostChildren),
+            "most-recently-modified" => Ok(Self::MostRecentlyModified),
+            "earliest-added" => Ok(Self::EarliestAdded),
+            "smallest-id" => Ok(Self::SmallestId),
+            "smallest-guid" => Ok(Self::SmallestGuid),
+            other => Err(anyhow!(
+                "unknown folder winner rule: {other} (expected outermost, most-children, most-recently-modified, prefer-root:<key>, earliest-added, smallest-id, smallest-guid)"
+            )),
+        }
+    }
+}
+
+/// Ordered list of winner rules for folder merge groups.
+///
+/// The configured rules are always followed by the fixed tie-breaks
+/// `earliest-added`, `smallest-id`, `smallest-guid`, so the choice stays deterministic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderWinnerPolicy {
+    pub rules: Vec<FolderWinnerRule>,
+}
+
+impl Default for FolderWinnerPolicy {
+    fn default() -> Self {
+        Self {
+            rules: vec![FolderWinnerRule::Outermost],
+        }
+    }
+}
+
+const TIE_BREAKS: [FolderWinnerRule; 3] = [
+    FolderWinnerRule::EarliestAdded,
+    FolderWinnerRule::SmallestId,
+    FolderWinnerRule::SmallestGuid,
+];
+
+impl FolderWinnerPolicy {
+    fn all_rules(&self) -> impl Iterator<Item = &FolderWinnerRule> {
+        self.rules.iter().chain(TIE_BREAKS.iter())
+    }
+
+    /// `Ordering::Less` means `a` ranks ahead of `b`.
+    pub fn compare(&self, arena: &Arena, a: Handle, b: Handle) -> Ordering {
+        let aa = &arena.nodes[a.0];
+        let bb = &arena.nodes[b.0];
+        self.all_rules()
+            .map(|r| r.compare(aa, bb, arena))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// Human-readable reason why `winner` ranked ahead of `loser`.
+    pub fn explain(&self, arena: &Arena, winner: Handle, loser: Handle) -> String {
+        let w = &arena.nodes[winner.0];
+        let l = &arena.nodes[loser.0];
+        self.all_rules()
+            .find(|r| r.compare(w, l, arena).is_ne())
+            .map(|r| r.describe(w, l, arena))
+            .unwrap_or_else(|| "tie: input order".to_string())
+    }
+}
+
+impl fmt::Display for FolderWinnerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self.rules.iter().map(|r| r.to_string()).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+impl FromStr for FolderWinnerPolicy {
+    type Err = anyhow::Error;
+
+    /// Parses a comma-separated rule list, e.g. `prefer-root:bookmark_bar,outermost`.
+    fn from_str(s: &str) -> Result<Self> {
+        let rules = s
+            .split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<FolderWinnerRule>>>()?;
+        if rules.is_empty() {
+            return Err(anyhow!("folder winner policy must name at least one rule"));
+        }
+        Ok(Self { rules })
+    }
+}
+
+fn live_children(arena: &Arena, n: &ArenaNode) -> usize {
+    n.children
+        .iter()
+        .filter(|c| !arena.nodes[c.0].deleted)
+        .count()
+}
+
+fn parse_modified(n: &ArenaNode) -> u64 {
+    n.date_modified
+        .as_deref()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0)
+}
+
+fn cmp_date_added(a: &ArenaNode, b: &ArenaNode) -> Ordering {
+    parse_edge_time(a.date_added.as_deref()).cmp(&parse_edge_time(b.date_added.as_deref()))
+}
+
+fn parse_edge_time(s: Option<&str>) -> u64 {
+    match s {
+        Some(v) => v.parse::<u64>().unwrap_or(u64::MAX),
+        None => u64::MAX,
+    }
+}
+
+fn cmp_id(a: &ArenaNode, b: &ArenaNode) -> Ordering {
+    let pa = a.id.as_deref().and_then(|s| s.parse::<u64>().ok());
+    let pb = b.id.as_deref().and_then(|s| s.parse::<u64>().ok());
+
+    match (pa, pb) {
+        (Some(aa), Some(bb)) => aa.cmp(&bb),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.id.cmp(&b.id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::NodeKind;
+
+    fn folder(depth: usize, root: &str) -> ArenaNode {
+        ArenaNode {
+            kind: NodeKind::Folder,
+            depth,
+            root_key: Some(root.into()),
+            ..ArenaNode::default()
+        }
+    }
+
+    fn arena_of(nodes: Vec<ArenaNode>) -> Arena {
+        let mut arena = Arena::default();
+        for n in nodes {
+            arena.nodes.push(n);
+            arena.parent.push(None);
+        }
+        arena
+    }
+
+    #[test]
+    fn parse_edge_time_handles_none_and_invalid() {
+        assert_eq!(parse_edge_time(None), u64::MAX);
+        assert_eq!(parse_edge_time(Some("not_a_number")), u64::MAX);
+        assert_eq!(parse_edge_time(Some("42")), 42);
+    }
+
+    #[test]
+    fn cmp_id_orders_numeric_before_missing_and_text() {
+        let a = ArenaNode {
+            id: Some("10".to_string()),
+            ..ArenaNode::default()
+        };
+        let b = ArenaNode {
+            id: None,
+            ..ArenaNode::default()
+        };
+        assert_eq!(cmp_id(&a, &b), Ordering::Less);
+        assert_eq!(cmp_id(&b, &a), Ordering::Greater);
+
+        let c = ArenaNode {
+            id: Some("abc".to_string()),
+            ..ArenaNode::default()
+        };
+        let d = ArenaNode {
+            id: Some("def".to_string()),
+            ..ArenaNode::default()
+        };
+        assert_eq!(cmp_id(&c, &d), Ordering::Less);
+    }
+
+    #[test]
+    fn default_policy_uses_depth_then_date_then_id_then_guid() {
+        let mut arena = arena_of(vec![
+            ArenaNode {
+                date_added: Some("10".to_string()),
+                id: Some("7".to_string()),
+                guid: Some("a".to_string()),
+                ..folder(2, "bookmark_bar")
+            },
+            ArenaNode {
+                date_added: Some("10".to_string()),
+                id: Some("7".to_string()),
+                guid: Some("b".to_string()),
+                ..folder(2, "bookmark_bar")
+            },
+        ]);
+        let policy = FolderWinnerPolicy::default();
+
+        assert_eq!(policy.compare(&arena, Handle(0), Handle(1)), Ordering::Less);
+        assert!(policy
+            .explain(&arena, Handle(0), Handle(1))
+            .starts_with("smallest-guid"));
+
+        arena.nodes[1].depth = 3;
+        assert_eq!(policy.compare(&arena, Handle(0), Handle(1)), Ordering::Less);
+        assert_eq!(
+            policy.explain(&arena, Handle(0), Handle(1)),
+            "outermost: depth 2 < 3"
+        );
+    }
+
+    #[test]
+    fn prefer_root_outranks_depth_when_listed_first() {
+        let arena = arena_of(vec![folder(1, "bookmark_bar"), folder(3, "other")]);
+        let policy: FolderWinnerPolicy = "prefer-root:other,outermost".parse().expect("parse");
+
+        assert_eq!(policy.compare(&arena, Handle(1), Handle(0)), Ordering::Less);
+        assert_eq!(
+            policy.explain(&arena, Handle(1), Handle(0)),
+            "prefer-root:other: root other over bookmark_bar"
+        );
+    }
+
+    #[test]
+    fn most_children_and_most_recently_modified_prefer_larger_values() {
+        let mut arena = arena_of(vec![
+            ArenaNode {
+                date_modified: Some("5".to_string()),
+                ..folder(1, "bookmark_bar")
+            },
+            ArenaNode {
+                date_modified: Some("9".to_string()),
+                children: vec![Handle(2)],
+                ..folder(2, "bookmark_bar")
+            },
+            ArenaNode::default(),
+        ]);
+
+        let policy: FolderWinnerPolicy = "most-children".parse().expect("parse");
+        assert_eq!(policy.compare(&arena, Handle(1), Handle(0)), Ordering::Less);
+
+        let policy: FolderWinnerPolicy = "most-recently-modified".parse().expect("parse");
+        assert_eq!(policy.compare(&arena, Handle(1), Handle(0)), Ordering::Less);
+
+        // Deleted children do not count.
+        arena.nodes[2].deleted = true;
+        let policy: FolderWinnerPolicy = "most-children,outermost".parse().expect("parse");
+        assert_eq!(policy.compare(&arena, Handle(0), Handle(1)), Ordering::Less);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_and_empty_rules() {
+        assert!("outermost,wat".parse::<FolderWinnerPolicy>().is_err());
+        assert!("".parse::<FolderWinnerPolicy>().is_err());
+        assert!("prefer-root:".parse::<FolderWinnerRule>().is_err());
+
+        let policy: FolderWinnerPolicy = " prefer-root:bookmark_bar , most-children"
+            .parse()
+            .expect("parse");
+        assert_eq!(policy.to_string(), "prefer-root:bookmark_bar,most-children");
+    }
+}
@@ -1,21 +1,35 @@
 use crate::domain::graph::Graph;
-use crate::usecase::normalize::arena::Arena;
-use serde_json::json;
+use crate::domain::model::NodeKind;
+use crate::usecase::normalize::arena::{Arena, Handle};
 use std::collections::HashMap;
 
-pub fn build_identity_graph(arena: &Arena) -> (Graph, serde_json::Value) {
+/// Folder containment graph over identities (`guid`, else `id`, else path).
+///
+/// Folders that reuse an identity collapse into one graph node, which is how
+/// cycles appear in a tree-shaped file. Preserved folders are left out, so no cycle runs
+/// through them and none of them is ever cut.
+#[derive(Debug, Clone, Default)]
+pub struct IdentityGraph {
+    pub graph: Graph,
+    /// Sorted identity keys; graph node `i` is `ids[i]`.
+    pub ids: Vec<String>,
+    /// Graph node of each arena handle (`None` for non-folders, deleted and preserved nodes).
+    pub identity_of: Vec<Option<usize>>,
+}
+
+pub fn build_identity_graph(arena: &Arena) -> IdentityGraph {
     let mut ids: Vec<String> = Vec::new();
     let mut handle_to_id: Vec<Option<String>> = vec![None; arena.nodes.len()];
 
     for (h, node) in arena.nodes.iter().enumerate() {
-        if node.node_type != "folder" {
+        if node.deleted || node.preserved || node.kind != NodeKind::Folder {
             continue;
         }
         let key = node
             .guid
             .clone()
             .or_else(|| node.id.clone())
-            .unwrap_or_else(|| format!("path:{}", node.path));
+            .unwrap_or_else(|| format!("path:{}", arena.origin_path(Handle(h))));
         handle_to_id[h] = Some(key);
     }
 
@@ -30,26 +44,22 @@ pub fn build_identity_graph(arena: &Arena) -> (Graph, serde_json::Value) {
         id_index.insert(k.clone(), i);
     }
 
+    let identity_of: Vec<Option<usize>> = handle_to_id
+        .iter()
+        .map(|k| k.as_ref().map(|k| id_index[k]))
+        .collect();
+
     let mut g = Graph::new(ids.len());
 
     for (parent_h, parent_node) in arena.nodes.iter().enumerate() {
-        if parent_node.node_type != "folder" {
-            continue;
-        }
-        let Some(parent_key) = handle_to_id[parent_h].as_ref() else {
+        let Some(p) = identity_of[parent_h] else {
             continue;
         };
-        let p = id_index[parent_key];
 
         for child in parent_node.children.iter() {
-            let child_node = &arena.nodes[child.0];
-            if child_node.node_type != "folder" {
-                continue;
-            }
-            let Some(child_key) = handle_to_id[child.0].as_ref() else {
+            let Some(c) = identity_of[child.0] else {
                 continue;
             };
-            let c = id_index[child_key];
             g.edges[p].push(c);
         }
     }
@@ -59,5 +69,9 @@ pub fn build_identity_graph(arena: &Arena) -> (Graph, serde_json::Value) {
         outs.dedup();
     }
 
-    (g, json!({"identity_nodes": ids.len()}))
+    IdentityGraph {
+        graph: g,
+        ids,
+        identity_of,
+    }
 }
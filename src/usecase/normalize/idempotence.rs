@@ -0,0 +1,162 @@
+use crate::domain::traits::{SccDetector, UrlCanonicalizer};
+use crate::infrastructure::schema_catalog::Schemas;
+use crate::infrastructure::schema_validator::{failing_nodes, schema_violations_with};
+use crate::infrastructure::serde_json_adapter::{model_misfits, BookmarksFileDto};
+use crate::usecase::normalize::removals::RemovalLimits;
+use crate::usecase::normalize::{normalize_bookmarks_with_options, NormalizeOptions};
+use anyhow::Result;
+use serde_json::Value;
+use std::fmt;
+
+/// Differences listed in a [`NotIdempotent`] message; the rest are only counted.
+const SHOWN_DIFFERENCES: usize = 20;
+
+/// Normalizing a run's output changed it again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotIdempotent {
+    /// One line per changed JSON pointer: `~ ptr: old -> new`, `- ptr: old` or `+ ptr: new`.
+    pub differences: Vec<String>,
+}
+
+impl fmt::Display for NotIdempotent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "normalizing the output again changes it ({} difference(s)):",
+            self.differences.len()
+        )?;
+        for line in self.differences.iter().take(SHOWN_DIFFERENCES) {
+            write!(f, "\n  {line}")?;
+        }
+        if self.differences.len() > SHOWN_DIFFERENCES {
+            write!(
+                f,
+                "\n  ... and {} more",
+                self.differences.len() - SHOWN_DIFFERENCES
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NotIdempotent {}
+
+/// Normalizes `output` (the result of a run with `options`) once more and fails with
+/// [`NotIdempotent`] if that changes anything, which means some policy oscillates.
+/// Removal limits do not apply to the second pass: anything it removes is a difference.
+/// Nodes the first pass preserved are found again in `output` by their schema violations
+/// and by not fitting the bookmarks model.
+pub async fn check_idempotent(
+    output: &BookmarksFileDto,
+    canonicalizer: &dyn UrlCanonicalizer,
+    scc: &dyn SccDetector,
+    options: &NormalizeOptions,
+) -> Result<()> {
+    check_idempotent_with(output, canonicalizer, scc, options, &Schemas::embedded()).await
+}
+
+/// [`check_idempotent`] for a run that read its input against `schemas`.
+pub async fn check_idempotent_with(
+    output: &BookmarksFileDto,
+    canonicalizer: &dyn UrlCanonicalizer,
+    scc: &dyn SccDetector,
+    options: &NormalizeOptions,
+    schemas: &Schemas,
+) -> Result<()> {
+    let before = serde_json::to_value(output)?;
+    let preserve = if options.preserve.is_empty() {
+        Default::default()
+    } else {
+        let mut violations = schema_violations_with(schemas.for_document(&before), &before);
+        violations.extend(model_misfits(&before));
+        failing_nodes(&violations)
+    };
+    let options = NormalizeOptions {
+        removal_limits: RemovalLimits::default(),
+        record_provenance: false,
+        preserve,
+        ..options.clone()
+    };
+    let (again, _) =
+        normalize_bookmarks_with_options(output.clone(), canonicalizer, scc, &options, None)
+            .await?;
+
+    let differences = json_diff(&before, &serde_json::to_value(&again)?);
+    if differences.is_empty() {
+        return Ok(());
+    }
+    Err(NotIdempotent { differences }.into())
+}
+
+/// Leaf-level differences between two JSON documents, in document order.
+pub fn json_diff(before: &Value, after: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut stack: Vec<(String, Option<&Value>, Option<&Value>)> =
+        vec![(String::new(), Some(before), Some(after))];
+    while let Some((pointer, a, b)) = stack.pop() {
+        match (a, b) {
+            (Some(Value::Object(a)), Some(Value::Object(b))) => {
+                let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys.into_iter().rev() {
+                    let child = format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+                    stack.push((child, a.get(key), b.get(key)));
+                }
+            }
+            (Some(Value::Array(a)), Some(Value::Array(b))) => {
+                for i in (0..a.len().max(b.len())).rev() {
+                    stack.push((format!("{pointer}/{i}"), a.get(i), b.get(i)));
+                }
+            }
+            (Some(a), Some(b)) if a == b => {}
+            (Some(a), Some(b)) => out.push(format!("~ {pointer}: {} -> {}", brief(a), brief(b))),
+            (Some(a), None) => out.push(format!("- {pointer}: {}", brief(a))),
+            (None, Some(b)) => out.push(format!("+ {pointer}: {}", brief(b))),
+            (None, None) => {}
+        }
+    }
+    out
+}
+
+/// Compact JSON, cut to a readable length.
+fn brief(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(80) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_lists_changed_removed_and_added_leaves_by_pointer() {
+        let before = json!({"roots": {"a/b": {"name": "x", "children": [1, 2]}}});
+        let after = json!({"roots": {"a/b": {"name": "y", "children": [1], "guid": "g"}}});
+        assert_eq!(
+            json_diff(&before, &after),
+            vec![
+                "- /roots/a~1b/children/1: 2",
+                "+ /roots/a~1b/guid: \"g\"",
+                "~ /roots/a~1b/name: \"x\" -> \"y\"",
+            ]
+        );
+        assert!(json_diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn message_lists_the_first_differences_and_counts_the_rest() {
+        let err = NotIdempotent {
+            differences: (0..SHOWN_DIFFERENCES + 3)
+                .map(|i| format!("- /roots/x/children/{i}: 1"))
+                .collect(),
+        };
+        let text = err.to_string();
+        assert!(text.starts_with("normalizing the output again changes it (23 difference(s)):"));
+        assert!(text.contains("\n  - /roots/x/children/19: 1\n  ... and 3 more"));
+    }
+}
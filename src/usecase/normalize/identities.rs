@@ -0,0 +1,490 @@
+use crate::domain::model::NodeKind;
+use crate::usecase::normalize::arena::{Arena, Handle};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+/// What to do with a `guid` or `id` shared by more than one node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateIdentityMode {
+    /// Report only; the identity graph treats the nodes as one identity.
+    #[default]
+    Report,
+    /// Treat folder instances as the same folder: identical copies are dropped, differing
+    /// copies move their children into the first (shallowest) instance.
+    Merge,
+    /// Treat them as separate nodes: every instance after the first gets a fresh value.
+    Separate,
+}
+
+impl fmt::Display for DuplicateIdentityMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Report => "report",
+            Self::Merge => "merge",
+            Self::Separate => "separate",
+        })
+    }
+}
+
+impl FromStr for DuplicateIdentityMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "report" => Ok(Self::Report),
+            "merge" => Ok(Self::Merge),
+            "separate" => Ok(Self::Separate),
+            other => Err(anyhow!(
+                "unknown duplicate identity mode: {other} (expected report, merge, separate)"
+            )),
+        }
+    }
+}
+
+/// One node carrying a shared identity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IdentityInstance {
+    pub json_pointer: String,
+    pub name_path: String,
+    pub node_type: String,
+    pub name: Option<String>,
+    /// Fresh value assigned under `separate`.
+    pub reassigned: Option<String>,
+}
+
+/// A `guid` or `id` value shared by more than one node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateIdentity {
+    /// `guid` or `id`.
+    pub field: String,
+    pub value: String,
+    /// All instances have the same subtree: node types, names and URLs, recursively.
+    pub identical: bool,
+    /// Instances in breadth-first order; the first one keeps the identity.
+    pub instances: Vec<IdentityInstance>,
+}
+
+/// Finds every shared `guid`/`id` and applies `mode`. Runs on the freshly built arena,
+/// before the identity graph is derived.
+pub fn resolve_duplicate_identities(
+    arena: &mut Arena,
+    mode: DuplicateIdentityMode,
+) -> Vec<DuplicateIdentity> {
+    let order = breadth_first(arena);
+    let mut hashes = content_hashes(arena, &order);
+
+    let mut groups: BTreeMap<(&'static str, String), Vec<Handle>> = BTreeMap::new();
+    for &h in order.iter() {
+        let node = &arena.nodes[h.0];
+        if node.preserved {
+            continue;
+        }
+        if let Some(guid) = node.guid.as_ref() {
+            groups.entry(("guid", guid.clone())).or_default().push(h);
+        }
+        if let Some(id) = node.id.as_ref() {
+            groups.entry(("id", id.clone())).or_default().push(h);
+        }
+    }
+
+    let mut next_id = arena
+        .nodes
+        .iter()
+        .filter_map(|n| n.id.as_deref()?.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let mut out = Vec::new();
+    for ((field, value), handles) in groups {
+        if handles.len() < 2 {
+            continue;
+        }
+        // Earlier merges may have deleted instances or changed their subtrees; `hashes`
+        // is kept current for every live node.
+        let live: Vec<Handle> = handles
+            .iter()
+            .copied()
+            .filter(|h| !arena.nodes[h.0].deleted)
+            .collect();
+        let identical = live.iter().all(|h| hashes[h.0] == hashes[live[0].0]);
+        let mut instances: Vec<IdentityInstance> = handles
+            .iter()
+            .map(|h| IdentityInstance {
+                json_pointer: arena.json_pointer(*h),
+                name_path: arena.name_path(*h),
+                node_type: arena.nodes[h.0].kind.to_string(),
+                name: arena.nodes[h.0].name.clone(),
+                reassigned: None,
+            })
+            .collect();
+
+        match mode {
+            DuplicateIdentityMode::Report => {}
+            DuplicateIdentityMode::Merge => {
+                let parents: Vec<Handle> =
+                    handles.iter().filter_map(|h| arena.parent[h.0]).collect();
+                merge_instances(arena, &handles, identical);
+                for &h in parents.iter().chain(&handles[..1]) {
+                    rehash(arena, &mut hashes, h);
+                }
+            }
+            DuplicateIdentityMode::Separate => {
+                for (h, inst) in handles.iter().zip(instances.iter_mut()).skip(1) {
+                    let fresh = if field == "guid" {
+                        fresh_guid(&value, &inst.json_pointer)
+                    } else {
+                        next_id += 1;
+                        (next_id - 1).to_string()
+                    };
+                    let node = &mut arena.nodes[h.0];
+                    if field == "guid" {
+                        node.guid = Some(fresh.clone());
+                    } else {
+                        node.id = Some(fresh.clone());
+                    }
+                    inst.reassigned = Some(fresh);
+                }
+            }
+        }
+
+        out.push(DuplicateIdentity {
+            field: field.to_string(),
+            value,
+            identical,
+            instances,
+        });
+    }
+    out
+}
+
+/// Folds every later folder instance into the first one. Root containers and groups
+/// containing non-folders are left alone (reported only).
+fn merge_instances(arena: &mut Arena, handles: &[Handle], identical: bool) {
+    let keep = handles[0];
+    let mergeable = handles
+        .iter()
+        .all(|h| arena.nodes[h.0].kind == NodeKind::Folder && !arena.is_root_container(*h));
+    if !mergeable || arena.nodes[keep.0].deleted {
+        return;
+    }
+
+    for &dup in handles.iter().skip(1) {
+        // A repeat nested above the kept instance cannot be folded into it.
+        if arena.nodes[dup.0].deleted || arena.is_ancestor_of(dup, keep) {
+            continue;
+        }
+        if identical {
+            absorb_subtree(arena, dup, keep);
+        } else {
+            arena.move_children(dup, keep);
+            arena.absorb(dup, keep);
+        }
+    }
+    arena.compact_children();
+}
+
+/// Deletes an identical copy node by node, each into its counterpart under `keep`.
+/// Children are paired by guid, then id, then type, name and URL rather than by position;
+/// a child left without a counterpart is moved under `keep` instead of being dropped.
+fn absorb_subtree(arena: &mut Arena, dup: Handle, keep: Handle) {
+    let mut stack = vec![(dup, keep)];
+    while let Some((d, k)) = stack.pop() {
+        arena.absorb(d, k);
+        let live = |arena: &Arena, h: Handle| -> Vec<Handle> {
+            arena.nodes[h.0]
+                .children
+                .iter()
+                .copied()
+                .filter(|c| !arena.nodes[c.0].deleted)
+                .collect()
+        };
+        let mut unpaired = live(arena, k);
+        for ch in live(arena, d) {
+            match counterpart(arena, ch, &unpaired) {
+                Some(i) => stack.push((ch, unpaired.remove(i))),
+                None => {
+                    arena.parent[ch.0] = Some(k);
+                    arena.nodes[k.0].children.push(ch);
+                }
+            }
+        }
+    }
+}
+
+/// Index in `candidates` of the node `h` pairs with: same guid, else same id, else same
+/// type, name and URL.
+fn counterpart(arena: &Arena, h: Handle, candidates: &[Handle]) -> Option<usize> {
+    let node = &arena.nodes[h.0];
+    let same_guid = |c: &Handle| node.guid.is_some() && arena.nodes[c.0].guid == node.guid;
+    let same_id = |c: &Handle| node.id.is_some() && arena.nodes[c.0].id == node.id;
+    let same_path = |c: &Handle| {
+        let other = &arena.nodes[c.0];
+        other.kind == node.kind && other.name == node.name && other.url == node.url
+    };
+    candidates
+        .iter()
+        .position(same_guid)
+        .or_else(|| candidates.iter().position(same_id))
+        .or_else(|| candidates.iter().position(same_path))
+}
+
+/// Deterministic UUID-shaped guid derived from the shared value and the instance location.
+fn fresh_guid(value: &str, json_pointer: &str) -> String {
+    let hex = format!("{:x}", md5::compute(format!("{value}\u{0}{json_pointer}")));
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn breadth_first(arena: &Arena) -> Vec<Handle> {
+    let mut out = Vec::new();
+    let mut queue: VecDeque<Handle> = arena.root_container.values().copied().collect();
+    while let Some(h) = queue.pop_front() {
+        if arena.nodes[h.0].deleted {
+            continue;
+        }
+        out.push(h);
+        queue.extend(arena.nodes[h.0].children.iter().copied());
+    }
+    out
+}
+
+/// md5 over node type, name, URL and child hashes; children come later in breadth-first
+/// order, so walking it backwards sees them first.
+fn content_hashes(arena: &Arena, order: &[Handle]) -> Vec<String> {
+    let mut hashes = vec![String::new(); arena.nodes.len()];
+    for &h in order.iter().rev() {
+        hashes[h.0] = node_hash(arena, &hashes, h);
+    }
+    hashes
+}
+
+/// Recomputes the hashes of `h`'s subtree and of its ancestors after a merge changed it.
+fn rehash(arena: &Arena, hashes: &mut [String], h: Handle) {
+    let mut subtree = vec![h];
+    let mut i = 0;
+    while i < subtree.len() {
+        let node = &arena.nodes[subtree[i].0];
+        subtree.extend(
+            node.children
+                .iter()
+                .copied()
+                .filter(|c| !arena.nodes[c.0].deleted),
+        );
+        i += 1;
+    }
+    for &n in subtree.iter().rev() {
+        hashes[n.0] = node_hash(arena, hashes, n);
+    }
+    let mut cur = arena.parent[h.0];
+    while let Some(p) = cur {
+        hashes[p.0] = node_hash(arena, hashes, p);
+        cur = arena.parent[p.0];
+    }
+}
+
+fn node_hash(arena: &Arena, hashes: &[String], h: Handle) -> String {
+    let node = &arena.nodes[h.0];
+    let mut ctx = md5::Context::new();
+    for part in [
+        node.kind.as_str(),
+        node.name.as_deref().unwrap_or_default(),
+        node.url.as_deref().unwrap_or_default(),
+    ] {
+        ctx.consume(part);
+        ctx.consume([0u8]);
+    }
+    for ch in node.children.iter().filter(|c| !arena.nodes[c.0].deleted) {
+        ctx.consume(&hashes[ch.0]);
+    }
+    format!("{:x}", ctx.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::normalize::arena::ArenaNode;
+
+    fn push(arena: &mut Arena, parent: Option<usize>, node: ArenaNode) -> Handle {
+        let h = Handle(arena.nodes.len());
+        let depth = parent.map_or(0, |p| arena.nodes[p].depth + 1);
+        arena.nodes.push(ArenaNode {
+            root_key: Some("bookmark_bar".into()),
+            origin_parent: parent.map(Handle),
+            origin_index: parent.map_or(0, |p| arena.nodes[p].children.len()),
+            depth,
+            ..node
+        });
+        arena.parent.push(parent.map(Handle));
+        match parent {
+            Some(p) => arena.nodes[p].children.push(h),
+            None => {
+                arena.root_container.insert("bookmark_bar".to_string(), h);
+            }
+        }
+        h
+    }
+
+    fn folder(name: &str, guid: &str, id: &str) -> ArenaNode {
+        ArenaNode {
+            kind: NodeKind::Folder,
+            name: Some(name.to_string()),
+            guid: Some(guid.to_string()),
+            id: Some(id.to_string()),
+            ..ArenaNode::default()
+        }
+    }
+
+    fn url(u: &str, id: &str) -> ArenaNode {
+        ArenaNode {
+            kind: NodeKind::Url,
+            name: Some(u.to_string()),
+            url: Some(u.to_string()),
+            id: Some(id.to_string()),
+            ..ArenaNode::default()
+        }
+    }
+
+    /// root: [A(g1,id 2){x}, B(g2,id 3)[A(g1,id 4){y}]]
+    fn sample() -> Arena {
+        let mut arena = Arena::default();
+        push(&mut arena, None, folder("Bookmarks bar", "root", "1"));
+        push(&mut arena, Some(0), folder("A", "g1", "2"));
+        push(&mut arena, Some(1), url("http://x", "10"));
+        push(&mut arena, Some(0), folder("B", "g2", "3"));
+        push(&mut arena, Some(3), folder("A", "g1", "4"));
+        push(&mut arena, Some(4), url("http://y", "11"));
+        arena
+    }
+
+    #[test]
+    fn report_lists_shared_guid_with_locations() {
+        let mut arena = sample();
+        let found = resolve_duplicate_identities(&mut arena, DuplicateIdentityMode::Report);
+
+        assert_eq!(found.len(), 1);
+        let dup = &found[0];
+        assert_eq!((dup.field.as_str(), dup.value.as_str()), ("guid", "g1"));
+        assert!(!dup.identical);
+        let pointers: Vec<&str> = dup
+            .instances
+            .iter()
+            .map(|i| i.json_pointer.as_str())
+            .collect();
+        assert_eq!(
+            pointers,
+            vec![
+                "/roots/bookmark_bar/children/0",
+                "/roots/bookmark_bar/children/1/children/0"
+            ]
+        );
+        assert_eq!(dup.instances[1].name_path, "Bookmarks bar / B / A");
+    }
+
+    #[test]
+    fn merge_moves_children_of_differing_copy() {
+        let mut arena = sample();
+        resolve_duplicate_identities(&mut arena, DuplicateIdentityMode::Merge);
+
+        assert!(arena.nodes[4].deleted);
+        assert!(arena.nodes[3].children.is_empty());
+        assert_eq!(arena.nodes[1].children, vec![Handle(2), Handle(5)]);
+    }
+
+    #[test]
+    fn merge_drops_identical_copy_with_its_subtree() {
+        let mut arena = sample();
+        arena.nodes[5].url = Some("http://x".to_string());
+        arena.nodes[5].name = Some("http://x".to_string());
+
+        let found = resolve_duplicate_identities(&mut arena, DuplicateIdentityMode::Merge);
+        assert!(found[0].identical);
+        assert!(arena.nodes[4].deleted && arena.nodes[5].deleted);
+        assert_eq!(arena.nodes[1].children, vec![Handle(2)]);
+    }
+
+    #[test]
+    fn merge_sees_children_an_earlier_group_moved() {
+        // P(g3){A(g9){x}} and P(g3){A(g1){x}} start out identical; merging g1 then moves y
+        // from the deeper D(g1) into the second copy, so the g3 copies no longer match.
+        let mut arena = Arena::default();
+        push(&mut arena, None, folder("Bookmarks bar", "root", "1"));
+        push(&mut arena, Some(0), folder("P", "g3", "2"));
+        push(&mut arena, Some(1), folder("A", "g9", "3"));
+        push(&mut arena, Some(2), url("http://x", "10"));
+        push(&mut arena, Some(0), folder("P", "g3", "4"));
+        push(&mut arena, Some(4), folder("A", "g1", "5"));
+        push(&mut arena, Some(5), url("http://x", "11"));
+        push(&mut arena, Some(0), folder("Deep", "g7", "6"));
+        push(&mut arena, Some(7), folder("E", "g8", "7"));
+        push(&mut arena, Some(8), folder("D", "g1", "8"));
+        let y = push(&mut arena, Some(9), url("http://y", "12"));
+
+        let found = resolve_duplicate_identities(&mut arena, DuplicateIdentityMode::Merge);
+        let g3 = found.iter().find(|d| d.value == "g3").expect("g3 group");
+        assert!(!g3.identical);
+        assert!(!arena.nodes[y.0].deleted);
+        assert!(arena.reachable()[y.0]);
+        assert_eq!(arena.nodes[1].children, vec![Handle(2), Handle(5)]);
+    }
+
+    #[test]
+    fn identical_copies_pair_children_by_identity_not_position() {
+        // keep: [b, a]; dup: [a, b] with matching guids.
+        let mut arena = Arena::default();
+        push(&mut arena, None, folder("Bookmarks bar", "root", "1"));
+        let keep = push(&mut arena, Some(0), folder("F", "g1", "2"));
+        let kb = push(&mut arena, Some(1), folder("S", "gb", "3"));
+        let ka = push(&mut arena, Some(1), folder("S", "ga", "4"));
+        let dup = push(&mut arena, Some(0), folder("F", "g1", "5"));
+        let da = push(&mut arena, Some(4), folder("S", "ga", "6"));
+        let db = push(&mut arena, Some(4), folder("S", "gb", "7"));
+
+        absorb_subtree(&mut arena, dup, keep);
+        assert_eq!(arena.absorber(da), Some(ka));
+        assert_eq!(arena.absorber(db), Some(kb));
+    }
+
+    #[test]
+    fn separate_assigns_fresh_deterministic_identities() {
+        let mut arena = sample();
+        arena.nodes[5].id = Some("10".to_string());
+
+        let found = resolve_duplicate_identities(&mut arena, DuplicateIdentityMode::Separate);
+        let by_field: Vec<(&str, Option<&str>)> = found
+            .iter()
+            .map(|d| (d.field.as_str(), d.instances[1].reassigned.as_deref()))
+            .collect();
+
+        assert_eq!(by_field[0].0, "guid");
+        assert_eq!(by_field[1], ("id", Some("11")));
+        assert_eq!(arena.nodes[5].id.as_deref(), Some("11"));
+        assert_eq!(arena.nodes[2].id.as_deref(), Some("10"));
+
+        let fresh = arena.nodes[4].guid.clone().expect("guid");
+        assert_ne!(fresh, "g1");
+        assert_eq!(fresh.len(), 36);
+        assert_eq!(
+            fresh,
+            fresh_guid("g1", "/roots/bookmark_bar/children/1/children/0")
+        );
+    }
+
+    #[test]
+    fn parse_modes() {
+        assert_eq!(
+            "separate".parse::<DuplicateIdentityMode>().unwrap(),
+            DuplicateIdentityMode::Separate
+        );
+        assert!("split".parse::<DuplicateIdentityMode>().is_err());
+    }
+}
@@ -1,92 +1,124 @@
 mod arena;
 mod build;
+pub mod condense;
+pub mod convergence;
+pub mod cycles;
+pub mod folder_attrs;
 mod folder_merge;
+pub mod folder_name;
+pub mod folder_winner;
 mod graph;
+pub mod idempotence;
+pub mod identities;
+pub mod provenance;
 mod prune;
 mod rebuild;
+pub mod removals;
+pub mod stream;
 mod url_dedup;
+pub mod url_merge;
+pub mod url_winner;
+
+pub use folder_merge::folder_key;
 
 use crate::domain::traits::{SccDetector, UrlCanonicalizer};
-use crate::infrastructure::serde_json_adapter::{BookmarkNodeDto, BookmarksFileDto};
+use crate::infrastructure::process_memory::peak_memory_bytes;
+use crate::infrastructure::serde_json_adapter::BookmarksFileDto;
 use crate::usecase::event::AppEvent;
+use crate::usecase::normalize::convergence::{
+    loop_state, ConvergenceGuard, DEFAULT_MAX_MERGE_ITERATIONS,
+};
+use crate::usecase::normalize::cycles::CycleDiagnostic;
+use crate::usecase::normalize::folder_attrs::FolderMergePolicy;
+use crate::usecase::normalize::folder_name::FolderNamePolicy;
+use crate::usecase::normalize::folder_winner::FolderWinnerPolicy;
+use crate::usecase::normalize::identities::DuplicateIdentityMode;
+use crate::usecase::normalize::provenance::Provenance;
+use crate::usecase::normalize::removals::{RemovalLimitError, RemovalLimits, RemovalSummary};
+use crate::usecase::normalize::url_merge::UrlMergePolicy;
+use crate::usecase::normalize::url_winner::UrlWinnerPolicy;
+use crate::usecase::report::NormalizeReport;
 use crate::usecase::stats::NormalizeStats;
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 use tokio::sync::mpsc;
 
+pub use arena::FolderRegistry;
+
+/// Tunable policies for a normalization run. `Default` reproduces the documented domain rules.
 #[derive(Debug, Clone)]
-pub struct FolderRegistry {
-    pub counts: HashMap<String, usize>,
+pub struct NormalizeOptions {
+    pub folder_winner: FolderWinnerPolicy,
+    pub folder_name: FolderNamePolicy,
+    pub folder_merge: FolderMergePolicy,
+    pub url_winner: UrlWinnerPolicy,
+    pub url_merge: UrlMergePolicy,
+    pub duplicate_identities: DuplicateIdentityMode,
+    /// Upper bound on merge/dedup/prune iterations; see [`convergence::MergeLoopError`].
+    pub max_merge_iterations: usize,
+    /// Caps on removed URLs and folders; see [`removals::RemovalLimitError`].
+    pub removal_limits: RemovalLimits,
+    /// Record every input node's fate in [`NormalizeReport::provenance`].
+    pub record_provenance: bool,
+    /// Input nodes, by JSON pointer, to leave exactly as read; lenient reading fills it
+    /// with the nodes that failed their schema.
+    pub preserve: BTreeSet<String>,
 }
 
-impl FolderRegistry {
-    pub fn new() -> Self {
+impl Default for NormalizeOptions {
+    fn default() -> Self {
         Self {
-            counts: HashMap::new(),
-        }
-    }
-
-    pub fn count_folders(&mut self, dto: &BookmarksFileDto) {
-        self.counts.clear();
-        for root in dto.roots.values() {
-            self.traverse_and_count(root);
-        }
-    }
-
-    fn traverse_and_count(&mut self, node: &BookmarkNodeDto) {
-        if node.node_type == "folder" {
-            if let Some(name) = &node.name {
-                *self.counts.entry(name.clone()).or_insert(0) += 1;
-            }
-        }
-        for child in &node.children {
-            self.traverse_and_count(child);
-        }
-    }
-
-    pub fn all_unique(&self) -> bool {
-        self.counts.values().all(|&count| count == 1)
-    }
-
-    pub fn print_counts(&self) {
-        let mut sorted: Vec<_> = self.counts.iter().collect();
-        sorted.sort_by_key(|(name, _)| (*name).clone());
-        eprintln!("Folder counts:");
-        for (name, count) in sorted {
-            eprintln!("  {name}: {count}");
-        }
-        let all = self.all_unique();
-        eprintln!("All unique: {all}");
-    }
-
-    pub fn print_final_registry(&self) {
-        let mut sorted: Vec<_> = self.counts.iter().collect();
-        sorted.sort_by_key(|(name, _)| (*name).clone());
-        eprintln!("\nFinal Folder Registry:");
-        eprintln!("folder_name | count (number of occurrences)");
-        eprintln!("------------|-----------------------------");
-        for (name, count) in sorted {
-            eprintln!("{name:<12} | {count} (number of occurrences)");
+            folder_winner: FolderWinnerPolicy::default(),
+            folder_name: FolderNamePolicy::default(),
+            folder_merge: FolderMergePolicy::default(),
+            url_winner: UrlWinnerPolicy::default(),
+            url_merge: UrlMergePolicy::default(),
+            duplicate_identities: DuplicateIdentityMode::default(),
+            max_merge_iterations: DEFAULT_MAX_MERGE_ITERATIONS,
+            removal_limits: RemovalLimits::default(),
+            record_provenance: false,
+            preserve: BTreeSet::new(),
         }
-        let all = self.all_unique();
-        eprintln!("All unique: {all}");
     }
 }
 
-impl Default for FolderRegistry {
-    fn default() -> Self {
-        Self::new()
-    }
+pub async fn normalize_bookmarks(
+    input: BookmarksFileDto,
+    canonicalizer: &dyn UrlCanonicalizer,
+    scc: &dyn SccDetector,
+    sink: Option<mpsc::Sender<AppEvent>>,
+) -> Result<(BookmarksFileDto, NormalizeStats)> {
+    normalize_bookmarks_with_options(
+        input,
+        canonicalizer,
+        scc,
+        &NormalizeOptions::default(),
+        sink,
+    )
+    .await
 }
 
-pub async fn normalize_bookmarks(
+pub async fn normalize_bookmarks_with_options(
     input: BookmarksFileDto,
     canonicalizer: &dyn UrlCanonicalizer,
     scc: &dyn SccDetector,
+    options: &NormalizeOptions,
     sink: Option<mpsc::Sender<AppEvent>>,
 ) -> Result<(BookmarksFileDto, NormalizeStats)> {
+    let (out, report) =
+        normalize_bookmarks_with_report(input, canonicalizer, scc, options, sink).await?;
+    Ok((out, report.stats))
+}
+
+/// Like [`normalize_bookmarks_with_options`], but returns the full [`NormalizeReport`].
+pub async fn normalize_bookmarks_with_report(
+    input: BookmarksFileDto,
+    canonicalizer: &dyn UrlCanonicalizer,
+    scc: &dyn SccDetector,
+    options: &NormalizeOptions,
+    sink: Option<mpsc::Sender<AppEvent>>,
+) -> Result<(BookmarksFileDto, NormalizeReport)> {
     let mut stats = NormalizeStats::default();
-    let mut registry = FolderRegistry::new();
 
     emit(
         &sink,
@@ -96,6 +128,7 @@ pub async fn normalize_bookmarks(
     )
     .await;
     let mut arena = build::build_arena_from_dto(&input, &mut stats);
+    arena.canonicalize_urls(canonicalizer);
     emit(
         &sink,
         AppEvent::PhaseFinished {
@@ -104,22 +137,104 @@ pub async fn normalize_bookmarks(
     )
     .await;
 
+    normalize_arena(input, arena, stats, canonicalizer, scc, options, sink).await
+}
+
+/// Like [`normalize_bookmarks_with_report`], for a file read by [`stream::load_bookmarks`].
+pub async fn normalize_loaded_with_report(
+    loaded: stream::LoadedBookmarks,
+    canonicalizer: &dyn UrlCanonicalizer,
+    scc: &dyn SccDetector,
+    options: &NormalizeOptions,
+    sink: Option<mpsc::Sender<AppEvent>>,
+) -> Result<(BookmarksFileDto, NormalizeReport)> {
+    let mut stats = NormalizeStats::default();
+    let stream::LoadedBookmarks {
+        base, mut arena, ..
+    } = loaded;
+
+    emit(
+        &sink,
+        AppEvent::PhaseStarted {
+            name: "parse_and_index".into(),
+        },
+    )
+    .await;
+    build::index_arena(&mut arena, &mut stats);
+    arena.canonicalize_urls(canonicalizer);
+    emit(
+        &sink,
+        AppEvent::PhaseFinished {
+            name: "parse_and_index".into(),
+        },
+    )
+    .await;
+
+    normalize_arena(base, arena, stats, canonicalizer, scc, options, sink).await
+}
+
+/// Every phase after the arena is built; `input` supplies the top-level fields of the output.
+async fn normalize_arena(
+    input: BookmarksFileDto,
+    mut arena: arena::Arena,
+    mut stats: NormalizeStats,
+    canonicalizer: &dyn UrlCanonicalizer,
+    scc: &dyn SccDetector,
+    options: &NormalizeOptions,
+    sink: Option<mpsc::Sender<AppEvent>>,
+) -> Result<(BookmarksFileDto, NormalizeReport)> {
+    let mut cycles: Vec<CycleDiagnostic> = Vec::new();
+    if !options.preserve.is_empty() {
+        stats.nodes_preserved = arena.preserve(&options.preserve);
+    }
+
+    emit(
+        &sink,
+        AppEvent::PhaseStarted {
+            name: "identity_analysis".into(),
+        },
+    )
+    .await;
+    let duplicate_identities =
+        identities::resolve_duplicate_identities(&mut arena, options.duplicate_identities);
+    for dup in duplicate_identities.iter() {
+        emit(
+            &sink,
+            AppEvent::DuplicateIdentity {
+                field: dup.field.clone(),
+                value: dup.value.clone(),
+                identical: dup.identical,
+                instances: dup.instances.clone(),
+            },
+        )
+        .await;
+    }
+    emit(
+        &sink,
+        AppEvent::PhaseFinished {
+            name: "identity_analysis".into(),
+        },
+    )
+    .await;
+
     emit(&sink, AppEvent::PhaseStarted { name: "scc".into() }).await;
-    let (graph, _scc_summary) = graph::build_identity_graph(&arena);
-    let scc_res = scc.compute_scc(&graph);
+    let condensed = condense::condense(&mut arena, scc)?;
     emit(
         &sink,
         AppEvent::SccComputed {
-            nodes: graph.node_count(),
-            edges: graph.edge_count(),
-            components: scc_res.components.len(),
-            cyclic_components: scc_res.cyclic_component.iter().filter(|&&b| b).count(),
+            nodes: condensed.summary.nodes,
+            edges: condensed.summary.edges,
+            components: condensed.summary.components,
+            cyclic_components: condensed.summary.cyclic_components,
         },
     )
     .await;
+    report_condensed(&condensed, &sink, &mut stats, &mut cycles).await;
     emit(&sink, AppEvent::PhaseFinished { name: "scc".into() }).await;
+    let mut order = condensed.order;
 
-    // Iterative folder merging until all folders are unique
+    // Iterative folder merging until all folders are unique, bounded by the guard
+    let mut guard = ConvergenceGuard::new(options.max_merge_iterations, loop_state(&arena));
     let mut iteration = 0;
     loop {
         iteration += 1;
@@ -131,7 +246,19 @@ pub async fn normalize_bookmarks(
         )
         .await;
 
-        folder_merge::global_folder_merge(&mut arena, &sink, &mut stats).await;
+        if iteration > 1 {
+            order = recondense(&mut arena, scc, &sink, &mut stats, &mut cycles).await?;
+        }
+        folder_merge::global_folder_merge(
+            &mut arena,
+            &options.folder_winner,
+            &options.folder_name,
+            &options.folder_merge,
+            &order,
+            &sink,
+            &mut stats,
+        )
+        .await;
 
         emit(
             &sink,
@@ -140,7 +267,15 @@ pub async fn normalize_bookmarks(
             },
         )
         .await;
-        url_dedup::per_folder_url_dedup(&mut arena, canonicalizer, &sink, &mut stats).await;
+        url_dedup::per_folder_url_dedup(
+            &mut arena,
+            canonicalizer,
+            &options.url_winner,
+            &options.url_merge,
+            &sink,
+            &mut stats,
+        )
+        .await;
         emit(
             &sink,
             AppEvent::PhaseFinished {
@@ -156,7 +291,9 @@ pub async fn normalize_bookmarks(
             },
         )
         .await;
-        prune::prune_empty_folders(&mut arena, &sink, &mut stats).await;
+        // Merges and dedup reshape the tree, so prune follows a fresh condensation.
+        order = recondense(&mut arena, scc, &sink, &mut stats, &mut cycles).await?;
+        prune::prune_empty_folders(&mut arena, &order, &sink, &mut stats).await;
         emit(
             &sink,
             AppEvent::PhaseFinished {
@@ -172,20 +309,19 @@ pub async fn normalize_bookmarks(
             },
         )
         .await;
-        let current_dto =
-            rebuild::rebuild_dto_from_arena(input.clone(), arena.clone(), canonicalizer);
-        registry.count_folders(&current_dto);
-        registry.print_counts();
-
-        emit(
-            &sink,
-            AppEvent::FolderCounts {
-                counts: registry.counts.clone(),
-            },
-        )
-        .await;
+        // The arena keeps its folder index current, so no DTO is rebuilt here.
+        arena.folders.print_counts();
+        if sink.is_some() {
+            emit(
+                &sink,
+                AppEvent::FolderCounts {
+                    counts: arena.folders.counts().clone(),
+                },
+            )
+            .await;
+        }
 
-        if registry.all_unique() {
+        if arena.folders.all_unique() {
             emit(
                 &sink,
                 AppEvent::PhaseFinished {
@@ -203,6 +339,9 @@ pub async fn normalize_bookmarks(
             },
         )
         .await;
+
+        let state = loop_state(&arena);
+        guard.check(iteration, state, arena.folders.collisions())?;
     }
 
     emit(
@@ -212,6 +351,35 @@ pub async fn normalize_bookmarks(
         },
     )
     .await;
+    // Checked before the rebuild, so a run over the limits never produces output.
+    let removals = RemovalSummary::from_arena(&arena);
+    let exceeded = removals.exceeded(&options.removal_limits);
+    for limit in exceeded.iter() {
+        emit(
+            &sink,
+            AppEvent::RemovalLimitExceeded {
+                kind: limit.kind.to_string(),
+                removed: limit.removed,
+                total: limit.total,
+                limit: limit.limit.to_string(),
+                forced: options.removal_limits.force,
+            },
+        )
+        .await;
+    }
+    if !exceeded.is_empty() && !options.removal_limits.force {
+        return Err(RemovalLimitError {
+            summary: removals,
+            exceeded,
+        }
+        .into());
+    }
+
+    let provenance = options
+        .record_provenance
+        .then(|| Provenance::from_arena(&arena));
+
+    let folders = std::mem::take(&mut arena.folders);
     let out = rebuild::rebuild_dto_from_arena(input, arena, canonicalizer);
     // Removed x_merge_meta to preserve original JSON structure
     emit(
@@ -223,7 +391,9 @@ pub async fn normalize_bookmarks(
     .await;
 
     // Print final registry
-    registry.print_final_registry();
+    folders.print_final_registry();
+
+    stats.peak_memory_bytes = peak_memory_bytes();
 
     emit(
         &sink,
@@ -232,7 +402,63 @@ pub async fn normalize_bookmarks(
         },
     )
     .await;
-    Ok((out, stats))
+    Ok((
+        out,
+        NormalizeReport {
+            stats,
+            cycles,
+            duplicate_identities,
+            removals,
+            provenance,
+        },
+    ))
+}
+
+/// Re-derives the processing order for the current tree, cutting any identity cycle
+/// the previous phases produced.
+async fn recondense(
+    arena: &mut arena::Arena,
+    scc: &dyn SccDetector,
+    sink: &Option<mpsc::Sender<AppEvent>>,
+    stats: &mut NormalizeStats,
+    cycles: &mut Vec<CycleDiagnostic>,
+) -> Result<condense::ProcessingOrder> {
+    let condensed = condense::recondense(arena, scc)?;
+    report_condensed(&condensed, sink, stats, cycles).await;
+    Ok(condensed.order)
+}
+
+async fn report_condensed(
+    condensed: &condense::Condensed,
+    sink: &Option<mpsc::Sender<AppEvent>>,
+    stats: &mut NormalizeStats,
+    cycles: &mut Vec<CycleDiagnostic>,
+) {
+    for cycle in condensed.cycles.iter() {
+        emit(
+            sink,
+            AppEvent::CycleDetected {
+                members: cycle.members.clone(),
+                witness: cycle.witness.clone(),
+                witness_edges: cycle.witness_edges.clone(),
+            },
+        )
+        .await;
+        cycles.push(cycle.clone());
+    }
+    for cut in condensed.cuts.iter() {
+        stats.identity_edges_cut += 1;
+        emit(
+            sink,
+            AppEvent::IdentityEdgeCut {
+                identity: cut.identity.clone(),
+                parent_path: cut.parent_path.clone(),
+                child_path: cut.child_path.clone(),
+                moved_to: cut.moved_to.clone(),
+            },
+        )
+        .await;
+    }
 }
 
 async fn emit(sink: &Option<mpsc::Sender<AppEvent>>, ev: AppEvent) {
@@ -240,3 +466,57 @@ async fn emit(sink: &Option<mpsc::Sender<AppEvent>>, ev: AppEvent) {
         let _ = tx.send(ev).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::scc_kosaraju::KosarajuSccDetector;
+    use crate::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
+    use crate::usecase::normalize::convergence::{FolderCollision, MergeLoopError};
+
+    #[tokio::test]
+    async fn merge_loop_stops_when_an_iteration_leaves_the_tree_unchanged() {
+        let input: BookmarksFileDto = serde_json::from_str(
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "name": "a", "url": "https://a.example/"}
+                ]},
+                {"type": "folder", "name": "work", "children": [
+                    {"type": "url", "name": "b", "url": "https://b.example/"}
+                ]}
+            ]}}}"#,
+        )
+        .expect("input");
+        let mut stats = NormalizeStats::default();
+        let mut arena = build::build_arena_from_dto(&input, &mut stats);
+        // Preserved after indexing, so the registry still counts a collision that the merge
+        // phase, which skips preserved folders, can never resolve.
+        let preserved = arena
+            .nodes
+            .iter()
+            .position(|n| n.name.as_deref() == Some("work"));
+        arena.nodes[preserved.expect("work")].preserved = true;
+
+        let err = normalize_arena(
+            input,
+            arena,
+            stats,
+            &DefaultUrlCanonicalizer,
+            &KosarajuSccDetector,
+            &NormalizeOptions::default(),
+            None,
+        )
+        .await
+        .expect_err("the loop cannot converge");
+        assert_eq!(
+            err.downcast_ref::<MergeLoopError>(),
+            Some(&MergeLoopError::NoProgress {
+                iteration: 1,
+                collisions: vec![FolderCollision {
+                    key: "work".to_string(),
+                    count: 2,
+                }],
+            })
+        );
+    }
+}
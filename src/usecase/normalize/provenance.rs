@@ -0,0 +1,107 @@
+use crate::usecase::normalize::arena::{Arena, Handle};
+use serde::{Deserialize, Serialize};
+
+/// What a run did with one input node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "fate", rename_all = "snake_case")]
+pub enum Fate {
+    /// Still in the output.
+    Kept,
+    /// Merged or deduplicated into the node at `into` (a JSON pointer into the input),
+    /// which is kept or was itself merged further.
+    Merged { into: String },
+    /// Deleted with no surviving node to absorb it, e.g. a pruned empty folder.
+    Removed,
+}
+
+/// One input node, by JSON pointer into the input file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceEntry {
+    pub pointer: String,
+    #[serde(flatten)]
+    pub fate: Fate,
+}
+
+/// Fate of every input node (`normalize --provenance <path>`), in input allocation order;
+/// the evidence `verify-conservation` checks a run against.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub nodes: Vec<ProvenanceEntry>,
+}
+
+impl Provenance {
+    /// A deleted node counts as merged when its [`Arena::absorber`] chain ends at a node
+    /// that survived; each entry names only the first step of that chain.
+    pub fn from_arena(arena: &Arena) -> Self {
+        let live = arena.reachable();
+        let nodes = (0..arena.nodes.len())
+            .map(|i| {
+                let h = Handle(i);
+                let mut cur = h;
+                while !live[cur.0] {
+                    match arena.absorber(cur) {
+                        Some(next) => cur = next,
+                        None => break,
+                    }
+                }
+                let fate = match arena.absorber(h) {
+                    _ if live[h.0] => Fate::Kept,
+                    Some(into) if live[cur.0] => Fate::Merged {
+                        into: arena.json_pointer(into),
+                    },
+                    _ => Fate::Removed,
+                };
+                ProvenanceEntry {
+                    pointer: arena.json_pointer(h),
+                    fate,
+                }
+            })
+            .collect();
+        Provenance { nodes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::normalize::arena::ArenaNode;
+
+    #[test]
+    fn follows_absorber_chains_to_the_surviving_node() {
+        let mut arena = Arena::default();
+        for i in 0..5usize {
+            arena.nodes.push(ArenaNode {
+                root_key: Some("bookmark_bar".into()),
+                origin_parent: (i > 0).then_some(Handle(0)),
+                origin_index: i.saturating_sub(1),
+                depth: usize::from(i > 0),
+                ..ArenaNode::default()
+            });
+            arena.parent.push((i > 0).then_some(Handle(0)));
+            if i > 0 {
+                arena.nodes[0].children.push(Handle(i));
+            }
+        }
+        arena
+            .root_container
+            .insert("bookmark_bar".to_string(), Handle(0));
+
+        // 3 went into 2, which later went into 1; 4 was pruned.
+        arena.absorb(Handle(3), Handle(2));
+        arena.absorb(Handle(2), Handle(1));
+        arena.delete(Handle(4));
+
+        let fates: Vec<Fate> = Provenance::from_arena(&arena)
+            .nodes
+            .into_iter()
+            .map(|e| e.fate)
+            .collect();
+        let into = |i: usize| Fate::Merged {
+            into: format!("/roots/bookmark_bar/children/{i}"),
+        };
+        assert_eq!(
+            fates,
+            vec![Fate::Kept, Fate::Kept, into(0), into(1), Fate::Removed]
+        );
+    }
+}
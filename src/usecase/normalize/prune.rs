@@ -1,62 +1,45 @@
+use crate::domain::model::NodeKind;
 use crate::usecase::event::AppEvent;
-use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::arena::Arena;
+use crate::usecase::normalize::condense::ProcessingOrder;
 use crate::usecase::stats::NormalizeStats;
 use tokio::sync::mpsc;
 
 pub async fn prune_empty_folders(
     arena: &mut Arena,
+    order: &ProcessingOrder,
     sink: &Option<mpsc::Sender<AppEvent>>,
     stats: &mut NormalizeStats,
 ) {
-    let postorder = postorder_folders(arena);
-
-    for h in postorder {
+    // Reverse topological order of the condensed identity graph: descendants first.
+    for h in order.children_first() {
         if arena.is_root_container(h) {
             continue;
         }
-        if arena.nodes[h.0].deleted || arena.nodes[h.0].node_type != "folder" {
+        let node = &arena.nodes[h.0];
+        if node.deleted || node.preserved || node.kind != NodeKind::Folder {
             continue;
         }
 
-        // Collect deleted status first to avoid borrow conflict
-        let to_keep: Vec<Handle> = arena.nodes[h.0]
+        // Descendants come first, so children pruned earlier are already tombstones here.
+        let empty = arena.nodes[h.0]
             .children
             .iter()
-            .filter(|c| !arena.nodes[c.0].deleted)
-            .copied()
-            .collect();
-        arena.nodes[h.0].children = to_keep;
-
-        let mut has_url = false;
-        let mut has_folder = false;
-        let mut has_other = false;
-
-        for c in arena.nodes[h.0].children.iter() {
-            let t = arena.nodes[c.0].node_type.as_str();
-            if t == "url" {
-                has_url = true;
-            } else if t == "folder" {
-                has_folder = true;
-            } else {
-                has_other = true;
-            }
-        }
+            .all(|c| arena.nodes[c.0].deleted);
 
-        if !has_url && !has_folder && !has_other {
-            if let Some(parent) = arena.parent[h.0] {
-                arena.nodes[parent.0].children.retain(|c| c.0 != h.0);
-            }
-            arena.nodes[h.0].deleted = true;
+        if empty {
+            arena.delete(h);
             stats.folders_pruned += 1;
             emit(
                 sink,
                 AppEvent::FolderPruned {
-                    folder_path: arena.nodes[h.0].path.clone(),
+                    folder_path: arena.name_path(h),
                 },
             )
             .await;
         }
     }
+    arena.compact_children();
 }
 
 async fn emit(sink: &Option<mpsc::Sender<AppEvent>>, ev: AppEvent) {
@@ -65,45 +48,28 @@ async fn emit(sink: &Option<mpsc::Sender<AppEvent>>, ev: AppEvent) {
     }
 }
 
-fn postorder_folders(arena: &Arena) -> Vec<Handle> {
-    let mut out: Vec<Handle> = Vec::new();
-
-    for root in arena.root_container.values().copied() {
-        let mut stack: Vec<(Handle, bool)> = vec![(root, false)];
-        while let Some((h, expanded)) = stack.pop() {
-            if arena.nodes[h.0].deleted {
-                continue;
-            }
-            if arena.nodes[h.0].node_type != "folder" {
-                continue;
-            }
-            if expanded {
-                out.push(h);
-                continue;
-            }
-            stack.push((h, true));
-            for ch in arena.nodes[h.0].children.iter().rev() {
-                stack.push((*ch, false));
-            }
-        }
-    }
-
-    out
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::usecase::normalize::arena::ArenaNode;
+    use crate::infrastructure::scc_kosaraju::KosarajuSccDetector;
+    use crate::usecase::normalize::arena::{ArenaNode, Handle};
+    use crate::usecase::normalize::condense::condense;
+
+    async fn prune(arena: &mut Arena, stats: &mut NormalizeStats) {
+        let order = condense(arena, &KosarajuSccDetector)
+            .expect("condense")
+            .order;
+        prune_empty_folders(arena, &order, &None, stats).await;
+    }
 
     #[tokio::test]
     async fn does_not_prune_root_container_even_if_empty() {
         let mut arena = Arena::default();
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             name: Some("root".to_string()),
             depth: 0,
-            path: "/".to_string(),
+            root_key: Some("bookmark_bar".into()),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
@@ -112,7 +78,7 @@ mod tests {
             .insert("bookmark_bar".to_string(), Handle(0));
 
         let mut stats = NormalizeStats::default();
-        prune_empty_folders(&mut arena, &None, &mut stats).await;
+        prune(&mut arena, &mut stats).await;
 
         assert!(!arena.nodes[0].deleted);
         assert_eq!(stats.folders_pruned, 0);
@@ -123,10 +89,10 @@ mod tests {
         let mut arena = Arena::default();
 
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             name: Some("root".to_string()),
             depth: 0,
-            path: "/".to_string(),
+            root_key: Some("bookmark_bar".into()),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
@@ -135,27 +101,25 @@ mod tests {
             .insert("bookmark_bar".to_string(), Handle(0));
 
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             name: Some("keep".to_string()),
             depth: 1,
-            path: "/keep".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
         arena.nodes[0].children.push(Handle(1));
 
         arena.nodes.push(ArenaNode {
-            node_type: "other".to_string(),
+            kind: NodeKind::Other("other".to_string()),
             name: Some("mystery".to_string()),
             depth: 2,
-            path: "/keep/mystery".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(1)));
         arena.nodes[1].children.push(Handle(2));
 
         let mut stats = NormalizeStats::default();
-        prune_empty_folders(&mut arena, &None, &mut stats).await;
+        prune(&mut arena, &mut stats).await;
 
         assert!(!arena.nodes[1].deleted);
         assert_eq!(stats.folders_pruned, 0);
@@ -166,10 +130,10 @@ mod tests {
         let mut arena = Arena::default();
 
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             name: Some("root".to_string()),
             depth: 0,
-            path: "/".to_string(),
+            root_key: Some("bookmark_bar".into()),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
@@ -178,38 +142,35 @@ mod tests {
             .insert("bookmark_bar".to_string(), Handle(0));
 
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             name: Some("keep".to_string()),
             depth: 1,
-            path: "/keep".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
         arena.nodes[0].children.push(Handle(1));
 
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             name: Some("child".to_string()),
             depth: 2,
-            path: "/keep/child".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(1)));
         arena.nodes[1].children.push(Handle(2));
 
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             name: Some("x".to_string()),
             url: Some("https://example.com".to_string()),
             depth: 3,
-            path: "/keep/child/x".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(2)));
         arena.nodes[2].children.push(Handle(3));
 
         let mut stats = NormalizeStats::default();
-        prune_empty_folders(&mut arena, &None, &mut stats).await;
+        prune(&mut arena, &mut stats).await;
 
         assert!(!arena.nodes[1].deleted);
         assert_eq!(stats.folders_pruned, 0);
@@ -1,58 +1,62 @@
+use crate::domain::model::NodeKind;
 use crate::domain::traits::UrlCanonicalizer;
 use crate::infrastructure::serde_json_adapter::{BookmarkNodeDto, BookmarksFileDto};
-use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::arena::{Arena, ArenaNode, Handle};
+
+type SortKey = (u8, String, String);
 
 pub fn rebuild_dto_from_arena(
     mut base: BookmarksFileDto,
     mut arena: Arena,
     canonicalizer: &dyn UrlCanonicalizer,
 ) -> BookmarksFileDto {
-    let mut built: Vec<Option<BookmarkNodeDto>> = vec![None; arena.nodes.len()];
-
-    for root in arena.root_container.values().copied() {
-        let order = postorder_handles(&arena, root);
-        for h in order {
-            if arena.nodes[h.0].deleted {
-                continue;
-            }
+    // Every root is rebuilt from the arena; drop the input tree now rather than at the end.
+    base.roots.clear();
 
-            let node = std::mem::take(&mut arena.nodes[h.0]);
-            let mut kids: Vec<BookmarkNodeDto> = Vec::new();
-            for ch in node.children.iter() {
-                if let Some(k) = built[ch.0].take() {
-                    kids.push(k);
-                }
-            }
+    for (root_key, &root) in arena.root_container.iter() {
+        // Post-order puts a node's live children, in order, on top of this stack just
+        // before the node itself, so no per-handle table is needed.
+        let mut done: Vec<(SortKey, BookmarkNodeDto)> = Vec::new();
+        for h in postorder_handles(&arena, root) {
+            let mut node = std::mem::take(&mut arena.nodes[h.0]);
+            let live = node
+                .children
+                .iter()
+                .filter(|c| !arena.nodes[c.0].deleted)
+                .count();
+            let mut kids = done.split_off(done.len() - live);
 
             // Deterministic child order.
-            kids.sort_by_cached_key(|a| sort_key(a, canonicalizer));
+            kids.sort_by(|a, b| a.0.cmp(&b.0));
+            let kids: Vec<BookmarkNodeDto> = kids.into_iter().map(|(_, dto)| dto).collect();
+            let key = sort_key(&mut node, canonicalizer);
 
+            // The arena node is taken, so its fields move into the DTO without copies.
             let dto = BookmarkNodeDto {
-                node_type: node.node_type.clone(),
-                name: node.name.clone(),
-                url: node.url.clone(),
+                node_type: node.kind.to_string(),
+                name: node.name,
+                url: node.url,
                 children: kids,
-                date_added: node.date_added.clone(),
-                date_modified: node.date_modified.clone(),
-                date_last_used: node.date_last_used.clone(),
+                date_added: node.date_added,
+                date_modified: node.date_modified,
+                date_last_used: node.date_last_used,
                 visit_count: node.visit_count,
-                guid: node.guid.clone(),
-                id: node.id.clone(),
-                source: node.source.clone(),
+                guid: node.guid,
+                id: node.id,
+                source: node.source,
                 show_icon: node.show_icon,
                 // Preserve unknown extra fields from input nodes, but we will strip
                 // internal merge metadata (e.g. `x_merge_meta`) from the final
                 // output to remain Microsoft Edge compatible.
-                extra: node.extra.clone(),
+                extra: node.extra,
+                raw_type: node.raw_type,
             };
 
             // Removed write_merge_meta to preserve original JSON structure
-            built[h.0] = Some(dto);
+            done.push((key, dto));
         }
-    }
 
-    for (root_key, root_handle) in arena.root_container.iter() {
-        if let Some(root_node) = built[root_handle.0].take() {
+        if let Some((_, root_node)) = done.pop() {
             base.roots.insert(root_key.clone(), root_node);
         }
     }
@@ -84,16 +88,19 @@ fn postorder_handles(arena: &Arena, root: Handle) -> Vec<Handle> {
     out
 }
 
-fn sort_key(n: &BookmarkNodeDto, canonicalizer: &dyn UrlCanonicalizer) -> (u8, String, String) {
-    match n.node_type.as_str() {
-        "folder" => (
+/// Takes the node's cached canonical URL; it is not part of the output.
+fn sort_key(n: &mut ArenaNode, canonicalizer: &dyn UrlCanonicalizer) -> SortKey {
+    match &n.kind {
+        NodeKind::Folder => (
             0,
-            n.name.clone().unwrap_or_default().trim().to_lowercase(),
+            n.name.as_deref().unwrap_or_default().trim().to_lowercase(),
             n.id.clone().unwrap_or_default(),
         ),
-        "url" => (
+        NodeKind::Url => (
             1,
-            canonicalizer.canonicalize(n.url.as_deref().unwrap_or_default()),
+            n.canonical_url.take().unwrap_or_else(|| {
+                canonicalizer.canonicalize(n.url.as_deref().unwrap_or_default())
+            }),
             n.id.clone().unwrap_or_default(),
         ),
         other => (2, other.to_string(), n.name.clone().unwrap_or_default()),
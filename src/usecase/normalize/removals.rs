@@ -0,0 +1,315 @@
+use crate::domain::model::NodeKind;
+use crate::usecase::normalize::arena::Arena;
+use crate::usecase::normalize::folder_merge::folder_key;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Groups listed in a [`RemovalSummary`], largest first.
+const TOP_GROUPS: usize = 10;
+
+/// Most removals a run may make of one node type: a count, a percentage of the input, or
+/// both (either one trips it). Parsed from `250`, `5%` or `5%,250`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RemovalLimit {
+    pub count: Option<usize>,
+    pub percent: Option<f64>,
+}
+
+impl RemovalLimit {
+    fn exceeded_by(&self, removed: usize, total: usize) -> bool {
+        self.count.is_some_and(|max| removed > max)
+            || self
+                .percent
+                .is_some_and(|max| removed as f64 * 100.0 > max * total as f64)
+    }
+}
+
+impl fmt::Display for RemovalLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.percent, self.count) {
+            (Some(p), Some(c)) => write!(f, "{p}%,{c}"),
+            (Some(p), None) => write!(f, "{p}%"),
+            (None, Some(c)) => write!(f, "{c}"),
+            (None, None) => f.write_str("none"),
+        }
+    }
+}
+
+impl FromStr for RemovalLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut limit = RemovalLimit::default();
+        for part in s.split(',').map(str::trim) {
+            if let Some(p) = part.strip_suffix('%') {
+                let p: f64 = p
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("invalid removal percentage: {part}"))?;
+                if !(0.0..=100.0).contains(&p) || limit.percent.is_some() {
+                    return Err(anyhow!(
+                        "invalid removal percentage: {part} (expected one value in 0-100%)"
+                    ));
+                }
+                limit.percent = Some(p);
+            } else {
+                let c: usize = part
+                    .parse()
+                    .map_err(|_| anyhow!("invalid removal limit: {part} (expected a count like 250 or a percentage like 5%)"))?;
+                if limit.count.is_some() {
+                    return Err(anyhow!("invalid removal limit: {s} (more than one count)"));
+                }
+                limit.count = Some(c);
+            }
+        }
+        Ok(limit)
+    }
+}
+
+/// Guardrails on how much one run may delete. Unset limits never trip.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RemovalLimits {
+    pub urls: Option<RemovalLimit>,
+    pub folders: Option<RemovalLimit>,
+    /// Apply the run anyway when a limit is exceeded.
+    pub force: bool,
+}
+
+/// Removed nodes sharing a canonical URL or folder merge key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RemovalGroup {
+    /// `url` or `folder`.
+    pub kind: String,
+    pub key: String,
+    pub removed: usize,
+}
+
+/// What a run removes relative to its input; root containers are never counted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RemovalSummary {
+    pub urls_in: usize,
+    pub urls_removed: usize,
+    pub folders_in: usize,
+    pub folders_removed: usize,
+    /// The groups that lost the most nodes, largest first.
+    pub top_groups: Vec<RemovalGroup>,
+}
+
+impl RemovalSummary {
+    /// Every input URL or folder no longer reachable from a root container is removed,
+    /// whichever phase (identity merge, folder merge, dedup, prune) dropped it.
+    pub fn from_arena(arena: &Arena) -> Self {
+        let live = arena.reachable();
+
+        let mut summary = RemovalSummary::default();
+        let mut groups: HashMap<(&'static str, String), usize> = HashMap::new();
+        for (i, node) in arena.nodes.iter().enumerate() {
+            if node.depth == 0 {
+                continue;
+            }
+            let (kind, key) = match node.kind {
+                NodeKind::Url => {
+                    summary.urls_in += 1;
+                    if live[i] {
+                        continue;
+                    }
+                    summary.urls_removed += 1;
+                    let key = node
+                        .canonical_url
+                        .clone()
+                        .or_else(|| node.url.clone())
+                        .unwrap_or_default();
+                    ("url", key)
+                }
+                NodeKind::Folder => {
+                    summary.folders_in += 1;
+                    if live[i] {
+                        continue;
+                    }
+                    summary.folders_removed += 1;
+                    (
+                        "folder",
+                        folder_key(node.name.as_deref().unwrap_or_default()),
+                    )
+                }
+                NodeKind::Other(_) => continue,
+            };
+            *groups.entry((kind, key)).or_default() += 1;
+        }
+
+        let mut top: Vec<RemovalGroup> = groups
+            .into_iter()
+            .map(|((kind, key), removed)| RemovalGroup {
+                kind: kind.to_string(),
+                key,
+                removed,
+            })
+            .collect();
+        top.sort_by(|a, b| {
+            b.removed
+                .cmp(&a.removed)
+                .then_with(|| a.kind.cmp(&b.kind))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        top.truncate(TOP_GROUPS);
+        summary.top_groups = top;
+        summary
+    }
+
+    /// Limits this run goes over.
+    pub fn exceeded(&self, limits: &RemovalLimits) -> Vec<ExceededLimit> {
+        let checks = [
+            ("URLs", limits.urls, self.urls_removed, self.urls_in),
+            (
+                "folders",
+                limits.folders,
+                self.folders_removed,
+                self.folders_in,
+            ),
+        ];
+        checks
+            .into_iter()
+            .filter_map(|(kind, limit, removed, total)| {
+                let limit = limit?;
+                limit.exceeded_by(removed, total).then_some(ExceededLimit {
+                    kind,
+                    removed,
+                    total,
+                    limit,
+                })
+            })
+            .collect()
+    }
+}
+
+/// One [`RemovalLimit`] a run went over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExceededLimit {
+    /// `URLs` or `folders`.
+    pub kind: &'static str,
+    pub removed: usize,
+    pub total: usize,
+    pub limit: RemovalLimit,
+}
+
+impl fmt::Display for ExceededLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} {} (limit {})",
+            self.removed, self.total, self.kind, self.limit
+        )
+    }
+}
+
+/// A run stopped because it would remove more than [`RemovalLimits`] allow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovalLimitError {
+    pub summary: RemovalSummary,
+    pub exceeded: Vec<ExceededLimit>,
+}
+
+impl fmt::Display for RemovalLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let over: Vec<String> = self.exceeded.iter().map(ToString::to_string).collect();
+        write!(f, "run would remove {}", over.join(" and "))?;
+        let groups: Vec<String> = self
+            .summary
+            .top_groups
+            .iter()
+            .map(|g| format!("{} {:?} -{}", g.kind, g.key, g.removed))
+            .collect();
+        if !groups.is_empty() {
+            write!(f, "; top groups: {}", groups.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RemovalLimitError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::normalize::arena::{ArenaNode, Handle};
+
+    #[test]
+    fn parses_counts_percentages_and_both() {
+        assert_eq!(
+            "250".parse::<RemovalLimit>().unwrap(),
+            RemovalLimit {
+                count: Some(250),
+                percent: None
+            }
+        );
+        let both: RemovalLimit = "5%, 250".parse().unwrap();
+        assert_eq!(both.to_string(), "5%,250");
+        assert!("150%".parse::<RemovalLimit>().is_err());
+        assert!("lots".parse::<RemovalLimit>().is_err());
+    }
+
+    #[test]
+    fn counts_unreachable_nodes_and_groups_them() {
+        let mut arena = Arena::default();
+        let push = |arena: &mut Arena, node: ArenaNode, parent: Option<usize>| {
+            let h = arena.nodes.len();
+            arena.nodes.push(node);
+            arena.parent.push(parent.map(Handle));
+            if let Some(p) = parent {
+                arena.nodes[p].children.push(Handle(h));
+            }
+        };
+        push(
+            &mut arena,
+            ArenaNode {
+                depth: 0,
+                ..ArenaNode::default()
+            },
+            None,
+        );
+        arena
+            .root_container
+            .insert("bookmark_bar".to_string(), Handle(0));
+        for i in 0..3 {
+            let url = ArenaNode {
+                kind: NodeKind::Url,
+                url: Some(format!("https://example.com/#{i}")),
+                canonical_url: Some("https://example.com/".to_string()),
+                depth: 1,
+                deleted: i > 0,
+                ..ArenaNode::default()
+            };
+            push(&mut arena, url, Some(0));
+        }
+        let folder = ArenaNode {
+            name: Some(" Work ".to_string()),
+            depth: 1,
+            deleted: true,
+            ..ArenaNode::default()
+        };
+        push(&mut arena, folder, Some(0));
+
+        let summary = RemovalSummary::from_arena(&arena);
+        assert_eq!((summary.urls_in, summary.urls_removed), (3, 2));
+        assert_eq!((summary.folders_in, summary.folders_removed), (1, 1));
+        assert_eq!(summary.top_groups[0].key, "https://example.com/");
+        assert_eq!(summary.top_groups[0].removed, 2);
+        assert_eq!(summary.top_groups[1].key, "work");
+
+        let limits = RemovalLimits {
+            urls: Some("50%".parse().unwrap()),
+            folders: Some("1".parse().unwrap()),
+            force: false,
+        };
+        let exceeded = summary.exceeded(&limits);
+        assert_eq!(exceeded.len(), 1);
+        let err = RemovalLimitError { summary, exceeded };
+        assert_eq!(
+            err.to_string(),
+            "run would remove 2 of 3 URLs (limit 50%); top groups: url \"https://example.com/\" -2, folder \"work\" -1"
+        );
+    }
+}
@@ -0,0 +1,525 @@
+//! Streaming load: a Bookmarks file is deserialized straight into an [`Arena`], one node at
+//! a time, without holding the document as a `serde_json::Value` or a DTO tree.
+
+use crate::domain::model::NodeKind;
+use crate::infrastructure::read_options::{ReadOptions, SchemaMode};
+use crate::infrastructure::schema_validator::{
+    item_violations, schema_violations_with, SchemaValidationFailed, SchemaViolation,
+};
+use crate::infrastructure::serde_json_adapter::{
+    explain_parse_error, misfit_fields, misfit_violation, node_as_read, BookmarkNodeDto,
+    BookmarksFileDto,
+};
+use crate::usecase::normalize::arena::{Arena, ArenaNode, Handle};
+use crate::usecase::normalize::build::{allocation_order, arena_node};
+use anyhow::Result;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::Deserializer;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::io::Read;
+use std::sync::Arc;
+
+/// A Bookmarks file loaded by [`load_bookmarks`], ready for
+/// [`normalize_loaded_with_report`](super::normalize_loaded_with_report).
+pub struct LoadedBookmarks {
+    /// Top-level fields (`checksum`, `version`, unknown keys); `roots` is left empty.
+    pub(crate) base: BookmarksFileDto,
+    pub(crate) arena: Arena,
+    pub(crate) schema_violations: Vec<SchemaViolation>,
+}
+
+impl LoadedBookmarks {
+    pub fn node_count(&self) -> usize {
+        self.arena.nodes.len()
+    }
+
+    /// Violations tolerated under [`SchemaMode::Lenient`], in document order.
+    pub fn schema_violations(&self) -> &[SchemaViolation] {
+        &self.schema_violations
+    }
+}
+
+/// Reads a Bookmarks file from `reader` (wrap files in a `BufReader`).
+///
+/// Each node is checked against its folder or URL schema as soon as its fields are read;
+/// like [`validate_all_bookmark_items`](crate::infrastructure::schema_validator::validate_all_bookmark_items),
+/// only children of folders are checked. Node count, depth and string limits are checked as
+/// nodes arrive, before a too-deep subtree is descended into. The resulting arena is laid
+/// out exactly as `build_arena_from_dto` lays out the same file, so both paths normalize
+/// identically. Edge writes `version` after `roots`, so each node is checked against every
+/// schema set in `options.schemas` and the file's version picks whose failures count.
+/// Every failure is collected; [`SchemaMode::Strict`] then fails with
+/// [`SchemaValidationFailed`], [`SchemaMode::Lenient`] keeps them next to the arena, and
+/// [`SchemaMode::Off`] skips the schema checks.
+pub fn load_bookmarks<R: Read>(reader: R, options: &ReadOptions) -> Result<LoadedBookmarks> {
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let mut load = Loader {
+        arena: Arena::default(),
+        limits: options.clone(),
+        failed: Vec::new(),
+        misfits: Vec::new(),
+    };
+    let fields = FileSeed { load: &mut load }
+        .deserialize(&mut de)
+        .map_err(explain_parse_error)?;
+    de.end()?;
+    let Loader {
+        mut arena,
+        failed,
+        mut misfits,
+        ..
+    } = load;
+
+    let top = Value::Object(fields);
+    let set = options
+        .schemas
+        .index_for_version(top.get("version").and_then(Value::as_i64));
+    let mut violations = match options.schema {
+        // `roots` is an empty placeholder here, so only the file schema applies.
+        SchemaMode::Strict | SchemaMode::Lenient => {
+            schema_violations_with(&options.schemas.sets()[set], &top)
+        }
+        SchemaMode::Off => Vec::new(),
+    };
+
+    let (mut handles, relative): (Vec<Handle>, Vec<Vec<SchemaViolation>>) = failed
+        .into_iter()
+        .filter(|f| f.set == set)
+        .map(|f| (f.handle, f.violations))
+        .unzip();
+    // Slots are taken as nodes open, so read order is document order.
+    misfits.sort_by_key(|(h, _)| *h);
+    let failing = handles.len();
+    handles.extend(misfits.iter().map(|(h, _)| *h));
+    into_allocation_order(&mut arena, &mut handles);
+    let misfit_handles = handles.split_off(failing);
+    for (h, node_violations) in handles.into_iter().zip(relative) {
+        let node = arena.json_pointer(h);
+        let name_path = arena.origin_name_path(h);
+        violations.extend(node_violations.into_iter().map(|v| SchemaViolation {
+            pointer: format!("{node}{}", v.pointer),
+            node: Some(node.clone()),
+            name_path: Some(name_path.clone()),
+            ..v
+        }));
+    }
+    for (h, (_, fields)) in misfit_handles.into_iter().zip(misfits) {
+        violations.push(misfit_violation(
+            arena.json_pointer(h),
+            arena.origin_name_path(h),
+            &fields,
+        ));
+    }
+    if options.schema == SchemaMode::Strict && !violations.is_empty() {
+        return Err(SchemaValidationFailed { violations }.into());
+    }
+    let base: BookmarksFileDto = serde_json::from_value(top)?;
+
+    Ok(LoadedBookmarks {
+        base,
+        arena,
+        schema_violations: violations,
+    })
+}
+
+/// Renumbers handles into builder order, permuting nodes in place; `also` is renumbered
+/// with them.
+fn into_allocation_order(arena: &mut Arena, also: &mut [Handle]) {
+    let order = allocation_order(arena);
+    debug_assert_eq!(order.len(), arena.nodes.len());
+
+    let mut dest = vec![0usize; order.len()];
+    for (new, old) in order.iter().enumerate() {
+        dest[old.0] = new;
+    }
+
+    for node in arena.nodes.iter_mut() {
+        for ch in node.children.iter_mut() {
+            *ch = Handle(dest[ch.0]);
+        }
+        node.origin_parent = node.origin_parent.map(|p| Handle(dest[p.0]));
+    }
+    for p in arena.parent.iter_mut() {
+        *p = p.map(|p| Handle(dest[p.0]));
+    }
+    for h in arena.root_container.values_mut().chain(also.iter_mut()) {
+        *h = Handle(dest[h.0]);
+    }
+
+    // Every swap puts one node in its final slot.
+    for i in 0..dest.len() {
+        while dest[i] != i {
+            let j = dest[i];
+            arena.nodes.swap(i, j);
+            arena.parent.swap(i, j);
+            dest.swap(i, j);
+        }
+    }
+}
+
+/// State shared by the seeds while a file streams in.
+struct Loader {
+    arena: Arena,
+    limits: ReadOptions,
+    /// Failing schema items, with pointers relative to the node.
+    failed: Vec<NodeFailure>,
+    /// Nodes kept as read under [`SchemaMode::Lenient`], with the fields that did not fit.
+    misfits: Vec<(Handle, Vec<&'static str>)>,
+}
+
+/// The top-level object: `roots` goes to the arena, every other field is kept as JSON.
+struct FileSeed<'a> {
+    load: &'a mut Loader,
+}
+
+impl<'de> DeserializeSeed<'de> for FileSeed<'_> {
+    type Value = Map<String, Value>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FileSeed<'_> {
+    type Value = Map<String, Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Bookmarks file object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "roots" {
+                if fields.contains_key("roots") {
+                    return Err(de::Error::duplicate_field("roots"));
+                }
+                map.next_value_seed(RootsSeed {
+                    load: &mut *self.load,
+                })?;
+                // Checked by the file schema in place of the roots themselves.
+                fields.insert(key, Value::Object(Map::new()));
+            } else {
+                fields.insert(key, map.next_value()?);
+            }
+        }
+        Ok(fields)
+    }
+}
+
+struct RootsSeed<'a> {
+    load: &'a mut Loader,
+}
+
+impl<'de> DeserializeSeed<'de> for RootsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for RootsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of root containers")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if self.load.arena.root_container.contains_key(&key) {
+                return Err(de::Error::custom(format!(
+                    "duplicate root container: {key}"
+                )));
+            }
+            let loaded = map.next_value_seed(NodeSeed {
+                load: &mut *self.load,
+                root_key: Arc::from(key.as_str()),
+                parent: None,
+                index: 0,
+                depth: 0,
+            })?;
+            // Root containers are always checked.
+            self.load.failed.extend(loaded.schema.failed);
+            self.load.arena.root_container.insert(key, loaded.handle);
+        }
+        Ok(())
+    }
+}
+
+struct LoadedNode {
+    handle: Handle,
+    /// Schema failures in this subtree; they count only if the parent is a folder.
+    schema: SchemaFailures,
+}
+
+#[derive(Default)]
+struct SchemaFailures {
+    /// Every failing node in document order.
+    failed: Vec<NodeFailure>,
+}
+
+/// A node's schema errors under one of the candidate sets, by index into
+/// [`Schemas::sets`](crate::infrastructure::schema_catalog::Schemas::sets).
+struct NodeFailure {
+    handle: Handle,
+    set: usize,
+    violations: Vec<SchemaViolation>,
+}
+
+impl SchemaFailures {
+    fn append(&mut self, other: SchemaFailures) {
+        self.failed.extend(other.failed);
+    }
+}
+
+struct NodeSeed<'a> {
+    load: &'a mut Loader,
+    root_key: Arc<str>,
+    parent: Option<Handle>,
+    index: usize,
+    depth: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for NodeSeed<'_> {
+    type Value = LoadedNode;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<LoadedNode, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NodeSeed<'_> {
+    type Value = LoadedNode;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a bookmark node object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<LoadedNode, A::Error> {
+        // The slot is taken first so children can point at it; Edge writes `children`
+        // before the node's own fields.
+        let handle = Handle(self.load.arena.nodes.len());
+        let limits = self.load.limits.clone();
+        limits
+            .check_node_count(handle.0 + 1)
+            .and_then(|()| limits.check_depth(self.depth))
+            .map_err(de::Error::custom)?;
+        self.load.arena.nodes.push(ArenaNode::default());
+        self.load.arena.parent.push(self.parent);
+
+        let mut fields = Map::new();
+        let mut children: Option<LoadedChildren> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "children" {
+                if children.is_some() {
+                    return Err(de::Error::duplicate_field("children"));
+                }
+                children = Some(map.next_value_seed(ChildrenSeed {
+                    load: &mut *self.load,
+                    root_key: self.root_key.clone(),
+                    parent: handle,
+                    depth: self.depth + 1,
+                })?);
+            } else {
+                fields.insert(key, map.next_value()?);
+            }
+        }
+        let children = children.unwrap_or_default();
+        let misfits = if limits.schema == SchemaMode::Lenient {
+            misfit_fields(&fields)
+        } else {
+            Vec::new()
+        };
+
+        let value = Value::Object(fields);
+        limits
+            .check_strings(
+                value.get("name").and_then(Value::as_str),
+                value.get("url").and_then(Value::as_str),
+            )
+            .map_err(de::Error::custom)?;
+        let node_type = value.get("type").and_then(Value::as_str);
+        let mut schema = SchemaFailures::default();
+        if limits.schema != SchemaMode::Off {
+            for (set, schemas) in limits.schemas.sets().iter().enumerate() {
+                // Pointer and name path are filled in once the node's location is known.
+                let violations = item_violations(schemas, &value, "", "");
+                if !violations.is_empty() {
+                    schema.failed.push(NodeFailure {
+                        handle,
+                        set,
+                        violations,
+                    });
+                }
+            }
+        }
+        let failed_everywhere = schema.failed.len() == limits.schemas.sets().len();
+        if node_type == Some("folder") {
+            schema.append(children.schema);
+        }
+
+        // A node that fails its own schema may not fit the model either; strict loads fail
+        // anyway, so it is kept by name only to report the rest with their name paths.
+        let placeholder =
+            (limits.schema == SchemaMode::Strict && failed_everywhere).then(|| BookmarkNodeDto {
+                name: value
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                ..BookmarkNodeDto::default()
+            });
+        let dto: BookmarkNodeDto = match value {
+            Value::Object(fields) if !misfits.is_empty() => {
+                let dto = node_as_read(fields, &misfits).map_err(de::Error::custom)?;
+                self.load.misfits.push((handle, misfits));
+                dto
+            }
+            value => match serde_json::from_value(value) {
+                Ok(dto) => dto,
+                Err(e) => placeholder.ok_or_else(|| de::Error::custom(e))?,
+            },
+        };
+        let mut node = arena_node(&dto, self.root_key, self.parent, self.index, self.depth);
+        node.children = children.handles;
+        if self.depth == 0 {
+            node.kind = NodeKind::Folder;
+        }
+        self.load.arena.nodes[handle.0] = node;
+
+        Ok(LoadedNode { handle, schema })
+    }
+}
+
+#[derive(Default)]
+struct LoadedChildren {
+    handles: Vec<Handle>,
+    schema: SchemaFailures,
+}
+
+struct ChildrenSeed<'a> {
+    load: &'a mut Loader,
+    root_key: Arc<str>,
+    parent: Handle,
+    depth: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for ChildrenSeed<'_> {
+    type Value = LoadedChildren;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<LoadedChildren, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ChildrenSeed<'_> {
+    type Value = LoadedChildren;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of bookmark nodes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<LoadedChildren, A::Error> {
+        let mut out = LoadedChildren::default();
+        while let Some(child) = seq.next_element_seed(NodeSeed {
+            load: &mut *self.load,
+            root_key: self.root_key.clone(),
+            parent: Some(self.parent),
+            index: out.handles.len(),
+            depth: self.depth,
+        })? {
+            out.handles.push(child.handle);
+            out.schema.append(child.schema);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::normalize::build::build_arena_from_dto;
+    use crate::usecase::stats::NormalizeStats;
+
+    const FILE: &str = r#"{
+        "checksum": "abc",
+        "roots": {
+            "other": {"children": [], "name": "Other", "type": "folder"},
+            "bookmark_bar": {
+                "children": [
+                    {"children": [
+                        {"name": "x", "type": "url", "url": "https://x.example/"},
+                        {"children": [{"name": "y", "type": "url", "url": "https://y.example/"}],
+                         "name": "Inner", "type": "folder"}
+                    ], "name": "A", "type": "folder", "x_custom": 1},
+                    {"name": "z", "type": "url", "url": "https://z.example/"}
+                ],
+                "name": "Bar", "type": "folder"
+            }
+        },
+        "version": 1,
+        "sync_metadata": "keep"
+    }"#;
+
+    #[test]
+    fn arena_matches_the_dto_builder_layout() {
+        let loaded = load_bookmarks(FILE.as_bytes(), &ReadOptions::default()).expect("load");
+        let dto: BookmarksFileDto = serde_json::from_str(FILE).expect("dto");
+        let built = build_arena_from_dto(&dto, &mut NormalizeStats::default());
+
+        assert_eq!(loaded.arena.nodes.len(), built.nodes.len());
+        assert_eq!(loaded.arena.parent, built.parent);
+        assert_eq!(loaded.arena.root_container, built.root_container);
+        for (a, b) in loaded.arena.nodes.iter().zip(built.nodes.iter()) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.kind, b.kind);
+            assert_eq!(a.children, b.children);
+            assert_eq!(a.origin_parent, b.origin_parent);
+            assert_eq!(a.origin_index, b.origin_index);
+            assert_eq!(a.depth, b.depth);
+            assert_eq!(a.extra, b.extra);
+        }
+
+        assert!(loaded.base.roots.is_empty());
+        assert_eq!(loaded.base.checksum.as_deref(), Some("abc"));
+        assert_eq!(loaded.base.extra["sync_metadata"], "keep");
+    }
+
+    #[test]
+    fn schema_errors_are_raised_only_below_folders() {
+        let bad_url = r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+            {"type": "folder", "name": "A", "children": [
+                {"type": "url", "name": "x", "url": "https://x.example/", "visit_count": -1}
+            ]}
+        ]}}}"#;
+        let err = load_bookmarks(bad_url.as_bytes(), &ReadOptions::default())
+            .err()
+            .expect("schema error");
+        let failed = err
+            .downcast_ref::<SchemaValidationFailed>()
+            .expect("schema failure");
+        assert_eq!(failed.violations.len(), 1);
+        assert_eq!(
+            failed.violations[0].pointer,
+            "/roots/bookmark_bar/children/0/children/0/visit_count"
+        );
+        assert_eq!(
+            failed.violations[0].name_path.as_deref(),
+            Some("/bookmark_bar/A/x")
+        );
+
+        // Children of a URL node are not schema items, as in the in-memory validator.
+        let under_url = r#"{"roots": {"bookmark_bar": {"type": "folder", "children": [
+            {"type": "url", "name": "x", "url": "https://x.example/", "children": [
+                {"type": "url", "name": "y", "visit_count": -1}
+            ]}
+        ]}}}"#;
+        load_bookmarks(under_url.as_bytes(), &ReadOptions::default())
+            .expect("not checked below a url");
+    }
+}
@@ -1,6 +1,9 @@
+use crate::domain::model::NodeKind;
 use crate::domain::traits::UrlCanonicalizer;
 use crate::usecase::event::AppEvent;
-use crate::usecase::normalize::arena::{Arena, Handle};
+use crate::usecase::normalize::arena::{Arena, ArenaNode, Handle};
+use crate::usecase::normalize::url_merge::UrlMergePolicy;
+use crate::usecase::normalize::url_winner::{id_key, UrlWinnerPolicy};
 use crate::usecase::stats::NormalizeStats;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -9,6 +12,8 @@ use tokio::sync::mpsc;
 pub async fn per_folder_url_dedup(
     arena: &mut Arena,
     canonicalizer: &dyn UrlCanonicalizer,
+    policy: &UrlWinnerPolicy,
+    merge: &UrlMergePolicy,
     sink: &Option<mpsc::Sender<AppEvent>>,
     stats: &mut NormalizeStats,
 ) {
@@ -16,7 +21,7 @@ pub async fn per_folder_url_dedup(
         if arena.nodes[folder_h].deleted {
             continue;
         }
-        if arena.nodes[folder_h].node_type != "folder" {
+        if arena.nodes[folder_h].kind != NodeKind::Folder {
             continue;
         }
 
@@ -26,23 +31,29 @@ pub async fn per_folder_url_dedup(
         // Snapshot children list to avoid borrow fights.
         let children = arena.nodes[folder_h].children.clone();
         for ch in children {
-            if arena.nodes[ch.0].deleted {
+            if arena.nodes[ch.0].deleted || arena.nodes[ch.0].preserved {
                 continue;
             }
-            if arena.nodes[ch.0].node_type != "url" {
+            if arena.nodes[ch.0].kind != NodeKind::Url {
                 continue;
             }
-            let Some(url) = arena.nodes[ch.0].url.clone() else {
+            let node = &arena.nodes[ch.0];
+            let Some(url) = node.url.as_deref() else {
                 continue;
             };
 
-            let canon = canonicalizer.canonicalize(&url);
+            // Precomputed by the pipeline; nodes built elsewhere fall back to canonicalizing here.
+            let canon = match &node.canonical_url {
+                Some(key) => key.clone(),
+                None => canonicalizer.canonicalize(url),
+            };
             match best.get(&canon).copied() {
                 None => {
                     best.insert(canon, ch);
                 }
                 Some(existing) => {
-                    let winner = pick_url_winner(&arena.nodes[existing.0], &arena.nodes[ch.0]);
+                    let winner =
+                        pick_url_winner(policy, &arena.nodes[existing.0], &arena.nodes[ch.0]);
                     match winner {
                         UrlWinner::KeepExisting => {
                             removed_by_url.entry(canon).or_default().push(ch);
@@ -67,7 +78,7 @@ pub async fn per_folder_url_dedup(
         canon_keys.sort();
 
         for canon in canon_keys {
-            let Some(&_winner) = best.get(&canon) else {
+            let Some(&winner) = best.get(&canon) else {
                 continue;
             };
             let Some(removed) = removed_by_url.get(&canon) else {
@@ -80,30 +91,23 @@ pub async fn per_folder_url_dedup(
                 (
                     id_key(n.id.as_deref()),
                     n.guid.clone().unwrap_or_default(),
-                    n.path.clone(),
+                    arena.origin_path(*h),
                 )
             });
 
+            merge.apply(arena, winner, &removed_sorted);
+
+            // Tombstones; the folder's child list is compacted once after the pass.
             for rm in removed_sorted.iter() {
-                // Clone node data first to avoid borrow conflict
-                arena.nodes[rm.0].deleted = true;
+                arena.absorb(*rm, winner);
             }
 
-            // Collect non-deleted children to avoid borrow conflict
-            let to_keep: Vec<Handle> = arena.nodes[folder_h]
-                .children
-                .iter()
-                .filter(|h| !arena.nodes[h.0].deleted)
-                .copied()
-                .collect();
-            arena.nodes[folder_h].children = to_keep;
-
             stats.urls_deduped += removed.len();
 
             emit(
                 sink,
                 AppEvent::UrlDeduped {
-                    folder_path: arena.nodes[folder_h].path.clone(),
+                    folder_path: arena.name_path(Handle(folder_h)),
                     canonical_url: canon,
                     removed: removed_sorted.len(),
                 },
@@ -111,6 +115,7 @@ pub async fn per_folder_url_dedup(
             .await;
         }
     }
+    arena.compact_children();
 }
 
 async fn emit(sink: &Option<mpsc::Sender<AppEvent>>, ev: AppEvent) {
@@ -124,85 +129,23 @@ enum UrlWinner {
     KeepNew,
 }
 
-fn pick_url_winner(
-    existing: &crate::usecase::normalize::arena::ArenaNode,
-    new: &crate::usecase::normalize::arena::ArenaNode,
-) -> UrlWinner {
-    // Winner selection:
-    // 1) highest visit_count
-    // 2) latest date_last_used
-    // 3) earliest date_added
-    // 4) smallest id
-    let ex = url_rank(existing);
-    let nw = url_rank(new);
-    match nw.cmp(&ex) {
-        Ordering::Greater => UrlWinner::KeepNew,
+fn pick_url_winner(policy: &UrlWinnerPolicy, existing: &ArenaNode, new: &ArenaNode) -> UrlWinner {
+    // The existing winner is kept on ties, so input order breaks exact ties.
+    match policy.compare(new, existing) {
+        Ordering::Less => UrlWinner::KeepNew,
         _ => UrlWinner::KeepExisting,
     }
 }
 
-fn url_rank(
-    n: &crate::usecase::normalize::arena::ArenaNode,
-) -> (i64, u64, std::cmp::Reverse<u64>, std::cmp::Reverse<IdKey>) {
-    let visit = n.visit_count.unwrap_or(0);
-    let last_used = parse_edge_time(n.date_last_used.as_deref());
-    let date_added = parse_edge_time(n.date_added.as_deref());
-    (
-        visit,
-        last_used,
-        std::cmp::Reverse(date_added),
-        std::cmp::Reverse(id_key(n.id.as_deref())),
-    )
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-enum IdKey {
-    Parsed(u64),
-    Text(String),
-    Missing,
-}
-
-fn id_key(id: Option<&str>) -> IdKey {
-    let Some(id) = id else {
-        return IdKey::Missing;
-    };
-    if let Ok(v) = id.parse::<u64>() {
-        return IdKey::Parsed(v);
-    }
-    IdKey::Text(id.to_string())
-}
-
-fn parse_edge_time(s: Option<&str>) -> u64 {
-    match s {
-        Some(v) => v.parse::<u64>().unwrap_or(0),
-        None => 0,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
-    use crate::usecase::normalize::arena::ArenaNode;
-
-    #[test]
-    fn id_key_covers_missing_parsed_and_text() {
-        assert_eq!(id_key(None), IdKey::Missing);
-        assert_eq!(id_key(Some("7")), IdKey::Parsed(7));
-        assert_eq!(id_key(Some("abc")), IdKey::Text("abc".to_string()));
-    }
-
-    #[test]
-    fn parse_edge_time_handles_none_and_invalid() {
-        assert_eq!(parse_edge_time(None), 0);
-        assert_eq!(parse_edge_time(Some("nope")), 0);
-        assert_eq!(parse_edge_time(Some("9")), 9);
-    }
 
     #[test]
     fn pick_url_winner_can_keep_new() {
         let existing = ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             url: Some("https://example.com".to_string()),
             visit_count: Some(1),
             date_last_used: Some("1".to_string()),
@@ -211,7 +154,7 @@ mod tests {
             ..ArenaNode::default()
         };
         let new = ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             url: Some("https://example.com".to_string()),
             visit_count: Some(10),
             date_last_used: Some("2".to_string()),
@@ -221,7 +164,7 @@ mod tests {
         };
 
         assert!(matches!(
-            pick_url_winner(&existing, &new),
+            pick_url_winner(&UrlWinnerPolicy::default(), &existing, &new),
             UrlWinner::KeepNew
         ));
     }
@@ -230,9 +173,9 @@ mod tests {
     async fn per_folder_url_dedup_skips_nodes_without_url() {
         let mut arena = Arena::default();
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             depth: 0,
-            path: "/".to_string(),
+            root_key: Some("bookmark_bar".into()),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
@@ -241,10 +184,9 @@ mod tests {
             .insert("bookmark_bar".to_string(), Handle(0));
 
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             url: None,
             depth: 1,
-            path: "/x".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
@@ -252,7 +194,15 @@ mod tests {
 
         let canonicalizer = DefaultUrlCanonicalizer;
         let mut stats = NormalizeStats::default();
-        per_folder_url_dedup(&mut arena, &canonicalizer, &None, &mut stats).await;
+        per_folder_url_dedup(
+            &mut arena,
+            &canonicalizer,
+            &UrlWinnerPolicy::default(),
+            &UrlMergePolicy::default(),
+            &None,
+            &mut stats,
+        )
+        .await;
 
         assert_eq!(stats.urls_deduped, 0);
         assert!(!arena.nodes[1].deleted);
@@ -263,26 +213,33 @@ mod tests {
         let mut arena = Arena::default();
 
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             url: Some("https://example.com".to_string()),
             depth: 0,
-            path: "/x".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
 
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             deleted: true,
             depth: 0,
-            path: "/".to_string(),
+            root_key: Some("bookmark_bar".into()),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
 
         let canonicalizer = DefaultUrlCanonicalizer;
         let mut stats = NormalizeStats::default();
-        per_folder_url_dedup(&mut arena, &canonicalizer, &None, &mut stats).await;
+        per_folder_url_dedup(
+            &mut arena,
+            &canonicalizer,
+            &UrlWinnerPolicy::default(),
+            &UrlMergePolicy::default(),
+            &None,
+            &mut stats,
+        )
+        .await;
 
         assert_eq!(stats.urls_deduped, 0);
     }
@@ -291,9 +248,9 @@ mod tests {
     async fn per_folder_url_dedup_emits_event_and_keeps_existing_winner() {
         let mut arena = Arena::default();
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             depth: 0,
-            path: "/".to_string(),
+            root_key: Some("bookmark_bar".into()),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
@@ -303,12 +260,11 @@ mod tests {
 
         // Existing winner: higher visit_count.
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             name: Some("a".to_string()),
             url: Some("https://example.com#frag".to_string()),
             visit_count: Some(10),
             depth: 1,
-            path: "/a".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
@@ -316,12 +272,11 @@ mod tests {
 
         // New loser: lower visit_count.
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             name: Some("b".to_string()),
             url: Some("https://example.com".to_string()),
             visit_count: Some(1),
             depth: 1,
-            path: "/b".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
@@ -331,7 +286,15 @@ mod tests {
         let mut stats = NormalizeStats::default();
 
         let (tx, mut rx) = mpsc::channel::<AppEvent>(8);
-        per_folder_url_dedup(&mut arena, &canonicalizer, &Some(tx), &mut stats).await;
+        per_folder_url_dedup(
+            &mut arena,
+            &canonicalizer,
+            &UrlWinnerPolicy::default(),
+            &UrlMergePolicy::default(),
+            &Some(tx),
+            &mut stats,
+        )
+        .await;
 
         assert_eq!(stats.urls_deduped, 1);
         assert!(!arena.nodes[1].deleted);
@@ -344,7 +307,7 @@ mod tests {
                 canonical_url,
                 removed,
             } => {
-                assert_eq!(folder_path, "/");
+                assert_eq!(folder_path, "bookmark_bar");
                 assert_eq!(canonical_url, "https://example.com");
                 assert_eq!(removed, 1);
             }
@@ -356,9 +319,9 @@ mod tests {
     async fn per_folder_url_dedup_covers_keep_new_and_keep_existing_paths() {
         let mut arena = Arena::default();
         arena.nodes.push(ArenaNode {
-            node_type: "folder".to_string(),
+            kind: NodeKind::Folder,
             depth: 0,
-            path: "/".to_string(),
+            root_key: Some("bookmark_bar".into()),
             ..ArenaNode::default()
         });
         arena.parent.push(None);
@@ -368,11 +331,10 @@ mod tests {
 
         // Initial (will lose to the next one).
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             url: Some("https://example.com#one".to_string()),
             visit_count: Some(1),
             depth: 1,
-            path: "/1".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
@@ -380,11 +342,10 @@ mod tests {
 
         // Better (wins) -> triggers KeepNew.
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             url: Some("https://example.com#two".to_string()),
             visit_count: Some(10),
             depth: 1,
-            path: "/2".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
@@ -392,11 +353,10 @@ mod tests {
 
         // Worse than current best -> triggers KeepExisting.
         arena.nodes.push(ArenaNode {
-            node_type: "url".to_string(),
+            kind: NodeKind::Url,
             url: Some("https://example.com".to_string()),
             visit_count: Some(0),
             depth: 1,
-            path: "/3".to_string(),
             ..ArenaNode::default()
         });
         arena.parent.push(Some(Handle(0)));
@@ -404,7 +364,15 @@ mod tests {
 
         let canonicalizer = DefaultUrlCanonicalizer;
         let mut stats = NormalizeStats::default();
-        per_folder_url_dedup(&mut arena, &canonicalizer, &None, &mut stats).await;
+        per_folder_url_dedup(
+            &mut arena,
+            &canonicalizer,
+            &UrlWinnerPolicy::default(),
+            &UrlMergePolicy::default(),
+            &None,
+            &mut stats,
+        )
+        .await;
 
         assert_eq!(stats.urls_deduped, 2);
         assert!(arena.nodes[1].deleted);
@@ -0,0 +1,343 @@
+use crate::usecase::normalize::arena::{Arena, Handle};
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// How the surviving URL's `date_added` is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateAddedMerge {
+    #[default]
+    Winner,
+    Earliest,
+}
+
+/// How the surviving URL's `date_last_used` is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LastUsedMerge {
+    #[default]
+    Winner,
+    Latest,
+}
+
+/// How the surviving URL's `visit_count` is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VisitCountMerge {
+    #[default]
+    Winner,
+    Sum,
+    Max,
+}
+
+/// How the surviving URL's `meta_info` object is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetaInfoMerge {
+    #[default]
+    Winner,
+    /// Winner keys take precedence; keys only present on losers are added.
+    Union,
+}
+
+/// How the surviving URL's title (`name`) is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TitleMerge {
+    #[default]
+    Winner,
+    /// Longest non-empty trimmed title; the winner keeps its title on ties.
+    Longest,
+}
+
+/// Field-level merge rules applied when duplicate URLs collapse into one winner.
+///
+/// The default is winner-takes-all: loser fields are discarded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UrlMergePolicy {
+    pub date_added: DateAddedMerge,
+    pub date_last_used: LastUsedMerge,
+    pub visit_count: VisitCountMerge,
+    pub meta_info: MetaInfoMerge,
+    pub title: TitleMerge,
+}
+
+impl UrlMergePolicy {
+    /// Folds every loser field into the winner: earliest added, latest used,
+    /// summed visits, union of `meta_info` and the longest title.
+    pub fn fold() -> Self {
+        Self {
+            date_added: DateAddedMerge::Earliest,
+            date_last_used: LastUsedMerge::Latest,
+            visit_count: VisitCountMerge::Sum,
+            meta_info: MetaInfoMerge::Union,
+            title: TitleMerge::Longest,
+        }
+    }
+
+    pub fn is_winner_takes_all(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Copies loser data into `winner` according to the configured field rules.
+    pub fn apply(&self, arena: &mut Arena, winner: Handle, losers: &[Handle]) {
+        if self.is_winner_takes_all() || losers.is_empty() {
+            return;
+        }
+
+        let mut merged = arena.nodes[winner.0].clone();
+        for l in losers.iter() {
+            let loser = &arena.nodes[l.0];
+
+            if self.date_added == DateAddedMerge::Earliest {
+                if let Some(t) = pick_time(&merged.date_added, &loser.date_added, |a, b| b < a) {
+                    merged.date_added = Some(t);
+                }
+            }
+
+            if self.date_last_used == LastUsedMerge::Latest {
+                if let Some(t) =
+                    pick_time(&merged.date_last_used, &loser.date_last_used, |a, b| b > a)
+                {
+                    merged.date_last_used = Some(t);
+                }
+            }
+
+            match (self.visit_count, merged.visit_count, loser.visit_count) {
+                (VisitCountMerge::Sum, w, Some(l)) => {
+                    merged.visit_count = Some(w.unwrap_or(0).saturating_add(l));
+                }
+                (VisitCountMerge::Max, w, Some(l)) if w.is_none_or(|w| l > w) => {
+                    merged.visit_count = Some(l);
+                }
+                _ => {}
+            }
+
+            if self.meta_info == MetaInfoMerge::Union {
+                if let Some(serde_json::Value::Object(extra_meta)) = loser.extra.get("meta_info") {
+                    let target = merged
+                        .extra
+                        .entry("meta_info".to_string())
+                        .or_insert_with(|| serde_json::Value::Object(Default::default()));
+                    if let serde_json::Value::Object(target) = target {
+                        for (k, v) in extra_meta.iter() {
+                            target.entry(k.clone()).or_insert_with(|| v.clone());
+                        }
+                    }
+                }
+            }
+
+            if self.title == TitleMerge::Longest {
+                let current = title_len(merged.name.as_deref());
+                if title_len(loser.name.as_deref()) > current {
+                    merged.name = loser.name.clone();
+                }
+            }
+        }
+
+        arena.nodes[winner.0] = merged;
+    }
+}
+
+impl FromStr for UrlMergePolicy {
+    type Err = anyhow::Error;
+
+    /// Parses a preset name: `winner` (winner-takes-all) or `fold`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::default()),
+            "fold" => Ok(Self::fold()),
+            other => Err(anyhow!(
+                "unknown url merge preset: {other} (expected winner, fold)"
+            )),
+        }
+    }
+}
+
+impl FromStr for DateAddedMerge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "earliest" => Ok(Self::Earliest),
+            other => Err(anyhow!(
+                "unknown date_added merge rule: {other} (expected winner, earliest)"
+            )),
+        }
+    }
+}
+
+impl FromStr for LastUsedMerge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "latest" => Ok(Self::Latest),
+            other => Err(anyhow!(
+                "unknown date_last_used merge rule: {other} (expected winner, latest)"
+            )),
+        }
+    }
+}
+
+impl FromStr for VisitCountMerge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "sum" => Ok(Self::Sum),
+            "max" => Ok(Self::Max),
+            other => Err(anyhow!(
+                "unknown visit_count merge rule: {other} (expected winner, sum, max)"
+            )),
+        }
+    }
+}
+
+impl FromStr for MetaInfoMerge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "union" => Ok(Self::Union),
+            other => Err(anyhow!(
+                "unknown meta_info merge rule: {other} (expected winner, union)"
+            )),
+        }
+    }
+}
+
+impl FromStr for TitleMerge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "winner" => Ok(Self::Winner),
+            "longest" => Ok(Self::Longest),
+            other => Err(anyhow!(
+                "unknown title merge rule: {other} (expected winner, longest)"
+            )),
+        }
+    }
+}
+
+/// Returns the loser's timestamp when it parses and `better(current, loser)` holds
+/// (or the winner has no parseable timestamp).
+fn pick_time(
+    current: &Option<String>,
+    loser: &Option<String>,
+    better: impl Fn(u64, u64) -> bool,
+) -> Option<String> {
+    let l = loser.as_deref()?.parse::<u64>().ok()?;
+    match current.as_deref().and_then(|c| c.parse::<u64>().ok()) {
+        Some(c) if !better(c, l) => None,
+        _ => loser.clone(),
+    }
+}
+
+fn title_len(name: Option<&str>) -> usize {
+    name.map_or(0, |t| t.trim().chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::NodeKind;
+    use crate::usecase::normalize::arena::ArenaNode;
+    use serde_json::json;
+
+    fn arena_with(nodes: Vec<ArenaNode>) -> Arena {
+        let mut arena = Arena::default();
+        for n in nodes {
+            arena.nodes.push(n);
+            arena.parent.push(None);
+        }
+        arena
+    }
+
+    fn winner_and_loser() -> Arena {
+        arena_with(vec![
+            ArenaNode {
+                kind: NodeKind::Url,
+                name: Some("Docs".to_string()),
+                date_added: Some("200".to_string()),
+                date_last_used: Some("500".to_string()),
+                visit_count: Some(3),
+                extra: [("meta_info".to_string(), json!({"a": "winner"}))].into(),
+                ..ArenaNode::default()
+            },
+            ArenaNode {
+                kind: NodeKind::Url,
+                name: Some("Project documentation".to_string()),
+                date_added: Some("100".to_string()),
+                date_last_used: Some("900".to_string()),
+                visit_count: Some(4),
+                extra: [("meta_info".to_string(), json!({"a": "loser", "b": "loser"}))].into(),
+                ..ArenaNode::default()
+            },
+        ])
+    }
+
+    #[test]
+    fn default_policy_keeps_winner_fields() {
+        let mut arena = winner_and_loser();
+        let before = arena.nodes[0].clone();
+        UrlMergePolicy::default().apply(&mut arena, Handle(0), &[Handle(1)]);
+        assert_eq!(arena.nodes[0].name, before.name);
+        assert_eq!(arena.nodes[0].visit_count, Some(3));
+    }
+
+    #[test]
+    fn fold_merges_every_field() {
+        let mut arena = winner_and_loser();
+        UrlMergePolicy::fold().apply(&mut arena, Handle(0), &[Handle(1)]);
+
+        let w = &arena.nodes[0];
+        assert_eq!(w.date_added.as_deref(), Some("100"));
+        assert_eq!(w.date_last_used.as_deref(), Some("900"));
+        assert_eq!(w.visit_count, Some(7));
+        assert_eq!(w.name.as_deref(), Some("Project documentation"));
+        assert_eq!(w.extra["meta_info"], json!({"a": "winner", "b": "loser"}));
+    }
+
+    #[test]
+    fn individual_rules_can_be_mixed() {
+        let mut arena = winner_and_loser();
+        let policy = UrlMergePolicy {
+            visit_count: VisitCountMerge::Max,
+            ..UrlMergePolicy::default()
+        };
+        policy.apply(&mut arena, Handle(0), &[Handle(1)]);
+        assert_eq!(arena.nodes[0].visit_count, Some(4));
+        assert_eq!(arena.nodes[0].date_added.as_deref(), Some("200"));
+    }
+
+    #[test]
+    fn pick_time_ignores_unparseable_loser_and_fills_missing_winner() {
+        assert_eq!(
+            pick_time(&Some("5".to_string()), &Some("x".to_string()), |a, b| b < a),
+            None
+        );
+        assert_eq!(
+            pick_time(&None, &Some("7".to_string()), |a, b| b < a).as_deref(),
+            Some("7")
+        );
+    }
+
+    #[test]
+    fn parse_presets_and_field_rules() {
+        assert_eq!(
+            "fold".parse::<UrlMergePolicy>().unwrap(),
+            UrlMergePolicy::fold()
+        );
+        assert!("winner"
+            .parse::<UrlMergePolicy>()
+            .unwrap()
+            .is_winner_takes_all());
+        assert!("merge".parse::<UrlMergePolicy>().is_err());
+        assert_eq!(
+            "max".parse::<VisitCountMerge>().unwrap(),
+            VisitCountMerge::Max
+        );
+        assert!("sum".parse::<TitleMerge>().is_err());
+    }
+}
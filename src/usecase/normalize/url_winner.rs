@@ -0,0 +1,348 @@
+use crate::usecase::normalize::arena::ArenaNode;
+use anyhow::{anyhow, Result};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// One criterion used to rank duplicate URL bookmarks within a folder.
+///
+/// Rules are applied in order; the first rule that distinguishes two bookmarks decides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlWinnerRule {
+    /// Highest `visit_count` (missing counts as 0).
+    MostVisited,
+    /// Latest `date_last_used` (missing counts as never used).
+    RecentlyUsed,
+    /// Earliest `date_added`, so the oldest copy keeps its history; like the folder rule, a
+    /// copy with no readable date loses to any dated one.
+    EarliestAdded,
+    /// Non-empty, longer title (trimmed, counted in characters).
+    LongerTitle,
+    /// `https://` over any other scheme.
+    PreferHttps,
+    /// The copy carrying a non-empty `meta_info` object.
+    HasMetaInfo,
+    /// Smallest numeric `id` (numeric ids before textual, textual before missing).
+    SmallestId,
+}
+
+impl UrlWinnerRule {
+    fn compare(self, a: &ArenaNode, b: &ArenaNode) -> Ordering {
+        match self {
+            Self::MostVisited => b.visit_count.unwrap_or(0).cmp(&a.visit_count.unwrap_or(0)),
+            Self::RecentlyUsed => parse_edge_time(b.date_last_used.as_deref())
+                .cmp(&parse_edge_time(a.date_last_used.as_deref())),
+            Self::EarliestAdded => added_time(a).cmp(&added_time(b)),
+            Self::LongerTitle => title_len(b).cmp(&title_len(a)),
+            Self::PreferHttps => is_https(b).cmp(&is_https(a)),
+            Self::HasMetaInfo => has_meta_info(b).cmp(&has_meta_info(a)),
+            Self::SmallestId => id_key(a.id.as_deref()).cmp(&id_key(b.id.as_deref())),
+        }
+    }
+}
+
+impl fmt::Display for UrlWinnerRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::MostVisited => "most-visited",
+            Self::RecentlyUsed => "recently-used",
+            Self::EarliestAdded => "earliest-added",
+            Self::LongerTitle => "longer-title",
+            Self::PreferHttps => "prefer-https",
+            Self::HasMetaInfo => "has-meta-info",
+            Self::SmallestId => "smallest-id",
+        })
+    }
+}
+
+impl FromStr for UrlWinnerRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "most-visited" => Ok(Self::MostVisited),
+            "recently-used" => Ok(Self::RecentlyUsed),
+            "earliest-added" => Ok(Self::EarliestAdded),
+            "longer-title" => Ok(Self::LongerTitle),
+            "prefer-https" => Ok(Self::PreferHttps),
+            "has-meta-info" => Ok(Self::HasMetaInfo),
+            "smallest-id" => Ok(Self::SmallestId),
+            other => Err(anyhow!(
+                "unknown url winner rule: {other} (expected most-visited, recently-used, earliest-added, longer-title, prefer-https, has-meta-info, smallest-id)"
+            )),
+        }
+    }
+}
+
+/// Ordered list of winner rules for per-folder URL deduplication.
+///
+/// `smallest-id` is always applied last so the choice stays deterministic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlWinnerPolicy {
+    pub rules: Vec<UrlWinnerRule>,
+    /// Set only by [`Default`]: its `earliest-added` counts a missing or unreadable
+    /// `date_added` as 0, so an undated copy wins, as the original ranking did.
+    undated_first: bool,
+}
+
+impl Default for UrlWinnerPolicy {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                UrlWinnerRule::MostVisited,
+                UrlWinnerRule::RecentlyUsed,
+                UrlWinnerRule::EarliestAdded,
+                UrlWinnerRule::SmallestId,
+            ],
+            undated_first: true,
+        }
+    }
+}
+
+impl UrlWinnerPolicy {
+    /// `Ordering::Less` means `a` ranks ahead of `b`.
+    pub fn compare(&self, a: &ArenaNode, b: &ArenaNode) -> Ordering {
+        self.rules
+            .iter()
+            .chain(std::iter::once(&UrlWinnerRule::SmallestId))
+            .map(|r| match r {
+                UrlWinnerRule::EarliestAdded if self.undated_first => {
+                    parse_edge_time(a.date_added.as_deref())
+                        .cmp(&parse_edge_time(b.date_added.as_deref()))
+                }
+                r => r.compare(a, b),
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl fmt::Display for UrlWinnerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self.rules.iter().map(|r| r.to_string()).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+impl FromStr for UrlWinnerPolicy {
+    type Err = anyhow::Error;
+
+    /// Parses a comma-separated rule list, e.g. `prefer-https,most-visited`.
+    fn from_str(s: &str) -> Result<Self> {
+        let rules = s
+            .split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<UrlWinnerRule>>>()?;
+        if rules.is_empty() {
+            return Err(anyhow!("url winner policy must name at least one rule"));
+        }
+        Ok(Self {
+            rules,
+            undated_first: false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum IdKey {
+    Parsed(u64),
+    Text(String),
+    Missing,
+}
+
+pub(super) fn id_key(id: Option<&str>) -> IdKey {
+    let Some(id) = id else {
+        return IdKey::Missing;
+    };
+    if let Ok(v) = id.parse::<u64>() {
+        return IdKey::Parsed(v);
+    }
+    IdKey::Text(id.to_string())
+}
+
+fn parse_edge_time(s: Option<&str>) -> u64 {
+    match s {
+        Some(v) => v.parse::<u64>().unwrap_or(0),
+        None => 0,
+    }
+}
+
+/// `date_added` for `earliest-added`. Like the folder rule, an unknown date sorts last, so an
+/// undated copy never beats one whose age is known.
+fn added_time(n: &ArenaNode) -> u64 {
+    n.date_added
+        .as_deref()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(u64::MAX)
+}
+
+fn title_len(n: &ArenaNode) -> usize {
+    n.name.as_deref().map_or(0, |t| t.trim().chars().count())
+}
+
+fn is_https(n: &ArenaNode) -> bool {
+    n.url
+        .as_deref()
+        .and_then(|u| u.trim().get(..8))
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"))
+}
+
+fn has_meta_info(n: &ArenaNode) -> bool {
+    n.extra
+        .get("meta_info")
+        .and_then(|v| v.as_object())
+        .is_some_and(|m| !m.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::NodeKind;
+    use serde_json::json;
+
+    fn url(u: &str) -> ArenaNode {
+        ArenaNode {
+            kind: NodeKind::Url,
+            url: Some(u.to_string()),
+            ..ArenaNode::default()
+        }
+    }
+
+    #[test]
+    fn id_key_covers_missing_parsed_and_text() {
+        assert_eq!(id_key(None), IdKey::Missing);
+        assert_eq!(id_key(Some("7")), IdKey::Parsed(7));
+        assert_eq!(id_key(Some("abc")), IdKey::Text("abc".to_string()));
+    }
+
+    #[test]
+    fn parse_edge_time_handles_none_and_invalid() {
+        assert_eq!(parse_edge_time(None), 0);
+        assert_eq!(parse_edge_time(Some("nope")), 0);
+        assert_eq!(parse_edge_time(Some("9")), 9);
+    }
+
+    #[test]
+    fn default_policy_prefers_visits_then_last_used() {
+        let policy = UrlWinnerPolicy::default();
+        let a = ArenaNode {
+            visit_count: Some(3),
+            date_last_used: Some("1".to_string()),
+            ..url("https://example.com")
+        };
+        let b = ArenaNode {
+            visit_count: Some(3),
+            date_last_used: Some("2".to_string()),
+            ..url("https://example.com")
+        };
+        assert_eq!(policy.compare(&b, &a), Ordering::Less);
+
+        let c = ArenaNode {
+            visit_count: Some(4),
+            ..url("https://example.com")
+        };
+        assert_eq!(policy.compare(&c, &b), Ordering::Less);
+    }
+
+    #[test]
+    fn default_policy_picks_the_same_winners_as_the_original_ranking() {
+        // The ranking the default policy replaced: highest tuple wins.
+        fn original_rank(
+            n: &ArenaNode,
+        ) -> (i64, u64, std::cmp::Reverse<u64>, std::cmp::Reverse<IdKey>) {
+            (
+                n.visit_count.unwrap_or(0),
+                parse_edge_time(n.date_last_used.as_deref()),
+                std::cmp::Reverse(parse_edge_time(n.date_added.as_deref())),
+                std::cmp::Reverse(id_key(n.id.as_deref())),
+            )
+        }
+        let copy = |added: Option<&str>, id: &str| ArenaNode {
+            date_added: added.map(str::to_string),
+            id: Some(id.to_string()),
+            ..url("https://example.com")
+        };
+        let copies = [
+            copy(Some("5"), "1"),
+            copy(None, "2"),
+            copy(Some("junk"), "3"),
+            copy(Some("9"), "4"),
+        ];
+
+        let policy = UrlWinnerPolicy::default();
+        for a in copies.iter() {
+            for b in copies.iter() {
+                assert_eq!(
+                    policy.compare(a, b),
+                    original_rank(b).cmp(&original_rank(a)),
+                    "{:?} vs {:?}",
+                    a.id,
+                    b.id
+                );
+            }
+        }
+        // An undated copy still wins the default ranking, as it always did.
+        assert_eq!(policy.compare(&copies[1], &copies[0]), Ordering::Less);
+    }
+
+    #[test]
+    fn alternative_rules_rank_as_documented() {
+        let http = ArenaNode {
+            name: Some("A much longer title".to_string()),
+            date_added: Some("5".to_string()),
+            ..url("http://example.com")
+        };
+        let https = ArenaNode {
+            name: Some("  ".to_string()),
+            date_added: Some("9".to_string()),
+            extra: [("meta_info".to_string(), json!({"k": "v"}))].into(),
+            ..url("HTTPS://example.com")
+        };
+
+        let policy: UrlWinnerPolicy = "prefer-https".parse().expect("parse");
+        assert_eq!(policy.compare(&https, &http), Ordering::Less);
+
+        let policy: UrlWinnerPolicy = "longer-title".parse().expect("parse");
+        assert_eq!(policy.compare(&http, &https), Ordering::Less);
+
+        let undated = url("https://example.com");
+        let policy: UrlWinnerPolicy = "earliest-added".parse().expect("parse");
+        assert_eq!(policy.compare(&http, &https), Ordering::Less);
+        assert_eq!(policy.compare(&https, &undated), Ordering::Less);
+
+        // Only the default chain keeps the original undated-first order.
+        let policy: UrlWinnerPolicy = "most-visited,recently-used,earliest-added"
+            .parse()
+            .expect("parse");
+        assert_eq!(policy.compare(&https, &undated), Ordering::Less);
+        assert_eq!(
+            UrlWinnerPolicy::default().compare(&undated, &https),
+            Ordering::Less
+        );
+
+        let policy: UrlWinnerPolicy = "has-meta-info,longer-title".parse().expect("parse");
+        assert_eq!(policy.compare(&https, &http), Ordering::Less);
+    }
+
+    #[test]
+    fn smallest_id_always_breaks_remaining_ties() {
+        let policy: UrlWinnerPolicy = "prefer-https".parse().expect("parse");
+        let a = ArenaNode {
+            id: Some("9".to_string()),
+            ..url("https://example.com")
+        };
+        let b = ArenaNode {
+            id: Some("10".to_string()),
+            ..url("https://example.com")
+        };
+        assert_eq!(policy.compare(&a, &b), Ordering::Less);
+        assert_eq!(policy.to_string(), "prefer-https");
+    }
+
+    #[test]
+    fn parse_rejects_unknown_and_empty_rules() {
+        assert!("most-visited,loudest".parse::<UrlWinnerPolicy>().is_err());
+        assert!(" , ".parse::<UrlWinnerPolicy>().is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use crate::usecase::normalize::cycles::CycleDiagnostic;
+use crate::usecase::normalize::identities::DuplicateIdentity;
+use crate::usecase::normalize::provenance::Provenance;
+use crate::usecase::normalize::removals::RemovalSummary;
+use crate::usecase::stats::NormalizeStats;
+use serde::Serialize;
+
+/// Machine-readable outcome of a normalization run (`normalize --report <path>`).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NormalizeReport {
+    pub stats: NormalizeStats,
+    /// Every cyclic identity component, with a witness cycle located in the input file.
+    pub cycles: Vec<CycleDiagnostic>,
+    /// Every `guid` or `id` shared by more than one node in the input.
+    pub duplicate_identities: Vec<DuplicateIdentity>,
+    /// URLs and folders the run removed, with the groups that lost the most.
+    pub removals: RemovalSummary,
+    /// Fate of every input node, when requested; written to its own file, not the report.
+    #[serde(skip)]
+    pub provenance: Option<Provenance>,
+}
@@ -3,8 +3,14 @@ use serde::Serialize;
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct NormalizeStats {
     pub folders_seen: usize,
+    pub identity_edges_cut: usize,
     pub folders_merged: usize,
     pub urls_seen: usize,
     pub urls_deduped: usize,
     pub folders_pruned: usize,
+    /// Nodes left exactly as read because they failed their schema (lenient mode).
+    pub nodes_preserved: usize,
+    /// Peak resident set size of the process at the end of the run, where the OS reports it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_memory_bytes: Option<u64>,
 }
@@ -1,35 +1,248 @@
 use crate::domain::traits::UrlCanonicalizer;
-use crate::infrastructure::schema_validator::{
-    validate_all_bookmark_items, validate_bookmarks_file,
-};
-use crate::infrastructure::serde_json_adapter::BookmarksFileDto;
-use anyhow::{anyhow, Result};
-use std::collections::{BTreeMap, BTreeSet};
+use crate::infrastructure::json_locator::{locate, TextPosition};
+use crate::infrastructure::schema_catalog::Schemas;
+use crate::infrastructure::schema_validator::schema_violations_with;
+use crate::infrastructure::serde_json_adapter::{BookmarkNodeDto, BookmarksFileDto};
+use crate::usecase::canonical::canonicalize_all;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// Post-normalization rules `validate` checks; `Schema` covers the JSON schemas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    Schema,
+    UniqueFolderName,
+    NoEmptyFolder,
+    UniqueSubfolderName,
+    UniqueUrlInFolder,
+}
+
+impl Rule {
+    pub fn is_schema(self) -> bool {
+        self == Rule::Schema
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rule::Schema => "schema",
+            Rule::UniqueFolderName => "unique-folder-name",
+            Rule::NoEmptyFolder => "no-empty-folder",
+            Rule::UniqueSubfolderName => "unique-subfolder-name",
+            Rule::UniqueUrlInFolder => "unique-url-in-folder",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// One broken rule, located by JSON pointer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub path: String,
+    /// Schema errors: root key and names down to the failing item.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_path: Option<String>,
+    /// Where `path` starts in the source text, once located.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<TextPosition>,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}] {}", self.severity, self.rule, self.path)?;
+        let mut details: Vec<String> = self.name_path.iter().cloned().collect();
+        details.extend(self.position.map(|p| p.to_string()));
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
 
+/// Every violation `validate` found, schema errors first, then in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        !self
+            .violations
+            .iter()
+            .any(|v| v.severity == Severity::Error)
+    }
+
+    pub fn schema_errors(&self) -> usize {
+        self.count(|rule| rule.is_schema())
+    }
+
+    pub fn invariant_errors(&self) -> usize {
+        self.count(|rule| !rule.is_schema())
+    }
+
+    fn count(&self, pick: impl Fn(Rule) -> bool) -> usize {
+        self.violations
+            .iter()
+            .filter(|v| v.severity == Severity::Error && pick(v.rule))
+            .count()
+    }
+
+    /// Sets the line and column of every violation with a path by scanning the source text
+    /// the report was made from.
+    pub fn locate<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+        let pointers: BTreeSet<String> = self
+            .violations
+            .iter()
+            .filter(|v| !v.path.is_empty())
+            .map(|v| v.path.clone())
+            .collect();
+        let found = locate(reader, &pointers)?;
+        for v in &mut self.violations {
+            v.position = found.get(&v.path).copied();
+        }
+        Ok(())
+    }
+
+    /// One line per violation.
+    pub fn to_text(&self) -> String {
+        self.violations
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// `validate` found errors; carries the whole report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationFailed(pub ValidationReport);
+
+impl fmt::Display for ValidationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bookmarks failed validation: {} schema error(s), {} invariant error(s)\n{}",
+            self.0.schema_errors(),
+            self.0.invariant_errors(),
+            self.0.to_text()
+        )
+    }
+}
+
+impl std::error::Error for ValidationFailed {}
+
+/// Fails with [`ValidationFailed`] listing every violation; see [`validation_report`].
 pub fn validate_bookmarks(
     dto: &BookmarksFileDto,
     canonicalizer: &dyn UrlCanonicalizer,
 ) -> Result<()> {
-    // First, validate against JSON schemas
-    let bookmarks_value = serde_json::to_value(dto)?;
-    validate_bookmarks_file(&bookmarks_value)?;
-    validate_all_bookmark_items(&bookmarks_value)?;
+    let report = validation_report(dto, canonicalizer)?;
+    if report.is_valid() {
+        return Ok(());
+    }
+    Err(ValidationFailed(report).into())
+}
 
-    // Iterative traversal (no recursion).
-    // We treat the document as a forest of folders rooted at `dto.roots`.
+/// Checks `dto` against the schemas and every post-normalization invariant, collecting all
+/// violations rather than stopping at the first.
+pub fn validation_report(
+    dto: &BookmarksFileDto,
+    canonicalizer: &dyn UrlCanonicalizer,
+) -> Result<ValidationReport> {
+    Ok(validate_value(serde_json::to_value(dto)?, canonicalizer))
+}
 
-    // Global uniqueness by normalized folder name.
-    let mut global_folder_owner: BTreeMap<String, String> = BTreeMap::new();
+/// Like [`validation_report`], for a parsed document that may not even fit the bookmarks
+/// model; the invariants are checked only when it does.
+pub fn validate_value(document: Value, canonicalizer: &dyn UrlCanonicalizer) -> ValidationReport {
+    validate_value_with(document, canonicalizer, &Schemas::embedded())
+}
+
+/// [`validate_value`] against `schemas` instead of the embedded ones.
+pub fn validate_value_with(
+    document: Value,
+    canonicalizer: &dyn UrlCanonicalizer,
+    schemas: &Schemas,
+) -> ValidationReport {
+    let mut violations: Vec<Violation> =
+        schema_violations_with(schemas.for_document(&document), &document)
+            .into_iter()
+            .map(|v| Violation {
+                rule: Rule::Schema,
+                severity: Severity::Error,
+                path: v.pointer,
+                name_path: v.name_path,
+                position: None,
+                message: v.message,
+            })
+            .collect();
+
+    match serde_json::from_value::<BookmarksFileDto>(document) {
+        Ok(dto) => violations.extend(invariant_violations(&dto, canonicalizer)),
+        Err(e) => violations.push(Violation {
+            rule: Rule::Schema,
+            severity: Severity::Error,
+            path: String::new(),
+            name_path: None,
+            position: None,
+            message: format!("document does not match the bookmarks model: {e}"),
+        }),
+    }
+    ValidationReport { violations }
+}
 
-    // Stack holds (path, node, is_root_container).
-    let mut stack: Vec<(
-        String,
-        &crate::infrastructure::serde_json_adapter::BookmarkNodeDto,
-        bool,
-    )> = Vec::new();
+fn invariant_violations(
+    dto: &BookmarksFileDto,
+    canonicalizer: &dyn UrlCanonicalizer,
+) -> Vec<Violation> {
+    let canonical = canonical_urls(dto, canonicalizer);
+    let mut out: Vec<Violation> = Vec::new();
+    let mut violation = |rule: Rule, path: &str, message: String| {
+        out.push(Violation {
+            rule,
+            severity: Severity::Error,
+            path: path.to_string(),
+            name_path: None,
+            position: None,
+            message,
+        })
+    };
+
+    // Iterative pre-order traversal (no recursion) of the forest rooted at `dto.roots`.
+
+    // Global uniqueness by normalized folder name: first pointer seen for each name.
+    let mut global_folder_owner: BTreeMap<String, String> = BTreeMap::new();
 
-    for (root_key, root) in dto.roots.iter() {
-        stack.push((format!("/{root_key}"), root, true));
+    // Stack holds (JSON pointer, node, is_root_container).
+    let mut stack: Vec<(String, &BookmarkNodeDto, bool)> = Vec::new();
+    for (root_key, root) in dto.roots.iter().rev() {
+        let key = root_key.replace('~', "~0").replace('/', "~1");
+        stack.push((format!("/roots/{key}"), root, true));
     }
 
     while let Some((path, node, is_root_container)) = stack.pop() {
@@ -39,57 +252,94 @@ pub fn validate_bookmarks(
 
         if let Some(name) = node.name.as_ref() {
             let norm = name.trim().to_lowercase();
-            if let Some(existing_path) = global_folder_owner.get(&norm) {
-                return Err(anyhow!(
-                    "folder name must be globally unique: {norm} (saw at {existing_path} and {path})"
-                ));
+            match global_folder_owner.get(&norm) {
+                Some(existing_path) => violation(
+                    Rule::UniqueFolderName,
+                    &path,
+                    format!(
+                        "folder name must be globally unique: {norm} (also at {existing_path})"
+                    ),
+                ),
+                None => {
+                    global_folder_owner.insert(norm, path.clone());
+                }
             }
-            global_folder_owner.insert(norm, path.clone());
         }
 
         // Empty folders removed (root containers are allowed to be empty).
         if !is_root_container && node.children.is_empty() {
-            return Err(anyhow!("empty folder found at {path}"));
+            violation(
+                Rule::NoEmptyFolder,
+                &path,
+                format!(
+                    "empty folder: {:?}",
+                    node.name.as_deref().unwrap_or_default()
+                ),
+            );
         }
 
         // No duplicate subfolder names within the same folder.
         let mut seen_child_folders: BTreeSet<String> = BTreeSet::new();
 
         // URL dedup per folder.
-        let mut seen_urls: BTreeSet<String> = BTreeSet::new();
+        let mut seen_urls: BTreeSet<&str> = BTreeSet::new();
 
-        for child in node.children.iter() {
+        for (idx, child) in node.children.iter().enumerate() {
+            let child_path = format!("{path}/children/{idx}");
             if child.node_type == "folder" {
                 if let Some(n) = child.name.as_ref() {
                     let norm = n.trim().to_lowercase();
                     if !seen_child_folders.insert(norm.clone()) {
-                        return Err(anyhow!("duplicate subfolder name under {path}: {norm}"));
+                        violation(
+                            Rule::UniqueSubfolderName,
+                            &child_path,
+                            format!("duplicate subfolder name under {path}: {norm}"),
+                        );
                     }
                 }
             }
 
             if child.node_type == "url" {
                 if let Some(url) = child.url.as_ref() {
-                    let canon = canonicalizer.canonicalize(url);
-                    if !seen_urls.insert(canon.clone()) {
-                        return Err(anyhow!("duplicate URL under {path}: {canon}"));
+                    let canon = canonical[url.as_str()].as_str();
+                    if !seen_urls.insert(canon) {
+                        violation(
+                            Rule::UniqueUrlInFolder,
+                            &child_path,
+                            format!("duplicate URL under {path}: {canon}"),
+                        );
                     }
                 }
             }
         }
 
-        // Push children deterministically.
-        // `children` is a Vec from JSON; we preserve order but include index in fallback path segment.
         for (idx, child) in node.children.iter().enumerate().rev() {
-            let seg = child
-                .name
-                .clone()
-                .unwrap_or_else(|| format!("{}[{idx}]", child.node_type));
-            stack.push((format!("{path}/{seg}"), child, false));
+            stack.push((format!("{path}/children/{idx}"), child, false));
         }
     }
 
-    Ok(())
+    out
+}
+
+/// Canonical key of every distinct URL in the file, computed once and in parallel.
+pub(crate) fn canonical_urls<'a>(
+    dto: &'a BookmarksFileDto,
+    canonicalizer: &dyn UrlCanonicalizer,
+) -> HashMap<&'a str, String> {
+    let mut distinct: BTreeSet<&str> = BTreeSet::new();
+    let mut stack: Vec<&BookmarkNodeDto> = dto.roots.values().collect();
+    while let Some(node) = stack.pop() {
+        if node.node_type == "url" {
+            if let Some(url) = node.url.as_deref() {
+                distinct.insert(url);
+            }
+        }
+        stack.extend(node.children.iter());
+    }
+
+    let urls: Vec<&str> = distinct.into_iter().collect();
+    let keys = canonicalize_all(canonicalizer, &urls);
+    urls.into_iter().zip(keys).collect()
 }
 
 #[cfg(test)]
@@ -216,6 +466,59 @@ mod tests {
         assert!(err.contains("duplicate URL"));
     }
 
+    #[test]
+    fn report_collects_every_violation_with_rule_and_pointer() {
+        let document = serde_json::json!({"roots": {
+            "bookmark_bar": {"type": "folder", "children": [
+                {"type": "folder", "name": "Work", "children": [
+                    {"type": "url", "url": "https://example.com/", "date_added": "yesterday"},
+                    {"type": "url", "url": "https://EXAMPLE.com/"}
+                ]},
+                {"type": "folder", "name": "Empty", "children": []}
+            ]},
+            "other": {"type": "folder", "children": [
+                {"type": "folder", "name": "work", "children": [
+                    {"type": "url", "url": "https://example.org/"}
+                ]}
+            ]}
+        }});
+
+        let report = validate_value(document, &DefaultUrlCanonicalizer);
+        let found: Vec<(Rule, &str)> = report
+            .violations
+            .iter()
+            .map(|v| (v.rule, v.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    Rule::Schema,
+                    "/roots/bookmark_bar/children/0/children/0/date_added"
+                ),
+                (
+                    Rule::UniqueUrlInFolder,
+                    "/roots/bookmark_bar/children/0/children/1"
+                ),
+                (Rule::NoEmptyFolder, "/roots/bookmark_bar/children/1"),
+                (Rule::UniqueFolderName, "/roots/other/children/0"),
+            ]
+        );
+        assert!(!report.is_valid());
+        assert_eq!((report.schema_errors(), report.invariant_errors()), (1, 3));
+        assert!(report
+            .violations
+            .iter()
+            .all(|v| v.severity == Severity::Error));
+
+        let json = serde_json::to_value(&report).expect("serialize");
+        assert_eq!(json["violations"][2]["rule"], "no-empty-folder");
+        assert_eq!(json["violations"][2]["severity"], "error");
+        assert!(report.to_text().contains(
+            "error [unique-folder-name] /roots/other/children/0: folder name must be globally unique: work (also at /roots/bookmark_bar/children/0)"
+        ));
+    }
+
     #[test]
     fn validate_folder_schema_requires_type_and_name() {
         let dto = BookmarksFileDto {
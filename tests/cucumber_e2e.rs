@@ -97,6 +97,27 @@ async fn i_run_bookmarks_normalize_to_an_output_file(world: &mut TestWorld) {
     world.last_cmd = Some(out);
 }
 
+#[when("I run bookmarks normalize with an idempotence check")]
+async fn i_run_bookmarks_normalize_with_an_idempotence_check(world: &mut TestWorld) {
+    let dir = world.dir.as_ref().expect("temp dir");
+    let input_path = world.input_path.as_ref().expect("input");
+    let output_path = dir.path().join("Out.json");
+
+    let out = run_cmd(vec![
+        "bookmarks".to_string(),
+        "normalize".to_string(),
+        "--in".to_string(),
+        input_path.to_string_lossy().into_owned(),
+        "--out".to_string(),
+        output_path.to_string_lossy().into_owned(),
+        "--check-idempotent".to_string(),
+    ])
+    .await;
+
+    world.output_path = Some(output_path);
+    world.last_cmd = Some(out);
+}
+
 #[when("I run bookmarks validate on the input file")]
 async fn i_run_bookmarks_validate_on_the_input_file(world: &mut TestWorld) {
     let input_path = world.input_path.as_ref().expect("input");
@@ -14,6 +14,13 @@ Feature: Bookmarks CLI normalization
     When I run bookmarks normalize twice to two output files
     Then the two outputs are identical
 
+  Scenario: Normalize is idempotent
+    Given a temp bookmarks workspace
+    And an input bookmarks file with duplicates
+    When I run bookmarks normalize with an idempotence check
+    Then the command succeeds
+    And stderr mentions "normalizing the output again changes nothing"
+
   Scenario: Overwriting without backup is refused
     Given a temp bookmarks workspace
     And an input bookmarks file with duplicates
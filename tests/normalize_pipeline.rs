@@ -73,7 +73,14 @@ use microsoft_edge_bookmark_sorter_flattener::infrastructure::serde_json_adapter
 };
 use microsoft_edge_bookmark_sorter_flattener::infrastructure::url_canonicalizer::DefaultUrlCanonicalizer;
 use microsoft_edge_bookmark_sorter_flattener::usecase::event::AppEvent;
-use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::normalize_bookmarks;
+use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::folder_name::{
+    FolderNamePolicy, FolderNameRule,
+};
+use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::identities::DuplicateIdentityMode;
+use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::{
+    normalize_bookmarks, normalize_bookmarks_with_options, normalize_bookmarks_with_report,
+    NormalizeOptions,
+};
 use serde_json::json;
 use std::collections::BTreeMap;
 use tokio::sync::mpsc;
@@ -93,6 +100,7 @@ fn root(children: Vec<BookmarkNodeDto>) -> BookmarkNodeDto {
         source: None,
         show_icon: None,
         extra: Default::default(),
+        raw_type: None,
     }
 }
 
@@ -117,6 +125,7 @@ fn folder(
         source: None,
         show_icon: None,
         extra: Default::default(),
+        raw_type: None,
     }
 }
 
@@ -142,6 +151,7 @@ fn url(
         source: None,
         show_icon: None,
         extra: Default::default(),
+        raw_type: None,
     }
 }
 
@@ -160,6 +170,7 @@ fn separator(name: &str) -> BookmarkNodeDto {
         source: None,
         show_icon: None,
         extra: Default::default(),
+        raw_type: None,
     }
 }
 
@@ -616,3 +627,599 @@ async fn url_dedup_resolves_ties_by_id_when_other_fields_equal() {
 
     // x_merge_meta removed to preserve original JSON structure for Microsoft Edge compatibility
 }
+
+#[tokio::test]
+async fn folder_winner_policy_prefer_root_overrides_outermost() {
+    // bookmark_bar: Docs (depth 1, id 1)
+    // other: Stuff/Docs (depth 2, id 2) -> wins under prefer-root:other
+    let docs_bar = folder(
+        "Docs",
+        Some("1"),
+        None,
+        Some("100"),
+        vec![url(
+            "a",
+            Some("10"),
+            "http://example.com/a",
+            None,
+            None,
+            Some("10"),
+        )],
+    );
+    let docs_other = folder(
+        "Docs",
+        Some("2"),
+        None,
+        Some("200"),
+        vec![url(
+            "b",
+            Some("11"),
+            "http://example.com/b",
+            None,
+            None,
+            Some("11"),
+        )],
+    );
+    let stuff = folder("Stuff", Some("3"), None, Some("300"), vec![docs_other]);
+
+    let input = mk_input(vec![
+        ("bookmark_bar", root(vec![docs_bar])),
+        ("other", root(vec![stuff])),
+    ]);
+
+    let options = NormalizeOptions {
+        folder_winner: "prefer-root:other,outermost".parse().expect("policy"),
+        ..NormalizeOptions::default()
+    };
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (tx, mut rx) = mpsc::channel::<AppEvent>(256);
+
+    let (out, stats) =
+        normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, Some(tx))
+            .await
+            .expect("normalize_bookmarks should succeed");
+
+    assert_eq!(stats.folders_merged, 1);
+    let docs = find_folders_named(&out, "Docs");
+    assert_eq!(docs.len(), 1);
+    assert_eq!(
+        docs[0].id.as_deref(),
+        Some("2"),
+        "winner lives under `other`"
+    );
+    assert_eq!(find_urls_in_folder(docs[0]).len(), 2);
+    assert!(out.roots["bookmark_bar"].children.is_empty());
+
+    let mut merged = None;
+    while let Some(ev) = rx.recv().await {
+        if let AppEvent::FolderMerged {
+            policy, rationale, ..
+        } = ev
+        {
+            merged = Some((policy, rationale));
+        }
+    }
+    let (policy, rationale) = merged.expect("should emit FolderMerged");
+    assert_eq!(policy, "prefer-root:other,outermost");
+    assert_eq!(
+        rationale,
+        vec!["prefer-root:other: root other over bookmark_bar".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn folder_winner_policy_never_merges_an_ancestor_into_its_nested_namesake() {
+    // bookmark_bar: Work (id 2) / Work (id 3, 2 URLs, modified and added later/earlier);
+    // every rule below ranks the inner Work first, yet the outer one must absorb it.
+    for rule in [
+        "most-children",
+        "most-recently-modified",
+        "prefer-root:bookmark_bar",
+    ] {
+        let inner = BookmarkNodeDto {
+            date_modified: Some("900".to_string()),
+            ..folder(
+                "Work",
+                Some("3"),
+                None,
+                Some("100"),
+                vec![
+                    url("a", Some("10"), "http://example.com/a", None, None, None),
+                    url("b", Some("11"), "http://example.com/b", None, None, None),
+                ],
+            )
+        };
+        let outer = BookmarkNodeDto {
+            date_modified: Some("100".to_string()),
+            ..folder("Work", Some("2"), None, Some("200"), vec![inner])
+        };
+        let input = mk_input(vec![("bookmark_bar", root(vec![outer]))]);
+
+        let options = NormalizeOptions {
+            folder_winner: rule.parse().expect("policy"),
+            ..NormalizeOptions::default()
+        };
+        let canonicalizer = DefaultUrlCanonicalizer;
+        let scc = KosarajuSccDetector;
+        let run = normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, None);
+        let (out, stats) = tokio::time::timeout(std::time::Duration::from_secs(10), run)
+            .await
+            .unwrap_or_else(|_| panic!("{rule}: merge did not finish"))
+            .expect("normalize_bookmarks should succeed");
+
+        assert_eq!(stats.folders_merged, 1, "{rule}");
+        let work = find_folders_named(&out, "Work");
+        assert_eq!(work.len(), 1, "{rule}");
+        assert_eq!(
+            work[0].id.as_deref(),
+            Some("2"),
+            "{rule}: the ancestor wins"
+        );
+        assert_eq!(find_urls_in_folder(work[0]).len(), 2, "{rule}");
+    }
+}
+
+#[tokio::test]
+async fn ancestor_rationale_is_given_only_to_losers_the_winner_contains() {
+    // bookmark_bar: Work (id 2) / Work (id 3, 3 URLs), and Work (id 4, 2 URLs) beside it.
+    // most-children picks id 3, which gives way to its ancestor id 2; id 4 also outranks
+    // id 2 but is not inside it.
+    let links = |prefix: &str, n: usize| -> Vec<BookmarkNodeDto> {
+        (0..n)
+            .map(|i| {
+                let name = format!("{prefix}{i}");
+                let href = format!("http://example.com/{name}");
+                url(&name, None, &href, None, None, None)
+            })
+            .collect()
+    };
+    let inner = folder("Work", Some("3"), None, None, links("i", 3));
+    let outer = folder("Work", Some("2"), None, None, vec![inner]);
+    let sibling = folder("Work", Some("4"), None, None, links("s", 2));
+    let input = mk_input(vec![("bookmark_bar", root(vec![outer, sibling]))]);
+
+    let options = NormalizeOptions {
+        folder_winner: "most-children".parse().expect("policy"),
+        ..NormalizeOptions::default()
+    };
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (tx, mut rx) = mpsc::channel::<AppEvent>(256);
+    let (out, _) =
+        normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, Some(tx))
+            .await
+            .expect("normalize_bookmarks should succeed");
+
+    let work = find_folders_named(&out, "Work");
+    assert_eq!(work.len(), 1);
+    assert_eq!(work[0].id.as_deref(), Some("2"));
+    assert_eq!(find_urls_in_folder(work[0]).len(), 5);
+
+    let mut merged = None;
+    while let Some(ev) = rx.recv().await {
+        if let AppEvent::FolderMerged {
+            losers, rationale, ..
+        } = ev
+        {
+            merged = Some((losers, rationale));
+        }
+    }
+    let (losers, rationale) = merged.expect("should emit FolderMerged");
+    assert_eq!(losers.len(), 2);
+    assert_eq!(rationale[0], "ancestor: contains the loser");
+    assert!(
+        rationale[1].starts_with("most-children: "),
+        "the sibling is explained by the policy, not as contained: {}",
+        rationale[1]
+    );
+}
+
+#[tokio::test]
+async fn url_winner_policy_earliest_added_overrides_visit_count() {
+    let a = folder(
+        "A",
+        Some("1"),
+        None,
+        Some("100"),
+        vec![
+            url(
+                "secure",
+                Some("3"),
+                "https://example.com/page",
+                Some(1),
+                Some("100"),
+                Some("20"),
+            ),
+            url(
+                "secure dup",
+                Some("4"),
+                "HTTPS://example.com/page#top",
+                Some(50),
+                Some("900"),
+                Some("10"),
+            ),
+        ],
+    );
+    let input = mk_input(vec![("bookmark_bar", root(vec![a]))]);
+
+    // Both copies are https, so prefer-https ties and earliest-added decides,
+    // overriding the default preference for the most visited copy.
+    let options = NormalizeOptions {
+        url_winner: "prefer-https,earliest-added".parse().expect("policy"),
+        ..NormalizeOptions::default()
+    };
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (out, stats) =
+        normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, None)
+            .await
+            .expect("normalize_bookmarks should succeed");
+
+    assert_eq!(stats.urls_deduped, 1);
+    let a_out = find_folders_named(&out, "A");
+    let urls = find_urls_in_folder(a_out[0]);
+    assert_eq!(urls.len(), 1);
+    assert_eq!(urls[0].id.as_deref(), Some("4"));
+}
+
+#[tokio::test]
+async fn url_merge_fold_keeps_loser_history_on_winner() {
+    let mut keep = url(
+        "Docs",
+        Some("1"),
+        "http://example.com/docs",
+        Some(10),
+        Some("500"),
+        Some("300"),
+    );
+    keep.extra.insert(
+        "meta_info".to_string(),
+        json!({"last_visited_desktop": "1"}),
+    );
+    let mut lose = url(
+        "Project documentation",
+        Some("2"),
+        "http://EXAMPLE.com/docs#intro",
+        Some(5),
+        Some("900"),
+        Some("100"),
+    );
+    lose.extra
+        .insert("meta_info".to_string(), json!({"power_bookmark_meta": "x"}));
+
+    let a = folder("A", Some("3"), None, Some("100"), vec![keep, lose]);
+    let input = mk_input(vec![("bookmark_bar", root(vec![a]))]);
+
+    let options = NormalizeOptions {
+        url_merge: "fold".parse().expect("policy"),
+        ..NormalizeOptions::default()
+    };
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (out, stats) =
+        normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, None)
+            .await
+            .expect("normalize_bookmarks should succeed");
+
+    assert_eq!(stats.urls_deduped, 1);
+    let a_out = find_folders_named(&out, "A");
+    let urls = find_urls_in_folder(a_out[0]);
+    assert_eq!(urls.len(), 1);
+
+    let w = urls[0];
+    assert_eq!(w.id.as_deref(), Some("1"), "winner identity is unchanged");
+    assert_eq!(w.url.as_deref(), Some("http://example.com/docs"));
+    assert_eq!(w.name.as_deref(), Some("Project documentation"));
+    assert_eq!(w.visit_count, Some(15));
+    assert_eq!(w.date_added.as_deref(), Some("100"));
+    assert_eq!(w.date_last_used.as_deref(), Some("900"));
+    assert_eq!(
+        w.extra.get("meta_info"),
+        Some(&json!({"last_visited_desktop": "1", "power_bookmark_meta": "x"}))
+    );
+}
+
+#[tokio::test]
+async fn folder_name_policy_picks_most_frequent_spelling() {
+    // "work" wins the merge (earliest added) but "Work" is the majority spelling.
+    let mk = |name: &str, id: &str, added: &str, link: &str| {
+        folder(
+            name,
+            Some(id),
+            None,
+            Some(added),
+            vec![url(link, None, link, None, None, None)],
+        )
+    };
+    let input = mk_input(vec![
+        (
+            "bookmark_bar",
+            root(vec![mk("work", "1", "100", "http://example.com/a")]),
+        ),
+        (
+            "other",
+            root(vec![mk("Work", "2", "200", "http://example.com/b")]),
+        ),
+        (
+            "synced",
+            root(vec![mk(" Work ", "3", "300", "http://example.com/c")]),
+        ),
+    ]);
+
+    let options = NormalizeOptions {
+        folder_name: FolderNamePolicy::new(FolderNameRule::MostFrequent),
+        ..NormalizeOptions::default()
+    };
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (tx, mut rx) = mpsc::channel::<AppEvent>(256);
+    let (out, stats) =
+        normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, Some(tx))
+            .await
+            .expect("normalize_bookmarks should succeed");
+
+    assert_eq!(stats.folders_merged, 2);
+    let work = find_folders_named(&out, "Work");
+    assert_eq!(work.len(), 1);
+    assert_eq!(work[0].id.as_deref(), Some("1"), "winner identity is kept");
+    assert_eq!(find_urls_in_folder(work[0]).len(), 3);
+
+    let mut named = None;
+    while let Some(ev) = rx.recv().await {
+        if let AppEvent::FolderMerged {
+            name, name_rule, ..
+        } = ev
+        {
+            named = Some((name, name_rule));
+        }
+    }
+    assert_eq!(
+        named,
+        Some(("Work".to_string(), "most-frequent".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn folder_merge_fold_unions_extra_and_reports_conflicts() {
+    let link = |u: &str| url(u, None, u, None, None, None);
+    let mut keep = folder(
+        "Docs",
+        Some("1"),
+        None,
+        Some("200"),
+        vec![link("http://example.com/a")],
+    );
+    keep.extra
+        .insert("meta_info".to_string(), json!({"color": "blue"}));
+    let mut lose = folder(
+        "Docs",
+        Some("2"),
+        None,
+        Some("100"),
+        vec![link("http://example.com/b")],
+    );
+    lose.extra.insert(
+        "meta_info".to_string(),
+        json!({"color": "red", "pinned": "1"}),
+    );
+    let stuff = folder("Stuff", Some("3"), None, Some("300"), vec![lose]);
+
+    let input = mk_input(vec![
+        ("bookmark_bar", root(vec![keep])),
+        ("other", root(vec![stuff])),
+    ]);
+    let options = NormalizeOptions {
+        folder_merge: "fold".parse().expect("policy"),
+        ..NormalizeOptions::default()
+    };
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (tx, mut rx) = mpsc::channel::<AppEvent>(256);
+    let (out, _stats) =
+        normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, Some(tx))
+            .await
+            .expect("normalize_bookmarks should succeed");
+
+    let docs = find_folders_named(&out, "Docs");
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].date_added.as_deref(), Some("100"));
+    assert_eq!(
+        docs[0].extra.get("meta_info"),
+        Some(&json!({"color": "blue", "pinned": "1"}))
+    );
+
+    let mut conflicts = Vec::new();
+    while let Some(ev) = rx.recv().await {
+        if let AppEvent::FolderAttributeConflict {
+            field,
+            kept,
+            dropped,
+            ..
+        } = ev
+        {
+            conflicts.push((field, kept, dropped));
+        }
+    }
+    assert_eq!(
+        conflicts,
+        vec![("meta_info.color".to_string(), json!("blue"), json!("red"))]
+    );
+}
+
+#[tokio::test]
+async fn reused_identity_cycle_is_cut_and_contents_move_to_shallowest_instance() {
+    // root -> A(g1) -> B(g2) -> A'(g1) -> x
+    let a2 = folder(
+        "A",
+        None,
+        Some("g1"),
+        Some("3"),
+        vec![url(
+            "x",
+            Some("10"),
+            "http://example.com/x",
+            None,
+            None,
+            None,
+        )],
+    );
+    let b = folder("B", None, Some("g2"), Some("2"), vec![a2]);
+    let a1 = folder("A", None, Some("g1"), Some("1"), vec![b]);
+    let input = mk_input(vec![("bookmark_bar", root(vec![a1]))]);
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (tx, mut rx) = mpsc::channel::<AppEvent>(256);
+    let (out, stats) = normalize_bookmarks(input, &canonicalizer, &scc, Some(tx))
+        .await
+        .expect("normalize_bookmarks should succeed");
+
+    assert_eq!(stats.identity_edges_cut, 1);
+    assert_eq!(
+        stats.folders_merged, 0,
+        "the cut repeat is not merged again"
+    );
+    let a = find_folders_named(&out, "A");
+    assert_eq!(a.len(), 1);
+    assert_eq!(find_urls_in_folder(a[0]).len(), 1);
+    assert!(find_folders_named(&out, "B").is_empty(), "B is left empty");
+
+    let mut cuts = Vec::new();
+    while let Some(ev) = rx.recv().await {
+        if let AppEvent::IdentityEdgeCut {
+            identity,
+            parent_path,
+            moved_to,
+            ..
+        } = ev
+        {
+            cuts.push((identity, parent_path, moved_to));
+        }
+    }
+    assert_eq!(cuts.len(), 1);
+    assert_eq!(cuts[0].0, "g1");
+    assert_eq!(cuts[0].1, "bookmark_bar / A / B", "edge from B is cut");
+    assert_eq!(
+        cuts[0].2, "bookmark_bar / A",
+        "contents move to the outer A"
+    );
+}
+
+#[tokio::test]
+async fn report_lists_cycle_members_and_witness_path() {
+    // root -> A(g1) -> B(g2) -> A'(g1)
+    let a2 = folder("A", None, Some("g1"), Some("3"), vec![]);
+    let b = folder("B", None, Some("g2"), Some("2"), vec![a2]);
+    let a1 = folder("A", None, Some("g1"), Some("1"), vec![b]);
+    let mut bar = root(vec![a1]);
+    bar.name = Some("Bookmarks bar".to_string());
+    let input = mk_input(vec![("bookmark_bar", bar)]);
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let (_out, report) = normalize_bookmarks_with_report(
+        input,
+        &canonicalizer,
+        &scc,
+        &NormalizeOptions::default(),
+        None,
+    )
+    .await
+    .expect("normalize_bookmarks should succeed");
+
+    assert_eq!(report.cycles.len(), 1);
+    let cycle = &report.cycles[0];
+    assert_eq!(cycle.witness, "A → B → A");
+
+    let ids: Vec<&str> = cycle.members.iter().map(|m| m.identity.as_str()).collect();
+    assert_eq!(ids, vec!["g1", "g2"]);
+    assert_eq!(
+        cycle.members[0].instances,
+        vec![
+            "/roots/bookmark_bar/children/0".to_string(),
+            "/roots/bookmark_bar/children/0/children/0/children/0".to_string()
+        ]
+    );
+
+    let last = cycle.witness_edges.last().expect("witness edge");
+    assert_eq!(
+        last.child.json_pointer,
+        "/roots/bookmark_bar/children/0/children/0/children/0"
+    );
+    assert_eq!(last.child.name_path, "Bookmarks bar / A / B / A");
+}
+
+#[tokio::test]
+async fn separate_duplicate_identities_get_fresh_guid_and_are_reported() {
+    let work = folder(
+        "Work",
+        Some("5"),
+        Some("g1"),
+        None,
+        vec![url("A", Some("7"), "https://a.example/", None, None, None)],
+    );
+    let travel = folder(
+        "Travel",
+        Some("6"),
+        Some("g1"),
+        None,
+        vec![url("B", Some("8"), "https://b.example/", None, None, None)],
+    );
+    let input = mk_input(vec![
+        ("bookmark_bar", root(vec![work])),
+        ("other", root(vec![travel])),
+    ]);
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let options = NormalizeOptions {
+        duplicate_identities: DuplicateIdentityMode::Separate,
+        ..NormalizeOptions::default()
+    };
+    let (out, report) =
+        normalize_bookmarks_with_report(input, &canonicalizer, &scc, &options, None)
+            .await
+            .expect("normalize_bookmarks should succeed");
+
+    assert_eq!(report.duplicate_identities.len(), 1);
+    let dup = &report.duplicate_identities[0];
+    assert_eq!((dup.field.as_str(), dup.value.as_str()), ("guid", "g1"));
+    assert!(!dup.identical);
+    let fresh = dup.instances[1].reassigned.clone().expect("fresh guid");
+
+    let travel = find_folders_named(&out, "Travel");
+    assert_eq!(travel[0].guid.as_deref(), Some(fresh.as_str()));
+    assert_eq!(
+        find_folders_named(&out, "Work")[0].guid.as_deref(),
+        Some("g1")
+    );
+}
+
+#[tokio::test]
+async fn same_named_root_containers_do_not_block_convergence() {
+    // Root containers are never merged, so they must not count as colliding folders.
+    let mut bar = root(vec![folder("Work", Some("3"), None, None, vec![])]);
+    bar.name = Some("Bookmarks".to_string());
+    let mut other = root(vec![folder("work", Some("4"), None, None, vec![])]);
+    other.name = Some("Bookmarks".to_string());
+    let input = mk_input(vec![("bookmark_bar", bar), ("other", other)]);
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let options = NormalizeOptions {
+        max_merge_iterations: 2,
+        ..NormalizeOptions::default()
+    };
+    let (out, _stats) =
+        normalize_bookmarks_with_options(input, &canonicalizer, &scc, &options, None)
+            .await
+            .expect("normalize_bookmarks should converge");
+
+    assert_eq!(find_folders_named(&out, "Bookmarks").len(), 2);
+}
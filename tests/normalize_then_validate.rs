@@ -20,3 +20,384 @@ async fn normalize_output_always_validates() {
 
     validate_bookmarks(&out, &canonicalizer).expect("normalize output must validate");
 }
+
+#[tokio::test]
+async fn streaming_load_normalizes_like_the_in_memory_path() {
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::read_options::ReadOptions;
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::serde_json_adapter::read_bookmarks_streaming;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::event::AppEvent;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::{
+        normalize_bookmarks_with_report, normalize_loaded_with_report, NormalizeOptions,
+    };
+    use tokio::sync::mpsc;
+
+    // Folder counts are a HashMap, so their debug order varies from run to run.
+    fn comparable(ev: AppEvent) -> String {
+        match ev {
+            AppEvent::FolderCounts { counts } => {
+                let sorted: std::collections::BTreeMap<_, _> = counts.into_iter().collect();
+                format!("FolderCounts {sorted:?}")
+            }
+            other => format!("{other:?}"),
+        }
+    }
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let options = NormalizeOptions::default();
+
+    for path in [
+        "tests/resources/BookmarksMini",
+        "tests/resources/BookmarksMiniNested",
+    ] {
+        let (tx, mut rx) = mpsc::channel::<AppEvent>(100_000);
+        let input = read_bookmarks_file(path).await.expect("read");
+        let (expected, expected_report) =
+            normalize_bookmarks_with_report(input, &canonicalizer, &scc, &options, Some(tx))
+                .await
+                .expect("normalize");
+        let mut expected_events = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            expected_events.push(comparable(ev));
+        }
+
+        let (tx, mut rx) = mpsc::channel::<AppEvent>(100_000);
+        let loaded = read_bookmarks_streaming(path, &ReadOptions::default()).expect("stream");
+        let (streamed, streamed_report) =
+            normalize_loaded_with_report(loaded, &canonicalizer, &scc, &options, Some(tx))
+                .await
+                .expect("normalize streamed");
+        let mut streamed_events = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            streamed_events.push(comparable(ev));
+        }
+
+        assert_eq!(
+            serde_json::to_value(&streamed).unwrap(),
+            serde_json::to_value(&expected).unwrap(),
+            "{path}"
+        );
+        // Everything but the final stats, whose peak memory differs between runs.
+        let last = expected_events.len() - 1;
+        assert_eq!(streamed_events[..last], expected_events[..last], "{path}");
+        assert_eq!(
+            streamed_report.stats.urls_deduped,
+            expected_report.stats.urls_deduped
+        );
+        assert_eq!(
+            streamed_report.stats.folders_merged,
+            expected_report.stats.folders_merged
+        );
+        assert!(streamed_report.stats.peak_memory_bytes.is_some());
+    }
+}
+
+#[tokio::test]
+async fn normalize_output_conserves_every_input_node() {
+    use microsoft_edge_bookmark_sorter_flattener::usecase::conservation::verify_conservation;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::identities::DuplicateIdentityMode;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::{
+        normalize_bookmarks_with_report, NormalizeOptions,
+    };
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    for path in [
+        "tests/resources/BookmarksMini",
+        "tests/resources/BookmarksMiniNested",
+    ] {
+        for mode in [DuplicateIdentityMode::Report, DuplicateIdentityMode::Merge] {
+            let input = read_bookmarks_file(path).await.expect("read input");
+            let options = NormalizeOptions {
+                duplicate_identities: mode,
+                record_provenance: true,
+                ..NormalizeOptions::default()
+            };
+            let (out, report) = normalize_bookmarks_with_report(
+                input.clone(),
+                &canonicalizer,
+                &scc,
+                &options,
+                None,
+            )
+            .await
+            .expect("normalize");
+            let provenance = report.provenance.expect("provenance recorded");
+
+            for evidence in [None, Some(&provenance)] {
+                let check = verify_conservation(
+                    &input,
+                    &out,
+                    evidence,
+                    &options.folder_name,
+                    &canonicalizer,
+                );
+                assert!(
+                    check.is_conserved(),
+                    "{path} ({mode}, provenance: {}): {:#?}",
+                    evidence.is_some(),
+                    check.violations
+                );
+                assert_eq!(check.urls_kept + check.urls_merged, check.urls_in, "{path}");
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn normalizing_the_output_again_changes_nothing() {
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::folder_name::FolderNamePolicy;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::idempotence::check_idempotent;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::identities::DuplicateIdentityMode;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::{
+        normalize_bookmarks_with_options, NormalizeOptions,
+    };
+
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+    let policies = [
+        NormalizeOptions::default(),
+        NormalizeOptions {
+            folder_name: FolderNamePolicy::new("most-frequent".parse().unwrap()),
+            folder_merge: "fold".parse().unwrap(),
+            url_merge: "fold".parse().unwrap(),
+            ..NormalizeOptions::default()
+        },
+        NormalizeOptions {
+            folder_winner: "most-children,outermost".parse().unwrap(),
+            folder_name: FolderNamePolicy::new("title-case".parse().unwrap())
+                .with_aliases([("work", "WORK")]),
+            url_winner: "prefer-https,most-visited".parse().unwrap(),
+            duplicate_identities: DuplicateIdentityMode::Merge,
+            ..NormalizeOptions::default()
+        },
+        NormalizeOptions {
+            duplicate_identities: DuplicateIdentityMode::Separate,
+            ..NormalizeOptions::default()
+        },
+    ];
+    for path in [
+        "tests/resources/BookmarksMini",
+        "tests/resources/BookmarksMiniNested",
+    ] {
+        for options in policies.iter() {
+            let input = read_bookmarks_file(path).await.expect("read input");
+            let (out, _) =
+                normalize_bookmarks_with_options(input, &canonicalizer, &scc, options, None)
+                    .await
+                    .expect("normalize");
+            check_idempotent(&out, &canonicalizer, &scc, options)
+                .await
+                .unwrap_or_else(|e| panic!("{path} with {options:?}: {e}"));
+        }
+    }
+}
+
+#[tokio::test]
+async fn lenient_reading_leaves_nodes_that_fail_their_schema_as_read() {
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::read_options::{
+        ReadOptions, SchemaMode,
+    };
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::schema_validator::failing_nodes;
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::serde_json_adapter::{
+        read_bookmarks_checked, read_bookmarks_streaming,
+    };
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::{
+        normalize_bookmarks_with_report, normalize_loaded_with_report, NormalizeOptions,
+    };
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("Bookmarks");
+    std::fs::write(
+        &path,
+        r#"{"version": 1, "roots": {"bookmark_bar": {"type": "folder", "name": "Bar", "children": [
+            {"type": "folder", "name": "Tools", "date_added": "yesterday", "children": [
+                {"type": "url", "name": "a", "url": "https://a.example/"},
+                {"type": "url", "name": "a", "url": "https://a.example/"}
+            ]},
+            {"type": "folder", "name": "tools", "children": [
+                {"type": "url", "name": "b", "url": "https://b.example/"}
+            ]},
+            {"type": "url", "name": "bm", "url": "javascript:void(0)", "visit_count": -1},
+            {"type": "url", "name": "bm", "url": "javascript:void(0)", "visit_count": -1},
+            {"type": "folder", "name": "Empty", "date_modified": "never", "children": []}
+        ]}}}"#,
+    )
+    .expect("write input");
+    let path = path.to_str().unwrap();
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+
+    let strict = ReadOptions::default();
+    assert!(read_bookmarks_checked(path, &strict).await.is_err());
+    let off = ReadOptions {
+        schema: SchemaMode::Off,
+        ..ReadOptions::default()
+    };
+    let (_, violations) = read_bookmarks_checked(path, &off).await.expect("off");
+    assert!(violations.is_empty());
+
+    let lenient = ReadOptions {
+        schema: SchemaMode::Lenient,
+        ..ReadOptions::default()
+    };
+    let (dto, violations) = read_bookmarks_checked(path, &lenient)
+        .await
+        .expect("lenient");
+    let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+    assert_eq!(
+        pointers,
+        vec![
+            "/roots/bookmark_bar/children/0/date_added",
+            "/roots/bookmark_bar/children/2/visit_count",
+            "/roots/bookmark_bar/children/3/visit_count",
+            "/roots/bookmark_bar/children/4/date_modified",
+        ]
+    );
+    let loaded = read_bookmarks_streaming(path, &lenient).expect("streaming");
+    assert_eq!(loaded.schema_violations(), violations.as_slice());
+
+    let options = NormalizeOptions {
+        preserve: failing_nodes(&violations),
+        ..NormalizeOptions::default()
+    };
+    let (out, report) = normalize_bookmarks_with_report(dto, &canonicalizer, &scc, &options, None)
+        .await
+        .expect("normalize");
+    let (streamed, _) = normalize_loaded_with_report(loaded, &canonicalizer, &scc, &options, None)
+        .await
+        .expect("normalize streamed");
+    assert_eq!(
+        serde_json::to_value(&out).unwrap(),
+        serde_json::to_value(&streamed).unwrap()
+    );
+    assert_eq!(report.stats.nodes_preserved, 4);
+
+    // Preserved nodes are neither merged, deduplicated nor pruned; their children are
+    // normalized as usual.
+    let bar = &out.roots["bookmark_bar"].children;
+    let summary: Vec<(Option<&str>, usize)> = bar
+        .iter()
+        .map(|n| (n.name.as_deref(), n.children.len()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some("Empty"), 0),
+            (Some("Tools"), 1),
+            (Some("tools"), 1),
+            (Some("bm"), 0),
+            (Some("bm"), 0),
+        ]
+    );
+    assert_eq!(bar[1].date_added.as_deref(), Some("yesterday"));
+    assert_eq!(bar[3].visit_count, Some(-1));
+}
+
+#[tokio::test]
+async fn lenient_reading_keeps_nodes_that_do_not_fit_the_model_as_read() {
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::read_options::{
+        ReadOptions, SchemaMode,
+    };
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::schema_validator::failing_nodes;
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::serde_json_adapter::{
+        read_bookmarks_checked, read_bookmarks_streaming,
+    };
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::idempotence::check_idempotent;
+    use microsoft_edge_bookmark_sorter_flattener::usecase::normalize::{
+        normalize_bookmarks_with_report, normalize_loaded_with_report, NormalizeOptions,
+    };
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("Bookmarks");
+    std::fs::write(
+        &path,
+        r#"{"version": 1, "roots": {"bookmark_bar": {"type": "folder", "name": "Bar", "children": [
+            {"name": "untyped", "url": "https://u.example/"},
+            {"type": "url", "name": 7, "url": "https://n.example/", "visit_count": "many"},
+            {"type": "folder", "name": "Dev", "show_icon": "yes", "children": [
+                {"type": "url", "name": "a", "url": "https://a.example/"},
+                {"type": "url", "name": "a", "url": "https://a.example/"}
+            ]},
+            {"type": null, "name": "null", "url": "https://z.example/"}
+        ]}, "other": {"name": "Other", "children": []}}}"#,
+    )
+    .expect("write input");
+    let path = path.to_str().unwrap();
+    let canonicalizer = DefaultUrlCanonicalizer;
+    let scc = KosarajuSccDetector;
+
+    let off = ReadOptions {
+        schema: SchemaMode::Off,
+        ..ReadOptions::default()
+    };
+    assert!(read_bookmarks_checked(path, &off).await.is_err());
+
+    let lenient = ReadOptions {
+        schema: SchemaMode::Lenient,
+        ..ReadOptions::default()
+    };
+    let (dto, violations) = read_bookmarks_checked(path, &lenient)
+        .await
+        .expect("lenient");
+    let misfits: Vec<&str> = violations
+        .iter()
+        .filter(|v| v.message.ends_with("kept as read"))
+        .map(|v| v.message.as_str())
+        .collect();
+    assert_eq!(
+        misfits,
+        vec![
+            "does not fit the bookmarks model (type); kept as read",
+            "does not fit the bookmarks model (name, visit_count); kept as read",
+            "does not fit the bookmarks model (show_icon); kept as read",
+            "does not fit the bookmarks model (type); kept as read",
+            "does not fit the bookmarks model (type); kept as read",
+        ]
+    );
+    assert!(violations.iter().all(|v| v.position.is_some()));
+    let loaded = read_bookmarks_streaming(path, &lenient).expect("streaming");
+    assert_eq!(loaded.schema_violations(), violations.as_slice());
+
+    let options = NormalizeOptions {
+        preserve: failing_nodes(&violations),
+        ..NormalizeOptions::default()
+    };
+    let (out, report) = normalize_bookmarks_with_report(dto, &canonicalizer, &scc, &options, None)
+        .await
+        .expect("normalize");
+    let (streamed, _) = normalize_loaded_with_report(loaded, &canonicalizer, &scc, &options, None)
+        .await
+        .expect("normalize streamed");
+    let written = serde_json::to_value(&out).unwrap();
+    assert_eq!(written, serde_json::to_value(&streamed).unwrap());
+    assert_eq!(report.stats.nodes_preserved, 5);
+
+    // Misfit fields are written back exactly as read; the children of a kept folder are
+    // still deduplicated.
+    let bar = written["roots"]["bookmark_bar"]["children"]
+        .as_array()
+        .expect("children");
+    assert!(bar
+        .iter()
+        .any(|n| n.get("type").is_none() && n["name"] == serde_json::json!("untyped")));
+    assert!(bar.iter().any(
+        |n| n["name"] == serde_json::json!(7) && n["visit_count"] == serde_json::json!("many")
+    ));
+    let dev = bar
+        .iter()
+        .find(|n| n["name"] == serde_json::json!("Dev"))
+        .expect("Dev");
+    assert_eq!(dev["show_icon"], serde_json::json!("yes"));
+    assert_eq!(dev["children"].as_array().map(Vec::len), Some(1));
+    let null = bar
+        .iter()
+        .find(|n| n["name"] == serde_json::json!("null"))
+        .expect("null");
+    assert_eq!(null.get("type"), Some(&serde_json::Value::Null));
+    assert!(written["roots"]["other"].get("type").is_none());
+
+    check_idempotent(&out, &canonicalizer, &scc, &options)
+        .await
+        .expect("idempotent");
+}
@@ -0,0 +1,108 @@
+use microsoft_edge_bookmark_sorter_flattener::domain::graph::Graph;
+use microsoft_edge_bookmark_sorter_flattener::domain::traits::SccDetector;
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::scc_kosaraju::KosarajuSccDetector;
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::scc_path_based::PathBasedSccDetector;
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::scc_selection::{
+    SccAlgorithm, SccSelection,
+};
+use microsoft_edge_bookmark_sorter_flattener::infrastructure::scc_tarjan::TarjanSccDetector;
+
+/// Deterministic pseudo-random graph (LCG) with self-loops and back edges.
+fn random_graph(n: usize, edges: usize, seed: u64) -> Graph {
+    let mut g = Graph::new(n);
+    let mut state = seed;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    for _ in 0..edges {
+        let (u, v) = (next() % n, next() % n);
+        g.edges[u].push(v);
+    }
+    for outs in g.edges.iter_mut() {
+        outs.sort_unstable();
+        outs.dedup();
+    }
+    g
+}
+
+#[test]
+fn tarjan_and_path_based_detect_self_loop_and_two_node_cycle() {
+    let mut g = Graph::new(3);
+    g.edges[0].push(0);
+    g.edges[1].push(2);
+    g.edges[2].push(1);
+
+    for det in [
+        &TarjanSccDetector as &dyn SccDetector,
+        &PathBasedSccDetector,
+    ] {
+        let scc = det.compute_scc(&g);
+        assert_eq!(scc.components, vec![vec![0], vec![1, 2]]);
+        assert_eq!(scc.cyclic_component, vec![true, true]);
+    }
+}
+
+#[test]
+fn tarjan_and_path_based_split_dag_into_singletons() {
+    // 0 -> 1 -> 2, 0 -> 2
+    let mut g = Graph::new(3);
+    g.edges[0].extend([1, 2]);
+    g.edges[1].push(2);
+
+    for det in [
+        &TarjanSccDetector as &dyn SccDetector,
+        &PathBasedSccDetector,
+    ] {
+        let scc = det.compute_scc(&g);
+        assert_eq!(scc.components, vec![vec![0], vec![1], vec![2]]);
+        assert!(scc.cyclic_component.iter().all(|&b| !b));
+    }
+}
+
+#[test]
+fn all_detectors_agree_on_random_graphs() {
+    for seed in 0..40 {
+        let g = random_graph(60, 20 + seed as usize * 3, seed);
+        let k = KosarajuSccDetector.compute_scc(&g);
+        let t = TarjanSccDetector.compute_scc(&g);
+        let p = PathBasedSccDetector.compute_scc(&g);
+
+        assert_eq!(k.first_partition_difference(&t), None, "seed {seed}");
+        assert_eq!(k.first_partition_difference(&p), None, "seed {seed}");
+        // Tarjan and path-based share the normalized numbering.
+        assert_eq!(t.components, p.components, "seed {seed}");
+        assert_eq!(t.cyclic_component, p.cyclic_component, "seed {seed}");
+    }
+}
+
+#[test]
+fn detectors_handle_deep_chains_without_recursion() {
+    let n = 200_000;
+    let mut g = Graph::new(n);
+    for v in 0..n - 1 {
+        g.edges[v].push(v + 1);
+    }
+    g.edges[n - 1].push(0);
+
+    for algo in [SccAlgorithm::Tarjan, SccAlgorithm::PathBased] {
+        let scc = algo.compute_scc(&g);
+        assert_eq!(scc.components.len(), 1);
+    }
+}
+
+#[test]
+fn cross_check_selection_returns_primary_result() {
+    let g = random_graph(30, 60, 7);
+    let sel = SccSelection {
+        algorithm: SccAlgorithm::PathBased,
+        cross_check: Some(SccAlgorithm::Kosaraju),
+    };
+    let res = sel.try_compute_scc(&g).expect("detectors agree");
+    assert_eq!(
+        res.components,
+        PathBasedSccDetector.compute_scc(&g).components
+    );
+}
@@ -65,3 +65,27 @@ fn validate_invalid_folder_item_integration() {
 
     assert!(validate_folder_item(&invalid_folder).is_err());
 }
+
+#[test]
+fn deeply_nested_input_validates_without_recursion() {
+    use microsoft_edge_bookmark_sorter_flattener::infrastructure::schema_validator::validate_all_bookmark_items;
+
+    // Built by moving each level into the next: `json!` would re-serialize the nested
+    // value recursively.
+    let mut node = json!({"type": "url", "name": "leaf", "url": "https://example.com/"});
+    for i in 0..2_000 {
+        let mut folder = json!({"type": "folder", "name": format!("f{i}")});
+        folder["children"] = serde_json::Value::Array(vec![node]);
+        node = folder;
+    }
+    let mut doc = json!({"roots": {}});
+    doc["roots"]["bookmark_bar"] = node;
+
+    validate_all_bookmark_items(&doc).expect("valid at any depth");
+
+    // Dropping the nested value would recurse just as deep; unwind it level by level.
+    let mut next = doc["roots"]["bookmark_bar"].take();
+    while let Some(child) = next.get_mut("children").and_then(|c| c.get_mut(0)) {
+        next = child.take();
+    }
+}